@@ -15,11 +15,227 @@ export interface ShapedGlyph {
  * Column definition for Table
  * Color structure (RGB/RGBA)
  */
+/**
+ * Color: RGB (`r`, `g`, `b`), `gray`, CMYK (`c`, `m`, `y`, `k`), a `spot` ink at `tint`
+ * with a CMYK `alternate`, or `components` in an `icc` profile registered with the document
+ */
 export interface Color {
-  r: number
-  g: number
-  b: number
+  r?: number
+  g?: number
+  b?: number
   a?: number
+  gray?: number
+  c?: number
+  m?: number
+  y?: number
+  k?: number
+  spot?: string
+  tint?: number
+  alternate?: Array<number>
+  icc?: string
+  components?: Array<number>
+}
+/** Inline span of a rich text paragraph */
+export interface TextSpan {
+  text: string
+  fontFamily?: string
+  weight?: number
+  italic?: boolean
+  size?: number
+  color?: Color
+  underline?: boolean
+  decoration?: TextDecoration
+  link?: string
+}
+/**
+ * Tab position in points from the start of the line.
+ * `align` is "left" (default), "right", "center" or "decimal";
+ * `leader` is "dots", "dashes" or "underline".
+ */
+export interface TabStop {
+  position: number
+  align?: string
+  leader?: string
+}
+/** Settings for `LayoutNode.text` */
+export interface TextOptions {
+  color?: Color
+  backgroundColor?: Color
+  /** "ltr", "rtl" or "auto" (default) */
+  direction?: string
+  /** "horizontal-tb" (default) or "vertical-rl" */
+  writingMode?: string
+  /** "normal" (default), "pre", "pre-wrap" or "nowrap" */
+  whiteSpace?: string
+  /** Language (e.g. "de") whose patterns hyphenate the text */
+  lang?: string
+  /** "left", "center", "right" or "justify" */
+  align?: string
+  /** Fixed box width */
+  width?: number
+  /** Multiple of the size (1.5) or points ("18pt") */
+  lineHeight?: number | string
+  /** Points */
+  letterSpacing?: number
+  wordSpacing?: number
+  /** Fewest lines left on either side of a page break (default 2) */
+  orphans?: number
+  widows?: number
+  /** Lines after this many are dropped */
+  maxLines?: number
+  /** "visible" (default), "clip" or "ellipsis" */
+  overflow?: string
+  /** Size the text may shrink down to so it fits its box */
+  minSize?: number
+  /** "greedy" (default), or "optimal" to break whole paragraphs at once for even spacing */
+  lineBreaker?: string
+  /** Underline, overline, strikethrough or a highlight on every line */
+  decoration?: TextDecoration
+  /** Positions tabs advance to, which may fill the gap with leaders */
+  tabStops?: Array<TabStop>
+}
+/** Settings for `LayoutNode.richText`; spans override the size and color */
+export interface RichTextOptions {
+  color?: Color
+  /** "left", "center", "right" or "justify" */
+  align?: string
+  /** "normal" (default), "pre", "pre-wrap" or "nowrap" */
+  whiteSpace?: string
+  /** Language (e.g. "de") whose patterns hyphenate the text */
+  lang?: string
+  /** Multiple of each span's size (1.5) or points ("18pt") */
+  lineHeight?: number | string
+  /** Points */
+  letterSpacing?: number
+  wordSpacing?: number
+  /** Fewest lines left on either side of a page break (default 2) */
+  orphans?: number
+  widows?: number
+}
+/** Settings for `LayoutNode.list` */
+export interface ListOptions {
+  /** "bullet" (default), a sample number like "1.", "a)" or "i.", or any bullet text */
+  marker?: string
+  /** Number of the first item (default 1) */
+  start?: number
+  /** Nesting depth, which picks the default bullet */
+  level?: number
+  /** Distance to the item content (default 18) */
+  indent?: number
+  /** Space between a marker and its item (default 6) */
+  markerGap?: number
+  /** Space between items */
+  spacing?: number
+  markerSize?: number
+  markerColor?: Color
+}
+/** Paint settings for `Page.drawPath`. Without `fill` or `stroke` the path is stroked in black. */
+export interface PathStyle {
+  fill?: Color
+  stroke?: Color
+  strokeWidth?: number
+  /** "nonzero" (default) or "evenodd" */
+  fillRule?: string
+  /** Alternating dash and gap lengths */
+  dash?: Array<number>
+  dashPhase?: number
+  /** "butt", "round" or "square" */
+  lineCap?: string
+  /** "miter", "round" or "bevel" */
+  lineJoin?: string
+  miterLimit?: number
+}
+/** Color at `offset` (0 to 1) along a gradient */
+export interface ColorStop {
+  offset: number
+  color: Color
+}
+/** Linear or radial gradient fill */
+export interface Gradient {
+  /** "linear" (default) or "radial" */
+  kind?: string
+  /** Linear direction in degrees, CSS style: 0 to top, 90 to right, 180 (default) to bottom */
+  angle?: number
+  /** Radial center as fractions of the box from its top-left corner (default 0.5) */
+  centerX?: number
+  centerY?: number
+  /** Radial extent in points, the farthest corner by default */
+  radius?: number
+  stops: Array<ColorStop>
+}
+/** Page transformation: scale, then skew, then rotate around the origin, then translate */
+export interface Transform {
+  /** Degrees, counterclockwise */
+  rotate?: number
+  scaleX?: number
+  scaleY?: number
+  /** Degrees */
+  skewX?: number
+  skewY?: number
+  translateX?: number
+  translateY?: number
+  /** Point that stays in place (default 0, 0) */
+  originX?: number
+  originY?: number
+}
+/** Optional content group registered with `Document.addLayer` */
+export interface Layer {
+  name: string
+  /** Shown when the document is opened (default true) */
+  visible?: boolean
+  /** Printed regardless of the on-screen state, e.g. false for screen-only notes */
+  print?: boolean
+  /** Shown on screen regardless of `visible` */
+  view?: boolean
+  /** Layers sharing a group name are exclusive: turning one on turns the others off */
+  group?: string
+}
+/**
+ * Text, image or full-page color drawn on every page added after `Document.addWatermark`.
+ * One of `text`, `image` or `color` (alone, a page background) is required.
+ */
+export interface Watermark {
+  text?: string
+  /** Image index from `Document.addImage` */
+  image?: number
+  /** Text color (gray by default), or the page color without text or image */
+  color?: Color
+  /** Text size (default 72) */
+  size?: number
+  /** Font for the text, the first registered font by default */
+  fontIndex?: number
+  /**
+   * Image size; a missing side keeps the image's aspect ratio, and without either
+   * the image is drawn at its natural size
+   */
+  width?: number
+  height?: number
+  /** 0 to 1 (default 1) */
+  opacity?: number
+  /** Degrees, counterclockwise */
+  rotation?: number
+  /**
+   * "center" (default), "top", "bottom", "left", "right", "top-left", "top-right",
+   * "bottom-left", "bottom-right" or "fill"
+   */
+  position?: string
+  /** "behind" (default) or "front" */
+  layer?: string
+  /** Distance from the page edges (default 36) */
+  margin?: number
+  /** Page numbers, e.g. "1", "2-", "1-3,7", "odd" or "even"; all pages by default */
+  pages?: string
+}
+/** Lines over, under or through text and a highlight behind each line */
+export interface TextDecoration {
+  underline?: boolean
+  overline?: boolean
+  lineThrough?: boolean
+  highlight?: Color
+  /** Color of the lines, defaults to the text color */
+  color?: Color
+  /** Leave gaps in the underline around descenders */
+  skipInk?: boolean
 }
 /** Template for repeating headers and footers */
 export interface FlowOptions {
@@ -56,18 +272,36 @@ export declare class Font {
   /** Shape text and return glyph IDs with positions */
   shapeText(text: string, size: number): Array<ShapedGlyph>
 }
+/** Fonts rich text spans can pick from by family and weight */
+export declare class FontSet {
+  constructor()
+  /**
+   * Register a font added to the document at `font_index`.
+   * Its family and weight are read from the font file.
+   */
+  add(font: Font, fontIndex: number): void
+}
 /** Represents a loaded image (JPEG or PNG) */
 export declare class Image {
   /** Load an image from a file path */
   static fromFile(path: string): Image
   /** Load an image from bytes */
   static fromBytes(data: Array<number>): Image
+  /** Load an SVG as vector graphics; `<text>` is drawn with the given fonts */
+  static fromSvg(data: Array<number>, fonts: Array<Font>): Image
 }
 /** Data Table with headers and rows */
 export declare class Table {
   constructor(columns: Array<TableColumn>)
   addRow(row: Array<string>): void
   setFontSize(size: number): void
+  /** Language of the cell text (e.g. "de"); enables hyphenation in narrow columns */
+  setLang(lang?: string | undefined | null): void
+  /**
+   * Fit cell text into its cell: keep at most `max_lines` lines, handle the rest with
+   * `overflow` ("visible", "clip" or "ellipsis"), and shrink the font down to `min_font_size`
+   */
+  setOverflow(maxLines?: number | undefined | null, overflow?: string | undefined | null, minFontSize?: number | undefined | null): void
 }
 /** Template loaded from JSON */
 export declare class Template {
@@ -85,10 +319,41 @@ export declare class Template {
 export declare class LayoutNode {
   /** Create a Column node */
   static column(children: Array<LayoutNode>, spacing?: number | undefined | null): LayoutNode
-  static row(children: Array<LayoutNode>, spacing?: number | undefined | null): LayoutNode
-  static text(text: string, size: number, color?: Color | undefined | null, backgroundColor?: Color | undefined | null): LayoutNode
-  static container(child: LayoutNode, padding?: number | undefined | null, border?: number | undefined | null): LayoutNode
-  static image(imageIndex: number, width: number, height: number): LayoutNode
+  /** Create a Row node. `direction` "rtl" mirrors the child order. */
+  static row(children: Array<LayoutNode>, spacing?: number | undefined | null, direction?: string | undefined | null): LayoutNode
+  /**
+   * Create a Text node; `options` sets its color, direction, wrapping, alignment,
+   * spacing, page break and overflow rules, decoration and tab stops.
+   */
+  static text(text: string, size: number, options?: TextOptions | undefined | null): LayoutNode
+  /**
+   * Create a RichText node from styled spans.
+   * Spans with a `font_family`, `weight` or `italic` pick a font from `fonts`.
+   */
+  static richText(spans: Array<TextSpan>, size: number, fonts?: FontSet | undefined | null, options?: RichTextOptions | undefined | null): LayoutNode
+  /**
+   * Apply page break rules to a node: `together` moves it to the next page whole
+   * instead of splitting it, `with_next` keeps it on the same page as the next node.
+   */
+  static keep(child: LayoutNode, together?: boolean | undefined | null, withNext?: boolean | undefined | null): LayoutNode
+  /**
+   * Create a List node with one item per node. Nested lists are items holding
+   * another list with a higher `level` in its `options`.
+   */
+  static list(items: Array<LayoutNode>, options?: ListOptions | undefined | null): LayoutNode
+  static container(child: LayoutNode, padding?: number | undefined | null, margin?: number | undefined | null, border?: number | undefined | null, backgroundGradient?: Gradient | undefined | null, clip?: boolean | undefined | null): LayoutNode
+  /** Image, optionally masked to a "circle" or a "rounded" rectangle with corner `mask_radius` */
+  static image(imageIndex: number, width: number, height: number, mask?: string | undefined | null, maskRadius?: number | undefined | null): LayoutNode
+  /**
+   * Child rotated (degrees, counterclockwise), scaled and skewed; the transformed
+   * bounding box takes up the space and sits at the top-left of the area, so
+   * unlike `Transform` on a page there is no origin to set
+   */
+  static transform(child: LayoutNode, rotate?: number | undefined | null, scaleX?: number | undefined | null, scaleY?: number | undefined | null, skewX?: number | undefined | null, skewY?: number | undefined | null): LayoutNode
+  /** Child placed on a layer from `Document.addLayer` */
+  static layer(child: LayoutNode, layerIndex: number): LayoutNode
+  /** Image cut to a path given relative to the image's bottom-left corner */
+  static maskedImage(imageIndex: number, width: number, height: number, mask: Path): LayoutNode
   static table(table: Table): LayoutNode
   static pageNumber(format: string, size: number, align?: string | undefined | null): LayoutNode
 }
@@ -106,15 +371,63 @@ export declare class Page {
   drawLine(x1: number, y1: number, x2: number, y2: number, width: number): this
   /** Draw a rectangle (stroke) */
   drawRect(x: number, y: number, w: number, h: number, width: number): this
+  /** Save the graphics state; opacity and blend mode changes end at the matching `restoreState` */
+  saveState(): this
+  restoreState(): this
+  /** Opacity (0-1) of everything drawn next; color alphas multiply with it */
+  setOpacity(fill: number, stroke?: number | undefined | null): this
+  /**
+   * Blend mode of everything drawn next: "normal", "multiply", "screen", "overlay",
+   * "darken", "lighten", "color-dodge", "color-burn", "hard-light", "soft-light",
+   * "difference", "exclusion", "hue", "saturation", "color" or "luminosity"
+   */
+  setBlendMode(mode: string): this
+  /** Draw a vector path, stroked and/or filled */
+  drawPath(path: Path, style?: PathStyle | undefined | null): this
+  /** Transform everything drawn until the matching `popTransform` */
+  pushTransform(transform: Transform): this
+  popTransform(): this
+  /** Clip everything drawn until the matching `popClip` to a path */
+  pushClip(path: Path, fillRule?: string | undefined | null): this
+  popClip(): this
+  /** Fill a path with a gradient spanning the path's bounds */
+  fillGradient(path: Path, gradient: Gradient, fillRule?: string | undefined | null): this
+  /** Fill a rectangle, rounded when `radius` is given, with a gradient */
+  drawRectGradient(x: number, y: number, w: number, h: number, gradient: Gradient, radius?: number | undefined | null): this
   /** Draw a filled rectangle (gray) */
   drawFillRect(x: number, y: number, w: number, h: number, gray: number): this
   /** Draw a table */
   drawTable(table: Table, x: number, y: number, font: Font, fontIndex: number): number
   /** Draw an image */
   drawImage(imageIndex: number, x: number, y: number, width: number, height: number): this
+  /** Draw a form registered with `Document.addForm` at its own size, bottom-left corner at (x, y) */
+  drawForm(formIndex: number, x: number, y: number): this
+  /** Put everything drawn until the matching `endLayer` on a layer from `Document.addLayer` */
+  beginLayer(layerIndex: number): this
+  endLayer(): this
   /** Render a declarative layout tree */
   renderLayout(node: LayoutNode, x: number, y: number, width: number, font: Font, fontIndex: number, currentPage?: number | undefined | null, totalPages?: number | undefined | null): void
 }
+/** Vector path builder for `Page.drawPath`. Coordinates are PDF points, y up. */
+export declare class Path {
+  constructor()
+  /** Start a new subpath */
+  moveTo(x: number, y: number): this
+  lineTo(x: number, y: number): this
+  /** Cubic Bézier curve with two control points */
+  cubicTo(x1: number, y1: number, x2: number, y2: number, x: number, y: number): this
+  /** Quadratic Bézier curve with one control point */
+  quadTo(cx: number, cy: number, x: number, y: number): this
+  /** Circular arc; angles in degrees counterclockwise from the x axis */
+  arc(cx: number, cy: number, radius: number, startAngle: number, endAngle: number): this
+  circle(cx: number, cy: number, radius: number): this
+  ellipse(cx: number, cy: number, rx: number, ry: number): this
+  /** Rectangle from its bottom-left corner, optionally with rounded corners */
+  rect(x: number, y: number, width: number, height: number, radius?: number | undefined | null): this
+  /** Closed polygon through flat `[x0, y0, x1, y1, ...]` coordinates */
+  polygon(points: Array<number>): this
+  close(): this
+}
 /** Represents a PDF document with multiple pages */
 export declare class Document {
   /** Create a new empty document in buffered mode */
@@ -134,6 +447,25 @@ export declare class Document {
    * Returns the image index to use in page rendering
    */
   addImage(image: Image): number
+  /**
+   * Register everything drawn on `page` as a form of the page's size, drawn by reference
+   * Returns the form index to use with `Page.drawForm`
+   */
+  addForm(page: Page): number
+  /**
+   * Register a layout tree, measured at up to `width`, as a form of its natural size
+   * Returns the form index to use with `Page.drawForm`
+   */
+  addLayoutForm(node: LayoutNode, width: number, font: Font, fontIndex: number): number
+  /**
+   * Register a layer (optional content group)
+   * Returns the layer index to use with `Page.beginLayer` and `LayoutNode.layer`
+   */
+  addLayer(layer: Layer): number
+  /** Draw a watermark or background on every page added from now on that it applies to */
+  addWatermark(watermark: Watermark): void
+  /** Register an ICC profile for colors given as `{ icc: name, components }` */
+  addIccProfile(name: string, data: Array<number>): void
   /** Add a page to the document */
   addPage(page: Page): void
   /** Finalize a streaming document */
@@ -142,7 +474,7 @@ export declare class Document {
   writeTo(path: string): void
   /**
    * Register assets from a loaded Template into this Document
-   * This is required if the template contains images.
+   * This is required if the template contains images or fonts used by rich text.
    */
   registerTemplateAssets(template: Template): void
   /** Automatically paginate a layout tree across multiple pages */
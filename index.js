@@ -310,9 +310,10 @@ if (!nativeBinding) {
   throw new Error(`Failed to load native binding`)
 }
 
-const { Font, Image, Table, Template, LayoutNode, Page, Document } = nativeBinding
+const { Font, FontSet, Image, Table, Template, LayoutNode, Page, Document } = nativeBinding
 
 module.exports.Font = Font
+module.exports.FontSet = FontSet
 module.exports.Image = Image
 module.exports.Table = Table
 module.exports.Template = Template
@@ -1,44 +1,172 @@
-/// Color representation for PDF rendering (RGB/RGBA)
+/// Color representation for PDF rendering: device RGB, gray and CMYK,
+/// spot (Separation) colors and ICC-based colors
 use serde::{Deserialize, Serialize};
+use crate::core::writer::PdfObject;
+use std::sync::Arc;
 
-#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(from = "ColorRepr", into = "ColorRepr")]
 pub struct Color {
-    pub r: f64,  // 0.0 to 1.0
+    /// RGB equivalent (0.0 to 1.0), used where only RGB can be drawn (color emoji).
+    /// For gray and CMYK colors it is a plain conversion, not a color-managed one.
+    pub r: f64,
     pub g: f64,
     pub b: f64,
-    #[serde(default = "default_alpha")]
     pub a: f64,  // alpha (1.0 = opaque)
+    /// The space the color is written in
+    pub space: ColorSpace,
 }
 
 fn default_alpha() -> f64 { 1.0 }
 
+/// Color space of a `Color`, holding the components written for it
+#[derive(Debug, Clone, PartialEq, Default)]
+pub enum ColorSpace {
+    /// DeviceRGB, from `r`, `g` and `b`
+    #[default]
+    Rgb,
+    /// DeviceGray, from `r`
+    Gray,
+    /// DeviceCMYK
+    Cmyk { c: f64, m: f64, y: f64, k: f64 },
+    /// A named ink at `tint` (0 to 1); viewers without the ink show the CMYK `alternate` scaled by the tint
+    Separation { name: ColorName, tint: f64, alternate: [f64; 4] },
+    /// Components in the ICC profile registered with the document under `profile`.
+    /// `count` is 1 (gray), 3 (RGB) or 4 (CMYK).
+    IccBased { profile: ColorName, components: [f64; 4], count: u8 },
+}
+
+/// A spot color or profile name. Clones share the string, so colors stay cheap to copy around.
+#[derive(Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ColorName(Arc<str>);
+
+impl ColorName {
+    pub fn new(name: &str) -> Self {
+        ColorName(Arc::from(name))
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+
+    /// The name as PDF name characters, with delimiters and non-ASCII bytes escaped as #xx
+    fn to_pdf_name(&self) -> String {
+        self.as_str().bytes()
+            .map(|b| match b {
+                b'!'..=b'~' if !b"#()<>[]{}/%".contains(&b) => (b as char).to_string(),
+                _ => format!("#{:02X}", b),
+            })
+            .collect()
+    }
+
+    /// 64-bit FNV-1a hash of the name, as hex digits for short resource names that agree
+    /// across pages and never need escaping
+    fn to_hash(&self) -> String {
+        let hash = self.as_str().bytes().fold(0xcbf29ce484222325u64, |hash, b| (hash ^ b as u64).wrapping_mul(0x100000001b3));
+        format!("{:016X}", hash)
+    }
+}
+
+impl std::fmt::Debug for ColorName {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        self.as_str().fmt(f)
+    }
+}
+
+fn cmyk_to_rgb(c: f64, m: f64, y: f64, k: f64) -> (f64, f64, f64) {
+    ((1.0 - c) * (1.0 - k), (1.0 - m) * (1.0 - k), (1.0 - y) * (1.0 - k))
+}
+
 impl Color {
     /// Create RGB color (opaque)
     pub fn rgb(r: f64, g: f64, b: f64) -> Self {
-        Color { r, g, b, a: 1.0 }
+        Color { r, g, b, a: 1.0, space: ColorSpace::Rgb }
     }
-    
+
     /// Create RGBA color with transparency
     pub fn rgba(r: f64, g: f64, b: f64, a: f64) -> Self {
-        Color { r, g, b, a }
+        Color { r, g, b, a, space: ColorSpace::Rgb }
+    }
+
+    /// DeviceCMYK color (opaque)
+    pub fn cmyk(c: f64, m: f64, y: f64, k: f64) -> Self {
+        let (r, g, b) = cmyk_to_rgb(c, m, y, k);
+        Color { r, g, b, a: 1.0, space: ColorSpace::Cmyk { c, m, y, k } }
+    }
+
+    /// Spot color: ink `name` at `tint`, shown as the CMYK `alternate` (at full tint) where the ink is unknown
+    pub fn spot(name: &str, tint: f64, alternate: [f64; 4]) -> Self {
+        let [c, m, y, k] = alternate.map(|v| v * tint);
+        let (r, g, b) = cmyk_to_rgb(c, m, y, k);
+        Color { r, g, b, a: 1.0, space: ColorSpace::Separation { name: ColorName::new(name), tint, alternate } }
+    }
+
+    /// RGB color in the ICC profile registered with the document as `profile`
+    pub fn icc_rgb(profile: &str, r: f64, g: f64, b: f64) -> Self {
+        Color { r, g, b, a: 1.0, space: ColorSpace::IccBased { profile: ColorName::new(profile), components: [r, g, b, 0.0], count: 3 } }
     }
-    
+
+    /// CMYK color in the ICC profile registered with the document as `profile`
+    pub fn icc_cmyk(profile: &str, c: f64, m: f64, y: f64, k: f64) -> Self {
+        let (r, g, b) = cmyk_to_rgb(c, m, y, k);
+        Color { r, g, b, a: 1.0, space: ColorSpace::IccBased { profile: ColorName::new(profile), components: [c, m, y, k], count: 4 } }
+    }
+
+    /// This color with alpha `a`
+    pub fn with_alpha(self, a: f64) -> Self {
+        Color { a, ..self }
+    }
+
     // Common colors
     pub fn black() -> Self { Color::rgb(0.0, 0.0, 0.0) }
     pub fn white() -> Self { Color::rgb(1.0, 1.0, 1.0) }
     pub fn red() -> Self { Color::rgb(1.0, 0.0, 0.0) }
     pub fn green() -> Self { Color::rgb(0.0, 1.0, 0.0) }
     pub fn blue() -> Self { Color::rgb(0.0, 0.0, 1.0) }
-    pub fn gray(intensity: f64) -> Self { Color::rgb(intensity, intensity, intensity) }
-    
-    /// Convert to PDF fill color operator (rg)
+    /// DeviceGray color
+    pub fn gray(intensity: f64) -> Self {
+        Color { r: intensity, g: intensity, b: intensity, a: 1.0, space: ColorSpace::Gray }
+    }
+
+    /// Operands and operator names for setting this color: (operands, fill op, stroke op)
+    fn pdf_operands(&self) -> (String, &'static str, &'static str) {
+        match self.space {
+            ColorSpace::Rgb => (format!("{:.3} {:.3} {:.3}", self.r, self.g, self.b), "rg", "RG"),
+            ColorSpace::Gray => (format!("{:.3}", self.r), "g", "G"),
+            ColorSpace::Cmyk { c, m, y, k } => (format!("{:.3} {:.3} {:.3} {:.3}", c, m, y, k), "k", "K"),
+            ColorSpace::Separation { tint, .. } => (format!("{:.3}", tint), "scn", "SCN"),
+            ColorSpace::IccBased { components, count, .. } => {
+                let values: Vec<String> = components[..count as usize].iter().map(|v| format!("{:.3}", v)).collect();
+                (values.join(" "), "scn", "SCN")
+            }
+        }
+    }
+
+    /// Convert to PDF fill color operator (rg, g, k, or cs/scn for resource-based spaces)
     pub fn to_pdf_fill(&self) -> String {
-        format!("{:.3} {:.3} {:.3} rg", self.r, self.g, self.b)
+        let (operands, op, _) = self.pdf_operands();
+        match self.color_space_resource() {
+            Some(space) => format!("/{} cs {} {}", space.resource_name(), operands, op),
+            None => format!("{} {}", operands, op),
+        }
     }
-    
-    /// Convert to PDF stroke color operator (RG)
+
+    /// Convert to PDF stroke color operator (RG, G, K, or CS/SCN for resource-based spaces)
     pub fn to_pdf_stroke(&self) -> String {
-        format!("{:.3} {:.3} {:.3} RG", self.r, self.g, self.b)
+        let (operands, _, op) = self.pdf_operands();
+        match self.color_space_resource() {
+            Some(space) => format!("/{} CS {} {}", space.resource_name(), operands, op),
+            None => format!("{} {}", operands, op),
+        }
+    }
+
+    /// The `/ColorSpace` resource the page needs for this color, if any
+    pub fn color_space_resource(&self) -> Option<ColorSpaceResource> {
+        match &self.space {
+            ColorSpace::Separation { name, alternate, .. } => Some(ColorSpaceResource::Separation { name: name.clone(), alternate: *alternate }),
+            ColorSpace::IccBased { profile, count, .. } => Some(ColorSpaceResource::IccBased { profile: profile.clone(), count: *count }),
+            _ => None,
+        }
     }
 }
 
@@ -47,3 +175,185 @@ impl Default for Color {
         Color::black()
     }
 }
+
+/// A color space written as a page `/ColorSpace` resource
+#[derive(Debug, Clone, PartialEq)]
+pub enum ColorSpaceResource {
+    Separation { name: ColorName, alternate: [f64; 4] },
+    IccBased { profile: ColorName, count: u8 },
+}
+
+impl ColorSpaceResource {
+    /// Resource name, from a hash of the ink or profile name so every page agrees on it
+    /// and it stays short whatever the name's length
+    pub fn resource_name(&self) -> String {
+        match self {
+            ColorSpaceResource::Separation { name, .. } => format!("Sep{}", name.to_hash()),
+            ColorSpaceResource::IccBased { profile, count } => format!("ICC{}_{}", count, profile.to_hash()),
+        }
+    }
+
+    fn device_space(count: u8) -> &'static str {
+        match count {
+            1 => "DeviceGray",
+            4 => "DeviceCMYK",
+            _ => "DeviceRGB",
+        }
+    }
+
+    /// The ICC profile stream for `data`
+    pub fn icc_profile_object(&self, data: &[u8]) -> PdfObject {
+        let count = match self {
+            ColorSpaceResource::IccBased { count, .. } => *count,
+            ColorSpaceResource::Separation { .. } => 4,
+        };
+        PdfObject::Stream(vec![
+            ("N".to_string(), PdfObject::Integer(count as i64)),
+            ("Alternate".to_string(), PdfObject::Name(Self::device_space(count).to_string())),
+        ], data.to_vec())
+    }
+
+    /// The color space object. ICC-based spaces reference `profile_id`, the stream from
+    /// `icc_profile_object`; without a registered profile they fall back to the device space.
+    pub fn to_pdf_object(&self, profile_id: Option<u32>) -> PdfObject {
+        match self {
+            ColorSpaceResource::Separation { name, alternate } => {
+                let cmyk = |values: [f64; 4]| PdfObject::Array(values.iter().map(|v| PdfObject::Real(*v)).collect());
+                PdfObject::Array(vec![
+                    PdfObject::Name("Separation".to_string()),
+                    PdfObject::Name(name.to_pdf_name()),
+                    PdfObject::Name("DeviceCMYK".to_string()),
+                    // Tint transform: linear from no ink to the full alternate color
+                    PdfObject::Dictionary(vec![
+                        ("FunctionType".to_string(), PdfObject::Integer(2)),
+                        ("Domain".to_string(), PdfObject::Array(vec![PdfObject::Integer(0), PdfObject::Integer(1)])),
+                        ("C0".to_string(), cmyk([0.0; 4])),
+                        ("C1".to_string(), cmyk(*alternate)),
+                        ("N".to_string(), PdfObject::Integer(1)),
+                    ]),
+                ])
+            }
+            ColorSpaceResource::IccBased { count, .. } => match profile_id {
+                Some(id) => PdfObject::Array(vec![PdfObject::Name("ICCBased".to_string()), PdfObject::Reference(id)]),
+                None => PdfObject::Name(Self::device_space(*count).to_string()),
+            },
+        }
+    }
+
+    /// Name of the ICC profile this space embeds
+    pub fn profile(&self) -> Option<&str> {
+        match self {
+            ColorSpaceResource::IccBased { profile, .. } => Some(profile.as_str()),
+            ColorSpaceResource::Separation { .. } => None,
+        }
+    }
+}
+
+/// Template form of a color: `{"r", "g", "b"}`, `{"gray"}`, `{"c", "m", "y", "k"}`,
+/// `{"spot", "tint", "alternate": [c, m, y, k]}` or `{"icc": profile, "components": [...]}`,
+/// each with an optional `"a"`
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum ColorRepr {
+    Spot {
+        spot: String,
+        #[serde(default = "default_alpha")]
+        tint: f64,
+        alternate: [f64; 4],
+        #[serde(default = "default_alpha")]
+        a: f64,
+    },
+    Icc {
+        icc: String,
+        components: Vec<f64>,
+        #[serde(default = "default_alpha")]
+        a: f64,
+    },
+    Cmyk {
+        c: f64,
+        m: f64,
+        y: f64,
+        k: f64,
+        #[serde(default = "default_alpha")]
+        a: f64,
+    },
+    Gray {
+        gray: f64,
+        #[serde(default = "default_alpha")]
+        a: f64,
+    },
+    Rgb {
+        r: f64,
+        g: f64,
+        b: f64,
+        #[serde(default = "default_alpha")]
+        a: f64,
+    },
+}
+
+impl From<ColorRepr> for Color {
+    fn from(repr: ColorRepr) -> Self {
+        match repr {
+            ColorRepr::Spot { spot, tint, alternate, a } => Color::spot(&spot, tint, alternate).with_alpha(a),
+            ColorRepr::Icc { icc, components, a } => {
+                let get = |i: usize| components.get(i).copied().unwrap_or(0.0);
+                let color = match components.len() {
+                    1 => {
+                        let level = get(0);
+                        Color { r: level, g: level, b: level, a: 1.0, space: ColorSpace::IccBased { profile: ColorName::new(&icc), components: [level, 0.0, 0.0, 0.0], count: 1 } }
+                    }
+                    4 => Color::icc_cmyk(&icc, get(0), get(1), get(2), get(3)),
+                    _ => Color::icc_rgb(&icc, get(0), get(1), get(2)),
+                };
+                color.with_alpha(a)
+            }
+            ColorRepr::Cmyk { c, m, y, k, a } => Color::cmyk(c, m, y, k).with_alpha(a),
+            ColorRepr::Gray { gray, a } => Color::gray(gray).with_alpha(a),
+            ColorRepr::Rgb { r, g, b, a } => Color::rgba(r, g, b, a),
+        }
+    }
+}
+
+impl From<Color> for ColorRepr {
+    fn from(color: Color) -> Self {
+        let a = color.a;
+        match color.space {
+            ColorSpace::Rgb => ColorRepr::Rgb { r: color.r, g: color.g, b: color.b, a },
+            ColorSpace::Gray => ColorRepr::Gray { gray: color.r, a },
+            ColorSpace::Cmyk { c, m, y, k } => ColorRepr::Cmyk { c, m, y, k, a },
+            ColorSpace::Separation { name, tint, alternate } => ColorRepr::Spot { spot: name.as_str().to_string(), tint, alternate, a },
+            ColorSpace::IccBased { profile, components, count } => ColorRepr::Icc {
+                icc: profile.as_str().to_string(),
+                components: components[..count as usize].to_vec(),
+                a,
+            },
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_long_spot_names_stay_distinct() {
+        let prefix = "P".repeat(80);
+        let first = Color::spot(&format!("{}1", prefix), 1.0, [0.0, 1.0, 0.0, 0.0]);
+        let second = Color::spot(&format!("{}2", prefix), 1.0, [0.0, 1.0, 0.0, 0.0]);
+        let (Some(a), Some(b)) = (first.color_space_resource(), second.color_space_resource()) else {
+            panic!("spot colors need a color space resource");
+        };
+        assert_ne!(a.resource_name(), b.resource_name());
+        assert_eq!(a.resource_name(), Color::spot(&format!("{}1", prefix), 0.5, [1.0, 0.0, 0.0, 0.0]).color_space_resource().unwrap().resource_name());
+    }
+
+    #[test]
+    fn test_resource_names_stay_short() {
+        let name = "Ü".repeat(200);
+        let spot = Color::spot(&name, 1.0, [0.0, 0.0, 1.0, 0.0]).color_space_resource().unwrap();
+        let icc = Color::icc_cmyk(&name, 0.0, 0.0, 0.0, 1.0).color_space_resource().unwrap();
+        assert!(spot.resource_name().len() <= 127);
+        assert!(icc.resource_name().len() <= 127);
+        assert_eq!(icc.profile(), Some(name.as_str()));
+    }
+}
@@ -0,0 +1,246 @@
+use owned_ttf_parser::{AsFaceRef, Face, GlyphId, OutlineBuilder, RasterImageFormat, RgbaColor, Transform};
+use owned_ttf_parser::colr::{ClipBox, CompositeMode, Paint, Painter};
+use crate::core::color::Color;
+use crate::core::font::Font;
+use crate::core::image::Image;
+
+/// A glyph drawn in color instead of with the font's monochrome outline
+#[derive(Debug, Clone)]
+pub enum ColorGlyph {
+    /// COLR/CPAL layers, bottom to top. Paths are PDF path operators in font units.
+    Layers(Vec<ColorLayer>),
+    /// sbix/CBDT bitmap. Placement is in em units relative to the glyph origin.
+    Bitmap { image: Image, x: f64, y: f64, width: f64, height: f64 },
+}
+
+#[derive(Debug, Clone)]
+pub struct ColorLayer {
+    pub path: String,
+    pub color: Color,
+}
+
+/// Look up the color representation of a glyph.
+/// `foreground` is used for COLR layers that reference the text color (palette index 0xFFFF).
+pub fn color_glyph(font: &Font, glyph_id: u16, foreground: &Color) -> Option<ColorGlyph> {
+    let face = font.face.as_face_ref();
+    let gid = GlyphId(glyph_id);
+
+    if face.is_color_glyph(gid) {
+        let mut painter = LayerPainter { face, current: String::new(), layers: Vec::new() };
+        let [r, g, b, a] = rgba(foreground);
+        let fg = RgbaColor::new(r, g, b, a);
+        face.paint_color_glyph(gid, 0, fg, &mut painter)?;
+        if !painter.layers.is_empty() {
+            return Some(ColorGlyph::Layers(painter.layers));
+        }
+    }
+
+    // Ask for the largest strike; it is scaled down to the font size when drawn
+    let raster = face.glyph_raster_image(gid, u16::MAX)?;
+    let ppem = raster.pixels_per_em.max(1) as f64;
+    let image = decode_raster(raster.format, raster.data, raster.width, raster.height)?;
+
+    Some(ColorGlyph::Bitmap {
+        x: raster.x as f64 / ppem,
+        y: raster.y as f64 / ppem,
+        width: raster.width as f64 / ppem,
+        height: raster.height as f64 / ppem,
+        image,
+    })
+}
+
+/// 8-bit RGBA of a color, as COLR palettes store them
+pub(crate) fn rgba(color: &Color) -> [u8; 4] {
+    [color.r, color.g, color.b, color.a].map(|c| (c.clamp(0.0, 1.0) * 255.0).round() as u8)
+}
+
+fn decode_raster(format: RasterImageFormat, data: &[u8], width: u16, height: u16) -> Option<Image> {
+    match format {
+        RasterImageFormat::PNG => Image::from_bytes(data).ok(),
+        RasterImageFormat::BitmapPremulBgra32 => {
+            let pixels = (width as usize) * (height as usize);
+            if data.len() < pixels * 4 {
+                return None;
+            }
+            let mut rgb = Vec::with_capacity(pixels * 3);
+            let mut alpha = Vec::with_capacity(pixels);
+            for px in data[..pixels * 4].chunks_exact(4) {
+                let a = px[3];
+                // Undo premultiplication
+                let unmul = |c: u8| if a == 0 { 0 } else { ((c as u32 * 255) / a as u32).min(255) as u8 };
+                rgb.extend_from_slice(&[unmul(px[2]), unmul(px[1]), unmul(px[0])]);
+                alpha.push(a);
+            }
+            Some(Image {
+                width: width as u32,
+                height: height as u32,
+                color_space: "DeviceRGB".to_string(),
+                bits_per_component: 8,
+                data: rgb,
+                filter: Some("FlateDecode".to_string()),
+                alpha: Some(alpha),
+                form: None,
+            })
+        }
+        // Monochrome/grayscale strikes are covered by the regular outline
+        _ => None,
+    }
+}
+
+/// Collects COLR layers as (path, solid color) pairs.
+/// Only solid paints are supported; gradients, clips and transforms from COLRv1 are ignored.
+struct LayerPainter<'f, 'a> {
+    face: &'f Face<'a>,
+    current: String,
+    layers: Vec<ColorLayer>,
+}
+
+impl<'a> Painter<'a> for LayerPainter<'_, 'a> {
+    fn outline_glyph(&mut self, glyph_id: GlyphId) {
+        let mut builder = PathBuilder { path: String::new(), last: (0.0, 0.0) };
+        self.face.outline_glyph(glyph_id, &mut builder);
+        self.current = builder.path;
+    }
+
+    fn paint(&mut self, paint: Paint<'a>) {
+        if let Paint::Solid(c) = paint {
+            self.layers.push(ColorLayer {
+                path: std::mem::take(&mut self.current),
+                color: Color::rgba(
+                    c.red as f64 / 255.0,
+                    c.green as f64 / 255.0,
+                    c.blue as f64 / 255.0,
+                    c.alpha as f64 / 255.0,
+                ),
+            });
+        }
+    }
+
+    fn push_clip(&mut self) {}
+    fn push_clip_box(&mut self, _clipbox: ClipBox) {}
+    fn pop_clip(&mut self) {}
+    fn push_layer(&mut self, _mode: CompositeMode) {}
+    fn pop_layer(&mut self) {}
+    fn push_transform(&mut self, _transform: Transform) {}
+    fn pop_transform(&mut self) {}
+}
+
+/// Converts glyph outlines to PDF path operators (quadratic curves become cubics)
+struct PathBuilder {
+    path: String,
+    last: (f32, f32),
+}
+
+impl OutlineBuilder for PathBuilder {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.path.push_str(&format!("{} {} m ", x, y));
+        self.last = (x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.path.push_str(&format!("{} {} l ", x, y));
+        self.last = (x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        let (x0, y0) = self.last;
+        let c1 = (x0 + 2.0 / 3.0 * (x1 - x0), y0 + 2.0 / 3.0 * (y1 - y0));
+        let c2 = (x + 2.0 / 3.0 * (x1 - x), y + 2.0 / 3.0 * (y1 - y));
+        self.path.push_str(&format!("{} {} {} {} {} {} c ", c1.0, c1.1, c2.0, c2.1, x, y));
+        self.last = (x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.path.push_str(&format!("{} {} {} {} {} {} c ", x1, y1, x2, y2, x, y));
+        self.last = (x, y);
+    }
+
+    fn close(&mut self) {
+        self.path.push_str("h ");
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::page::Page;
+
+    /// Roboto with a COLR/CPAL pair that draws 'A' as a red 'A' under a 'B' in the text color
+    fn colr_font() -> (Font, u16, u16) {
+        let roboto = Font::from_bytes(include_bytes!("../../Roboto-Regular.ttf").to_vec(), "Roboto".to_string()).unwrap();
+        let a = roboto.glyph_index('A').unwrap();
+        let b = roboto.glyph_index('B').unwrap();
+        let words = |values: &[u16]| values.iter().flat_map(|v| v.to_be_bytes()).collect::<Vec<u8>>();
+
+        // Version 0, one base glyph at 14, two layers at 20
+        let mut colr = words(&[0, 1, 0, 14, 0, 20, 2]);
+        colr.extend(words(&[a, 0, 2, a, 0, b, 0xFFFF]));
+        // One palette with one BGRA color record at 14: opaque red
+        let mut cpal = words(&[0, 1, 1, 1, 0, 14, 0]);
+        cpal.extend([0, 0, 255, 255]);
+
+        (Font::from_bytes(with_tables(include_bytes!("../../Roboto-Regular.ttf"), &[(*b"COLR", colr), (*b"CPAL", cpal)]), "Color".to_string()).unwrap(), a, b)
+    }
+
+    /// Rebuild an sfnt with extra tables
+    fn with_tables(font: &[u8], extra: &[([u8; 4], Vec<u8>)]) -> Vec<u8> {
+        let count = u16::from_be_bytes([font[4], font[5]]) as usize;
+        let mut tables: Vec<([u8; 4], Vec<u8>)> = (0..count).map(|i| {
+            let record = &font[12 + i * 16..28 + i * 16];
+            let field = |at: usize| u32::from_be_bytes(record[at..at + 4].try_into().unwrap()) as usize;
+            (record[..4].try_into().unwrap(), font[field(8)..field(8) + field(12)].to_vec())
+        }).collect();
+        tables.extend(extra.iter().cloned());
+        tables.sort_by_key(|(tag, _)| *tag);
+
+        let mut out = font[..4].to_vec();
+        out.extend((tables.len() as u16).to_be_bytes());
+        out.extend([0; 6]);
+        let mut offset = 12 + tables.len() * 16;
+        let mut data = Vec::new();
+        for (tag, table) in &tables {
+            out.extend(tag);
+            out.extend([0; 4]);
+            out.extend((offset as u32).to_be_bytes());
+            out.extend((table.len() as u32).to_be_bytes());
+            data.extend(table);
+            data.resize(data.len().next_multiple_of(4), 0);
+            offset = 12 + tables.len() * 16 + data.len();
+        }
+        out.extend(data);
+        out
+    }
+
+    #[test]
+    fn test_colr_layers_take_the_text_color() {
+        let (font, a, b) = colr_font();
+        assert!(font.color_glyph(b, &Color::black()).is_none());
+
+        let layers = |foreground| match font.color_glyph(a, &foreground).as_deref() {
+            Some(ColorGlyph::Layers(layers)) => layers.iter().map(|l| rgba(&l.color)).collect::<Vec<_>>(),
+            _ => panic!("expected COLR layers"),
+        };
+        assert_eq!(layers(Color::blue()), vec![[255, 0, 0, 255], [0, 0, 255, 255]]);
+        // Cached per text color
+        assert_eq!(layers(Color::green()), vec![[255, 0, 0, 255], [0, 255, 0, 255]]);
+    }
+
+    #[test]
+    fn test_color_glyph_placed_at_shaped_advance() {
+        let (font, _, _) = colr_font();
+        let shaped = font.shape_text("VA", 10.0);
+        let scale = 10.0 / font.units_per_em() as f64;
+
+        let mut page = Page::new(200.0, 200.0);
+        page.set_fill_color(Color::blue());
+        page.text_with_font("VA".to_string(), 20.0, 50.0, 10.0, 0, &font);
+        let content = String::from_utf8(page.content).unwrap();
+
+        // The 'A' sits where the shaper put it, kerned against the 'V'
+        let x = 20.0 + shaped[0].x_advance + shaped[1].x_offset;
+        assert!(content.contains(&format!("q {} 0 0 {} {} {} cm ", scale, scale, x, 50.0)), "{}", content);
+        assert!(content.contains(&format!("<{:04x}>", shaped[0].glyph_id)));
+        // The foreground layer is painted in the fill color
+        assert_eq!(content.matches(&Color::blue().to_pdf_fill()).count(), 2);
+    }
+}
@@ -1,8 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, BTreeSet, HashMap, HashSet};
+use std::collections::hash_map::Entry;
 use std::io::{self, Error, ErrorKind, Write};
 use crate::core::font::Font;
-use crate::core::page::Page;
+use crate::core::page::{Link, Page};
 use crate::core::image::Image;
+use crate::core::form::Form;
+use crate::core::layer::{self, Layer};
+use crate::core::watermark::{Watermark, WatermarkLayer};
+use crate::core::graphics_state::ExtGState;
+use crate::core::color::ColorSpaceResource;
+use crate::core::transform::Matrix;
 use crate::core::writer::{PdfWriter, PdfObject, WriteSeek};
 use flate2::write::ZlibEncoder;
 use flate2::Compression;
@@ -19,8 +26,14 @@ pub enum DocumentMode {
         catalog_id: u32,
         pages_id: u32,
         font_id: u32,
-        custom_font_ids: Vec<u32>,  // Track custom font object IDs
+        custom_font_ids: Vec<(u32, u32)>,  // Track custom font object IDs (Identity-H, Identity-V)
         image_ids: Vec<u32>,        // Track image object IDs (index -> object_id)
+        color_bitmap_ids: HashMap<(usize, u16), u32>,  // Color glyph bitmaps already written
+        ext_gstate_ids: HashMap<ExtGState, u32>,  // Transparency states already written
+        color_space_ids: HashMap<String, u32>,  // Spot and ICC color spaces already written, by resource name
+        icc_profile_ids: HashMap<String, u32>,  // ICC profile streams already written
+        form_ids: HashMap<u32, u32>,  // Forms already written (index -> object_id)
+        layer_ids: Vec<u32>,  // Optional content groups (index -> object_id)
     },
 }
 
@@ -30,6 +43,12 @@ pub struct Document {
     pub fonts: Vec<Font>,  // Registered custom fonts
     pub fonts_embedded: bool,  // Track if fonts have been written in streaming mode
     pub images: Vec<Image>, // Registered images (Buffered mode only)
+    pub image_sizes: Vec<(f64, f64)>, // Natural size of every registered image, for watermarks
+    pub icc_profiles: HashMap<String, Vec<u8>>, // ICC profiles by the name colors refer to
+    pub forms: Vec<Form>, // Registered forms, written once and drawn by reference
+    pub layers: Vec<Layer>, // Optional content groups, listed in the catalog
+    pub watermarks: Vec<Watermark>, // Drawn on every page added after them
+    watermark_forms: HashMap<(usize, u32, u32), u32>, // (watermark, width bits, height bits) -> form_index
 }
 
 impl Document {
@@ -40,6 +59,12 @@ impl Document {
             fonts: Vec::new(),
             fonts_embedded: false,
             images: Vec::new(),
+            image_sizes: Vec::new(),
+            icc_profiles: HashMap::new(),
+            forms: Vec::new(),
+            layers: Vec::new(),
+            watermarks: Vec::new(),
+            watermark_forms: HashMap::new(),
         }
     }
     
@@ -53,12 +78,7 @@ impl Document {
         let font_id = 3;
         let next_object_id = 4; // Next available object ID
         
-        // Write Catalog (with forward reference to Pages)
-        let catalog = PdfObject::Dictionary(vec![
-            ("Type".to_string(), PdfObject::Name("Catalog".to_string())),
-            ("Pages".to_string(), PdfObject::Reference(pages_id)),
-        ]);
-        writer.write_object(catalog_id, &catalog)?;
+        // The Catalog is written by finalize(), once all layers are known
         
         // Write Font (shared resource)
         let font = PdfObject::Dictionary(vec![
@@ -78,10 +98,22 @@ impl Document {
                 font_id,
                 custom_font_ids: Vec::new(),
                 image_ids: Vec::new(),
+                color_bitmap_ids: HashMap::new(),
+                ext_gstate_ids: HashMap::new(),
+                color_space_ids: HashMap::new(),
+                icc_profile_ids: HashMap::new(),
+                form_ids: HashMap::new(),
+                layer_ids: Vec::new(),
             },
             fonts: Vec::new(),
             fonts_embedded: false,
             images: Vec::new(),
+            image_sizes: Vec::new(),
+            icc_profiles: HashMap::new(),
+            forms: Vec::new(),
+            layers: Vec::new(),
+            watermarks: Vec::new(),
+            watermark_forms: HashMap::new(),
         })
     }
     
@@ -92,9 +124,83 @@ impl Document {
         (self.fonts.len() - 1) as u32
     }
 
+    /// Register an ICC profile for colors created with `Color::icc_rgb`/`icc_cmyk` under `name`.
+    /// In streaming mode, register it before adding pages that use it.
+    /// Colors whose profile is missing are written in the matching device space.
+    pub fn add_icc_profile(&mut self, name: &str, data: Vec<u8>) {
+        self.icc_profiles.insert(name.to_string(), data);
+    }
+
+    /// Register a form with the document
+    /// Returns the form index to use with `Page::draw_form`; forms can draw other forms too.
+    /// In streaming mode a form is written with the first page that draws it.
+    pub fn add_form(&mut self, form: &Form) -> u32 {
+        self.forms.push(form.clone());
+        (self.forms.len() - 1) as u32
+    }
+
+    /// Add a watermark or background to every page added from now on that it applies to.
+    /// Watermarks behind the content stack in the order added, the first at the bottom;
+    /// those in front stack over the content the same way.
+    pub fn add_watermark(&mut self, watermark: Watermark) {
+        self.watermarks.push(watermark);
+    }
+
+    /// Draw the watermarks that apply to the page about to be added. Each is recorded
+    /// once per page size as a form, so the pages share one copy.
+    fn apply_watermarks(&mut self, page: &mut Page) {
+        let page_number = match &self.mode {
+            DocumentMode::Buffered(pages) => pages.len() + 1,
+            DocumentMode::Streaming { page_ids, .. } => page_ids.len() + 1,
+        };
+        let mut behind = Vec::new();
+        let mut front = Vec::new();
+        for (index, watermark) in self.watermarks.iter().enumerate() {
+            if !watermark.applies_to(page_number) {
+                continue;
+            }
+            let key = (index, page.width.to_bits(), page.height.to_bits());
+            let form_index = *self.watermark_forms.entry(key).or_insert_with(|| {
+                let mut form = Form::new(page.width as f64, page.height as f64);
+                watermark.draw(&mut form.page, &self.fonts, &self.image_sizes);
+                self.forms.push(form);
+                (self.forms.len() - 1) as u32
+            });
+            match watermark.layer {
+                WatermarkLayer::Behind => behind.push(form_index),
+                WatermarkLayer::Front => front.push(form_index),
+            }
+        }
+        for form_index in behind.into_iter().rev() {
+            page.draw_form_behind(form_index, 0.0, 0.0);
+        }
+        for form_index in front {
+            page.draw_form_over(form_index, 0.0, 0.0);
+        }
+    }
+
+    /// Register a layer (optional content group) with the document
+    /// Returns the layer index to use with `Page::begin_layer`
+    pub fn add_layer(&mut self, layer: &Layer) -> io::Result<u32> {
+        self.layers.push(layer.clone());
+        if let DocumentMode::Streaming { writer, next_object_id, layer_ids, .. } = &mut self.mode {
+            let layer_id = *next_object_id;
+            *next_object_id += 1;
+            writer.write_object(layer_id, &layer.to_pdf_object())?;
+            layer_ids.push(layer_id);
+        }
+        Ok((self.layers.len() - 1) as u32)
+    }
+
+    /// The index of the layer named `name`
+    pub fn layer_index(&self, name: &str) -> Option<u32> {
+        self.layers.iter().position(|l| l.name == name).map(|i| i as u32)
+    }
+
     /// Register an image with the document
     /// Returns the image index to use in page rendering
     pub fn add_image(&mut self, image: &Image) -> io::Result<u32> {
+        self.image_sizes.push(image.natural_size());
         match &mut self.mode {
             DocumentMode::Buffered(_) => {
                 self.images.push(image.clone());
@@ -110,7 +216,7 @@ impl Document {
                 let image_id = *next_object_id;
                 embed_image(writer, image, image_id)?;
                 
-                *next_object_id += 1;
+                *next_object_id += image_object_count(image);
                 image_ids.push(image_id);
                 
                 Ok((image_ids.len() - 1) as u32)
@@ -120,7 +226,8 @@ impl Document {
     
     /// Add a page to the document
     pub fn add_page(&mut self, page: &Page) -> io::Result<()> {
-        let page = page.clone(); // Page is Clone
+        let mut page = page.clone(); // Page is Clone
+        self.apply_watermarks(&mut page);
         match &mut self.mode {
             DocumentMode::Buffered(pages) => {
                 pages.push(page);
@@ -134,54 +241,155 @@ impl Document {
                 font_id,
                 custom_font_ids,
                 image_ids,
+                color_bitmap_ids,
+                ext_gstate_ids,
+                color_space_ids,
+                icc_profile_ids,
+                form_ids,
+                layer_ids,
                 ..  // Ignore catalog_id
             } => {
                 // Embed fonts lazily before the first page
                 if !self.fonts_embedded && !self.fonts.is_empty() {
                     for font in &self.fonts {
                         let base_id = *next_object_id;
-                        let type0_id = embed_custom_font(writer, font, base_id, None)?;
-                        custom_font_ids.push(type0_id);
-                        *next_object_id += 4;  // 4 objects per font
+                        let type0_ids = embed_custom_font(writer, font, base_id, None)?;
+                        custom_font_ids.push(type0_ids);
+                        *next_object_id += 5;  // 5 objects per font
                     }
                     self.fonts_embedded = true;
                 }
                 
-                // Write content stream immediately
-                let content_id = *next_object_id;
-                *next_object_id += 1;
-                
-                let content_stream = PdfObject::Stream(vec![], page.content.clone());
-                writer.write_object(content_id, &content_stream)?;
+                // Forms this page draws that earlier pages did not, with the forms drawn inside them
+                let mut new_forms = BTreeSet::new();
+                let mut pending: Vec<u32> = page.used_forms.iter().copied().collect();
+                while let Some(index) = pending.pop() {
+                    let Some(form) = self.forms.get(index as usize) else { continue };
+                    if !form_ids.contains_key(&index) && new_forms.insert(index) {
+                        pending.extend(form.page.used_forms.iter().copied());
+                    }
+                }
+                let contents: Vec<&Page> = std::iter::once(&page)
+                    .chain(new_forms.iter().map(|index| &self.forms[*index as usize].page))
+                    .collect();
+
+                // Write color glyph bitmaps not yet seen by earlier pages
+                for content in &contents {
+                    let mut bitmap_keys: Vec<_> = content.used_color_bitmaps.keys().copied().collect();
+                    bitmap_keys.sort();
+                    for key in bitmap_keys {
+                        if let Entry::Vacant(entry) = color_bitmap_ids.entry(key) {
+                            let image = &content.used_color_bitmaps[&key];
+                            embed_image(writer, image, *next_object_id)?;
+                            entry.insert(*next_object_id);
+                            *next_object_id += image_object_count(image);
+                        }
+                    }
+                }
+
+                // Write transparency states not yet seen by earlier pages
+                let states: BTreeSet<ExtGState> = contents.iter()
+                    .flat_map(|content| content.used_ext_gstates.iter().copied())
+                    .collect();
+                for state in states {
+                    if let Entry::Vacant(entry) = ext_gstate_ids.entry(state) {
+                        writer.write_object(*next_object_id, &state.to_pdf_object())?;
+                        entry.insert(*next_object_id);
+                        *next_object_id += 1;
+                    }
+                }
                 
+                // Write spot and ICC color spaces not yet seen by earlier pages
+                for (name, space) in contents.iter().flat_map(|content| &content.used_color_spaces) {
+                    if color_space_ids.contains_key(name) {
+                        continue;
+                    }
+                    let profile_id = match space.profile().and_then(|p| self.icc_profiles.get_key_value(p)) {
+                        Some((profile, data)) => match icc_profile_ids.get(profile) {
+                            Some(id) => Some(*id),
+                            None => {
+                                let id = *next_object_id;
+                                *next_object_id += 1;
+                                writer.write_object(id, &space.icc_profile_object(data))?;
+                                icc_profile_ids.insert(profile.clone(), id);
+                                Some(id)
+                            }
+                        },
+                        None => None,
+                    };
+                    let space_id = *next_object_id;
+                    *next_object_id += 1;
+                    writer.write_object(space_id, &space.to_pdf_object(profile_id))?;
+                    color_space_ids.insert(name.clone(), space_id);
+                }
+
                 // Build font resources dictionary including custom fonts
                 let mut font_resources = vec![
                     ("F1".to_string(), PdfObject::Reference(*font_id))
                 ];
-                for (i, type0_id) in custom_font_ids.iter().enumerate() {
+                for (i, (type0_id, type0_v_id)) in custom_font_ids.iter().enumerate() {
                     font_resources.push((format!("F{}", i + 2), PdfObject::Reference(*type0_id)));
+                    font_resources.push((format!("FV{}", i + 2), PdfObject::Reference(*type0_v_id)));
                 }
 
-                // Build XObject resources (images)
-                let mut xobject_resources = Vec::new();
-                for image_idx in &page.used_images {
-                    if let Some(obj_id) = image_ids.get(*image_idx as usize) {
-                        xobject_resources.push((format!("Im{}", image_idx), PdfObject::Reference(*obj_id)));
+                // Number the new forms before writing any, as they can draw each other
+                for index in &new_forms {
+                    form_ids.insert(*index, *next_object_id);
+                    *next_object_id += 1;
+                }
+                let shared = SharedResources {
+                    fonts: &font_resources,
+                    images: image_ids,
+                    color_bitmaps: color_bitmap_ids,
+                    ext_gstates: ext_gstate_ids,
+                    color_spaces: color_space_ids,
+                    forms: form_ids,
+                    layers: layer_ids,
+                };
+                for index in &new_forms {
+                    let form = &self.forms[*index as usize];
+                    let mut shading_ids = Vec::new();
+                    for shading in &form.page.shadings {
+                        let shading_id = *next_object_id;
+                        *next_object_id += 1;
+                        writer.write_object(shading_id, &shading.to_pdf_object())?;
+                        shading_ids.push(shading_id);
                     }
+                    let resources = content_resources(&form.page, &shading_ids, &shared);
+                    writer.write_object(shared.forms[index], &form.to_pdf_object(&Matrix::identity(), resources))?;
                 }
-                
-                let mut resources_dict = vec![
-                    ("Font".to_string(), PdfObject::Dictionary(font_resources))
-                ];
-                if !xobject_resources.is_empty() {
-                    resources_dict.push(("XObject".to_string(), PdfObject::Dictionary(xobject_resources)));
+
+                // Shadings belong to the page that placed them
+                let mut shading_ids = Vec::new();
+                for shading in &page.shadings {
+                    let shading_id = *next_object_id;
+                    *next_object_id += 1;
+                    writer.write_object(shading_id, &shading.to_pdf_object())?;
+                    shading_ids.push(shading_id);
                 }
 
+                // Write content stream immediately
+                let content_id = *next_object_id;
+                *next_object_id += 1;
+                
+                let content_stream = PdfObject::Stream(vec![], page.content.clone());
+                writer.write_object(content_id, &content_stream)?;
+                
+                let resources_dict = content_resources(&page, &shading_ids, &shared);
+                // Write link annotations
+                let mut annot_refs = Vec::new();
+                for link in &page.links {
+                    let annot_id = *next_object_id;
+                    *next_object_id += 1;
+                    writer.write_object(annot_id, &link_annotation(link))?;
+                    annot_refs.push(PdfObject::Reference(annot_id));
+                }
+                
                 // Write page object immediately
                 let page_id = *next_object_id;
                 *next_object_id += 1;
                 
-                let page_obj = PdfObject::Dictionary(vec![
+                let mut page_dict = vec![
                     ("Type".to_string(), PdfObject::Name("Page".to_string())),
                     ("Parent".to_string(), PdfObject::Reference(*pages_id)),
                     ("MediaBox".to_string(), PdfObject::Array(vec![
@@ -192,8 +400,11 @@ impl Document {
                     ])),
                     ("Resources".to_string(), PdfObject::Dictionary(resources_dict)),
                     ("Contents".to_string(), PdfObject::Reference(content_id)),
-                ]);
-                writer.write_object(page_id, &page_obj)?;
+                ];
+                if !annot_refs.is_empty() {
+                    page_dict.push(("Annots".to_string(), PdfObject::Array(annot_refs)));
+                }
+                writer.write_object(page_id, &PdfObject::Dictionary(page_dict))?;
                 
                 // Track page ID for later
                 page_ids.push(page_id);
@@ -215,8 +426,12 @@ impl Document {
                 page_ids,
                 pages_id,
                 catalog_id,
+                layer_ids,
                 ..
             } => {
+                writer.write_object(*catalog_id, &catalog(*pages_id, &self.layers, layer_ids))?;
+
+
                 // Now write the Pages object with all Kids
                 let page_refs: Vec<PdfObject> = page_ids.iter()
                     .map(|page_id| PdfObject::Reference(*page_id))
@@ -256,20 +471,90 @@ impl Document {
                 let pages_id = 2;
                 let font_id = 3;  // Built-in Helvetica
                 
-                // Calculate object IDs for custom fonts (each font needs 4 objects)
+                // Calculate object IDs for custom fonts (each font needs 5 objects)
                 let mut custom_font_ids = Vec::new();
                 let mut next_id = 4;
                 for _ in 0..self.fonts.len() {
                     custom_font_ids.push(next_id);
-                    next_id += 4;  // FontFile, FontDescriptor, CIDFont, Type0
+                    next_id += 5;  // FontFile, FontDescriptor, CIDFont, Type0 (H), Type0 (V)
                 }
 
                 // Calculate object IDs for images
                 let mut image_object_ids = Vec::new();
-                for _ in 0..self.images.len() {
+                for image in &self.images {
                     image_object_ids.push(next_id);
+                    next_id += image_object_count(image);
+                }
+
+                // Calculate object IDs for layers
+                let layer_ids: Vec<u32> = (next_id..next_id + self.layers.len() as u32).collect();
+                next_id += self.layers.len() as u32;
+                
+                // Pages and forms draw with the same shared resources
+                let contents: Vec<&Page> = pages.iter()
+                    .chain(self.forms.iter().map(|form| &form.page))
+                    .collect();
+
+                // Calculate object IDs for color glyph bitmaps (deduplicated across pages)
+                let mut color_bitmaps: BTreeMap<(usize, u16), &Image> = BTreeMap::new();
+                for content in &contents {
+                    for (key, image) in &content.used_color_bitmaps {
+                        color_bitmaps.entry(*key).or_insert(image);
+                    }
+                }
+                let mut color_bitmap_ids = HashMap::new();
+                for (key, image) in &color_bitmaps {
+                    color_bitmap_ids.insert(*key, next_id);
+                    next_id += image_object_count(image);
+                }
+
+                // Calculate object IDs for transparency states (deduplicated across pages)
+                let states: BTreeSet<ExtGState> = contents.iter()
+                    .flat_map(|content| content.used_ext_gstates.iter().copied())
+                    .collect();
+                let mut ext_gstate_ids = HashMap::new();
+                for state in &states {
+                    ext_gstate_ids.insert(*state, next_id);
                     next_id += 1;
                 }
+
+                // Calculate object IDs for spot and ICC color spaces (deduplicated across pages),
+                // each ICC-based one preceded by its profile the first time the profile is used
+                let mut color_spaces: BTreeMap<&String, &ColorSpaceResource> = BTreeMap::new();
+                for content in &contents {
+                    for (name, space) in &content.used_color_spaces {
+                        color_spaces.entry(name).or_insert(space);
+                    }
+                }
+                let mut icc_profile_ids: HashMap<&str, u32> = HashMap::new();
+                let mut color_space_ids = HashMap::new();
+                let mut profile_objects = Vec::new();
+                for (name, space) in &color_spaces {
+                    let profile = space.profile().and_then(|p| self.icc_profiles.get_key_value(p));
+                    if let Some((profile, data)) = profile.filter(|(p, _)| !icc_profile_ids.contains_key(p.as_str())) {
+                        icc_profile_ids.insert(profile, next_id);
+                        profile_objects.push((next_id, space.icc_profile_object(data)));
+                        next_id += 1;
+                    }
+                    color_space_ids.insert((*name).clone(), next_id);
+                    next_id += 1;
+                }
+
+                // Calculate object IDs for each page's shadings
+                let mut page_shading_ids = Vec::new();
+                for page in pages {
+                    page_shading_ids.push((next_id..next_id + page.shadings.len() as u32).collect::<Vec<u32>>());
+                    next_id += page.shadings.len() as u32;
+                }
+
+                // Calculate object IDs for forms, each followed by its shadings
+                let mut form_ids = HashMap::new();
+                let mut form_shading_ids = Vec::new();
+                for (i, form) in self.forms.iter().enumerate() {
+                    form_ids.insert(i as u32, next_id);
+                    form_shading_ids.push((next_id + 1..next_id + 1 + form.page.shadings.len() as u32).collect::<Vec<u32>>());
+                    next_id += 1 + form.page.shadings.len() as u32;
+                }
                 
                 // Calculate object IDs for pages
                 let mut page_object_ids = Vec::new();
@@ -279,12 +564,17 @@ impl Document {
                     page_object_ids.push((content_id, page_id));
                 }
                 
+                // Calculate object IDs for link annotations (after all pages)
+                let mut annot_id = next_id + (pages.len() * 2) as u32;
+                let mut page_annot_ids = Vec::new();
+                for page in pages {
+                    let ids: Vec<u32> = (annot_id..annot_id + page.links.len() as u32).collect();
+                    annot_id += page.links.len() as u32;
+                    page_annot_ids.push(ids);
+                }
+                
                 // Write Catalog
-                let catalog = PdfObject::Dictionary(vec![
-                    ("Type".to_string(), PdfObject::Name("Catalog".to_string())),
-                    ("Pages".to_string(), PdfObject::Reference(pages_id)),
-                ]);
-                writer.write_object(catalog_id, &catalog)?;
+                writer.write_object(catalog_id, &catalog(pages_id, &self.layers, &layer_ids))?;
                 
                 // Write Pages tree
                 let page_refs: Vec<PdfObject> = page_object_ids.iter()
@@ -308,8 +598,8 @@ impl Document {
                 
                 // Aggregate glyph usage across all pages for subsetting
                 let mut font_glyph_usage: HashMap<usize, HashSet<u16>> = HashMap::new();
-                for page in pages {
-                    for (font_idx, gids) in &page.used_glyphs {
+                for content in &contents {
+                    for (font_idx, gids) in &content.used_glyphs {
                         font_glyph_usage
                             .entry(*font_idx)
                             .or_insert_with(HashSet::new)
@@ -321,21 +611,61 @@ impl Document {
                 let mut type0_font_ids = Vec::new();
                 for (i, font) in self.fonts.iter().enumerate() {
                     let used_gids = font_glyph_usage.get(&i);
-                    let type0_id = embed_custom_font(&mut writer, font, custom_font_ids[i], used_gids)?;
-                    type0_font_ids.push(type0_id);
+                    let type0_ids = embed_custom_font(&mut writer, font, custom_font_ids[i], used_gids)?;
+                    type0_font_ids.push(type0_ids);
                 }
 
                 // Embed images
                 for (i, image) in self.images.iter().enumerate() {
                     embed_image(&mut writer, image, image_object_ids[i])?;
                 }
+                for (layer, id) in self.layers.iter().zip(&layer_ids) {
+                    writer.write_object(*id, &layer.to_pdf_object())?;
+                }
+                for (key, image) in &color_bitmaps {
+                    embed_image(&mut writer, image, color_bitmap_ids[key])?;
+                }
+                for state in &states {
+                    writer.write_object(ext_gstate_ids[state], &state.to_pdf_object())?;
+                }
+                for (id, profile) in &profile_objects {
+                    writer.write_object(*id, profile)?;
+                }
+                for (name, space) in &color_spaces {
+                    let profile_id = space.profile().and_then(|p| icc_profile_ids.get(p)).copied();
+                    writer.write_object(color_space_ids[*name], &space.to_pdf_object(profile_id))?;
+                }
+                for (page, ids) in pages.iter().zip(&page_shading_ids) {
+                    for (shading, id) in page.shadings.iter().zip(ids) {
+                        writer.write_object(*id, &shading.to_pdf_object())?;
+                    }
+                }
                 
                 // Build font resources dictionary
                 let mut font_resources = vec![
                     ("F1".to_string(), PdfObject::Reference(font_id))
                 ];
-                for (i, type0_id) in type0_font_ids.iter().enumerate() {
+                for (i, (type0_id, type0_v_id)) in type0_font_ids.iter().enumerate() {
                     font_resources.push((format!("F{}", i + 2), PdfObject::Reference(*type0_id)));
+                    font_resources.push((format!("FV{}", i + 2), PdfObject::Reference(*type0_v_id)));
+                }
+                let shared = SharedResources {
+                    fonts: &font_resources,
+                    images: &image_object_ids,
+                    color_bitmaps: &color_bitmap_ids,
+                    ext_gstates: &ext_gstate_ids,
+                    color_spaces: &color_space_ids,
+                    forms: &form_ids,
+                    layers: &layer_ids,
+                };
+
+                // Write forms
+                for (i, (form, shading_ids)) in self.forms.iter().zip(&form_shading_ids).enumerate() {
+                    for (shading, id) in form.page.shadings.iter().zip(shading_ids) {
+                        writer.write_object(*id, &shading.to_pdf_object())?;
+                    }
+                    let resources = content_resources(&form.page, shading_ids, &shared);
+                    writer.write_object(form_ids[&(i as u32)], &form.to_pdf_object(&Matrix::identity(), resources))?;
                 }
                 
                 // Write each page
@@ -345,22 +675,9 @@ impl Document {
                     let content_stream = PdfObject::Stream(vec![], page.content.clone());
                     writer.write_object(content_id, &content_stream)?;
 
-                    // Build XObject resources (images)
-                    let mut xobject_resources = Vec::new();
-                    for image_idx in &page.used_images {
-                        if let Some(obj_id) = image_object_ids.get(*image_idx as usize) {
-                            xobject_resources.push((format!("Im{}", image_idx), PdfObject::Reference(*obj_id)));
-                        }
-                    }
-
-                    let mut resources_dict = vec![
-                        ("Font".to_string(), PdfObject::Dictionary(font_resources.clone()))
-                    ];
-                    if !xobject_resources.is_empty() {
-                        resources_dict.push(("XObject".to_string(), PdfObject::Dictionary(xobject_resources)));
-                    }
+                    let resources_dict = content_resources(page, &page_shading_ids[i], &shared);
                     
-                    let page_obj = PdfObject::Dictionary(vec![
+                    let mut page_dict = vec![
                         ("Type".to_string(), PdfObject::Name("Page".to_string())),
                         ("Parent".to_string(), PdfObject::Reference(pages_id)),
                         ("MediaBox".to_string(), PdfObject::Array(vec![
@@ -371,8 +688,17 @@ impl Document {
                         ])),
                         ("Resources".to_string(), PdfObject::Dictionary(resources_dict)),
                         ("Contents".to_string(), PdfObject::Reference(content_id)),
-                    ]);
-                    writer.write_object(page_id, &page_obj)?;
+                    ];
+                    
+                    let annot_ids = &page_annot_ids[i];
+                    for (link, id) in page.links.iter().zip(annot_ids) {
+                        writer.write_object(*id, &link_annotation(link))?;
+                    }
+                    if !annot_ids.is_empty() {
+                        let refs = annot_ids.iter().map(|id| PdfObject::Reference(*id)).collect();
+                        page_dict.push(("Annots".to_string(), PdfObject::Array(refs)));
+                    }
+                    writer.write_object(page_id, &PdfObject::Dictionary(page_dict))?;
                 }
                 
                 writer.write_xref_and_trailer(catalog_id)?;
@@ -399,11 +725,14 @@ fn subset_font(font: &Font, used_gids: &HashSet<u16>) -> Vec<u8> {
 }
 
 /// Embed a custom TrueType font into PDF
-fn embed_custom_font<W: WriteSeek>(writer: &mut PdfWriter<W>, font: &Font, base_id: u32, used_gids: Option<&HashSet<u16>>) -> io::Result<u32> {
+/// Returns the object IDs of the horizontal (Identity-H) and vertical (Identity-V) Type0 fonts,
+/// which share a single CIDFont
+fn embed_custom_font<W: WriteSeek>(writer: &mut PdfWriter<W>, font: &Font, base_id: u32, used_gids: Option<&HashSet<u16>>) -> io::Result<(u32, u32)> {
     let font_file_id = base_id;
     let font_descriptor_id = base_id + 1;
     let cid_font_id = base_id + 2;
     let type0_font_id = base_id + 3;
+    let type0_vertical_id = base_id + 4;
     
     // 1. Write TrueType font file stream
     let font_data = if let Some(gids) = used_gids {
@@ -501,6 +830,37 @@ fn embed_custom_font<W: WriteSeek>(writer: &mut PdfWriter<W>, font: &Font, base_
         ])
     };
 
+    // Vertical metrics for Identity-V: DW2 = [origin_y advance_y], W2 = c [w1y v1x v1y]
+    let scale = 1000.0 / font.units_per_em() as f32;
+    let dw2 = PdfObject::Array(vec![
+        PdfObject::Integer(font.ascent() as i64),
+        PdfObject::Integer(font.descent() as i64 - font.ascent() as i64),
+    ]);
+    let vertical_entry = |gid: u16| -> Vec<PdfObject> {
+        let advance = (font.get_glyph_vertical_advance(gid) as f32 * scale) as i64;
+        let origin_x = (font.get_glyph_width(gid) as f32 * scale / 2.0) as i64;
+        let origin_y = (font.get_glyph_vertical_origin(gid) as f32 * scale) as i64;
+        vec![PdfObject::Integer(-advance), PdfObject::Integer(origin_x), PdfObject::Integer(origin_y)]
+    };
+    let w2_array = if let Some(gids) = used_gids {
+        let mut sorted_gids: Vec<u16> = gids.iter().copied().collect();
+        sorted_gids.sort();
+        
+        let mut w2 = Vec::new();
+        for gid in sorted_gids {
+            w2.push(PdfObject::Integer(gid as i64));
+            w2.push(PdfObject::Array(vertical_entry(gid)));
+        }
+        PdfObject::Array(w2)
+    } else if font.has_vertical_metrics() {
+        // Streaming mode: full table, same as W above
+        let entries = (0..font.number_of_glyphs()).flat_map(vertical_entry).collect();
+        PdfObject::Array(vec![PdfObject::Integer(0), PdfObject::Array(entries)])
+    } else {
+        // No vmtx: every glyph uses DW2
+        PdfObject::Array(vec![])
+    };
+
     //3. Write CIDFont
     let cid_font = PdfObject::Dictionary(vec![
         ("Type".to_string(), PdfObject::Name("Font".to_string())),
@@ -515,6 +875,8 @@ fn embed_custom_font<W: WriteSeek>(writer: &mut PdfWriter<W>, font: &Font, base_
         ("CIDToGIDMap".to_string(), PdfObject::Name("Identity".to_string())),
         ("DW".to_string(), PdfObject::Integer(1000)),
         ("W".to_string(), w_array),
+        ("DW2".to_string(), dw2),
+        ("W2".to_string(), w2_array),
     ]);
     writer.write_object(cid_font_id, &cid_font)?;
     
@@ -530,11 +892,159 @@ fn embed_custom_font<W: WriteSeek>(writer: &mut PdfWriter<W>, font: &Font, base_
     ]);
     writer.write_object(type0_font_id, &type0_font)?;
     
-    Ok(type0_font_id)
+    // 5. Write vertical Type0 font (same descendant, Identity-V encoding)
+    let type0_vertical = PdfObject::Dictionary(vec![
+        ("Type".to_string(), PdfObject::Name("Font".to_string())),
+        ("Subtype".to_string(), PdfObject::Name("Type0".to_string())),
+        ("BaseFont".to_string(), PdfObject::Name(font.get_name().to_string())),
+        ("Encoding".to_string(), PdfObject::Name("Identity-V".to_string())),
+        ("DescendantFonts".to_string(), PdfObject::Array(vec![
+            PdfObject::Reference(cid_font_id)
+        ])),
+    ]);
+    writer.write_object(type0_vertical_id, &type0_vertical)?;
+    
+    Ok((type0_font_id, type0_vertical_id))
+}
+
+/// ExtGState resource dictionary for the transparency states a page uses
+fn ext_gstate_resources(page: &Page, ids: &HashMap<ExtGState, u32>) -> Option<PdfObject> {
+    let mut states: Vec<&ExtGState> = page.used_ext_gstates.iter().collect();
+    if states.is_empty() {
+        return None;
+    }
+    states.sort();
+    let entries = states.into_iter()
+        .filter_map(|state| ids.get(state).map(|id| (state.name(), PdfObject::Reference(*id))))
+        .collect();
+    Some(PdfObject::Dictionary(entries))
+}
+
+/// Object IDs of the resources pages and forms share
+struct SharedResources<'a> {
+    fonts: &'a [(String, PdfObject)],
+    images: &'a [u32],
+    color_bitmaps: &'a HashMap<(usize, u16), u32>,
+    ext_gstates: &'a HashMap<ExtGState, u32>,
+    color_spaces: &'a HashMap<String, u32>,
+    forms: &'a HashMap<u32, u32>,
+    layers: &'a [u32],
+}
+
+/// Resource dictionary entries for the content of a page or form
+fn content_resources(content: &Page, shading_ids: &[u32], shared: &SharedResources) -> Vec<(String, PdfObject)> {
+    // XObjects: images, color glyph bitmaps and forms
+    let mut xobject_resources = Vec::new();
+    for image_idx in &content.used_images {
+        if let Some(obj_id) = shared.images.get(*image_idx as usize) {
+            xobject_resources.push((format!("Im{}", image_idx), PdfObject::Reference(*obj_id)));
+        }
+    }
+    for (font_idx, gid) in content.used_color_bitmaps.keys() {
+        if let Some(obj_id) = shared.color_bitmaps.get(&(*font_idx, *gid)) {
+            xobject_resources.push((format!("CG{}_{}", font_idx, gid), PdfObject::Reference(*obj_id)));
+        }
+    }
+    for form_idx in &content.used_forms {
+        if let Some(obj_id) = shared.forms.get(form_idx) {
+            xobject_resources.push((format!("Fm{}", form_idx), PdfObject::Reference(*obj_id)));
+        }
+    }
+
+    let mut resources_dict = vec![
+        ("Font".to_string(), PdfObject::Dictionary(shared.fonts.to_vec()))
+    ];
+    if !xobject_resources.is_empty() {
+        resources_dict.push(("XObject".to_string(), PdfObject::Dictionary(xobject_resources)));
+    }
+    if let Some(states) = ext_gstate_resources(content, shared.ext_gstates) {
+        resources_dict.push(("ExtGState".to_string(), states));
+    }
+    if let Some(shadings) = shading_resources(shading_ids) {
+        resources_dict.push(("Shading".to_string(), shadings));
+    }
+    if let Some(spaces) = color_space_resources(content, shared.color_spaces) {
+        resources_dict.push(("ColorSpace".to_string(), spaces));
+    }
+    // Layers are marked-content properties
+    let mut layer_indices: Vec<u32> = content.used_layers.iter().copied().collect();
+    layer_indices.sort();
+    let layer_resources: Vec<(String, PdfObject)> = layer_indices.iter()
+        .filter_map(|i| shared.layers.get(*i as usize).map(|id| (format!("OC{}", i), PdfObject::Reference(*id))))
+        .collect();
+    if !layer_resources.is_empty() {
+        resources_dict.push(("Properties".to_string(), PdfObject::Dictionary(layer_resources)));
+    }
+    resources_dict
+}
+
+/// The document catalog, with the layers' /OCProperties when there are any
+fn catalog(pages_id: u32, layers: &[Layer], layer_ids: &[u32]) -> PdfObject {
+    let mut dict = vec![
+        ("Type".to_string(), PdfObject::Name("Catalog".to_string())),
+        ("Pages".to_string(), PdfObject::Reference(pages_id)),
+    ];
+    if !layers.is_empty() {
+        dict.push(("OCProperties".to_string(), layer::oc_properties(layers, layer_ids)));
+    }
+    PdfObject::Dictionary(dict)
+}
+
+/// Shading resource dictionary naming a page's shadings /Sh0, /Sh1, ...
+fn shading_resources(ids: &[u32]) -> Option<PdfObject> {
+    if ids.is_empty() {
+        return None;
+    }
+    let entries = ids.iter().enumerate()
+        .map(|(i, id)| (format!("Sh{}", i), PdfObject::Reference(*id)))
+        .collect();
+    Some(PdfObject::Dictionary(entries))
+}
+
+/// ColorSpace resource dictionary for the spot and ICC color spaces a page uses
+fn color_space_resources(page: &Page, ids: &HashMap<String, u32>) -> Option<PdfObject> {
+    if page.used_color_spaces.is_empty() {
+        return None;
+    }
+    let entries = page.used_color_spaces.keys()
+        .filter_map(|name| ids.get(name).map(|id| (name.clone(), PdfObject::Reference(*id))))
+        .collect();
+    Some(PdfObject::Dictionary(entries))
+}
+
+/// Build a URI link annotation
+fn link_annotation(link: &Link) -> PdfObject {
+    PdfObject::Dictionary(vec![
+        ("Type".to_string(), PdfObject::Name("Annot".to_string())),
+        ("Subtype".to_string(), PdfObject::Name("Link".to_string())),
+        ("Rect".to_string(), PdfObject::Array(vec![
+            PdfObject::Real(link.x),
+            PdfObject::Real(link.y),
+            PdfObject::Real(link.x + link.width),
+            PdfObject::Real(link.y + link.height),
+        ])),
+        ("Border".to_string(), PdfObject::Array(vec![PdfObject::Integer(0), PdfObject::Integer(0), PdfObject::Integer(0)])),
+        ("A".to_string(), PdfObject::Dictionary(vec![
+            ("S".to_string(), PdfObject::Name("URI".to_string())),
+            ("URI".to_string(), PdfObject::String(link.uri.clone())),
+        ])),
+    ])
+}
+
+/// Number of PDF objects an image occupies (the image plus an optional soft mask)
+fn image_object_count(image: &Image) -> u32 {
+    if image.alpha.is_some() { 2 } else { 1 }
 }
 
 /// Embed an image into the PDF
+/// If the image has an alpha channel, its soft mask is written at `object_id + 1`
 fn embed_image<W: WriteSeek>(writer: &mut PdfWriter<W>, image: &Image, object_id: u32) -> io::Result<()> {
+    // Vector images are forms scaled to the unit square pages draw images in
+    if let Some(form) = &image.form {
+        let unit = Matrix::scale(1.0 / form.width.max(f64::EPSILON), 1.0 / form.height.max(f64::EPSILON));
+        return writer.write_object(object_id, &form.to_pdf_object(&unit, form.inline_resources()));
+    }
+
     // If filter is explicitly set (e.g. DCTDecode for JPEG), use raw data
     // If filter is None or FlateDecode was requested (for PNG), compress data
     
@@ -568,8 +1078,65 @@ fn embed_image<W: WriteSeek>(writer: &mut PdfWriter<W>, image: &Image, object_id
         dict.push(("Filter".to_string(), PdfObject::Name(f)));
     }
     
+    if let Some(alpha) = &image.alpha {
+        let smask_id = object_id + 1;
+        let mut encoder = ZlibEncoder::new(Vec::new(), Compression::default());
+        encoder.write_all(alpha)?;
+        let smask = PdfObject::Stream(vec![
+            ("Type".to_string(), PdfObject::Name("XObject".to_string())),
+            ("Subtype".to_string(), PdfObject::Name("Image".to_string())),
+            ("Width".to_string(), PdfObject::Integer(image.width as i64)),
+            ("Height".to_string(), PdfObject::Integer(image.height as i64)),
+            ("ColorSpace".to_string(), PdfObject::Name("DeviceGray".to_string())),
+            ("BitsPerComponent".to_string(), PdfObject::Integer(8)),
+            ("Filter".to_string(), PdfObject::Name("FlateDecode".to_string())),
+        ], encoder.finish()?);
+        writer.write_object(smask_id, &smask)?;
+        dict.push(("SMask".to_string(), PdfObject::Reference(smask_id)));
+    }
+    
     let image_obj = PdfObject::Stream(dict, data);
     writer.write_object(object_id, &image_obj)?;
     
     Ok(())
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn translucent_page() -> Page {
+        let mut page = Page::new(200.0, 200.0);
+        page.set_opacity(0.5, 0.5);
+        page
+    }
+
+    fn count_ext_gstates(pdf: &[u8]) -> usize {
+        String::from_utf8_lossy(pdf).matches("/Type /ExtGState").count()
+    }
+
+    #[test]
+    fn test_ext_gstates_shared_across_pages_buffered() {
+        let mut doc = Document::new();
+        doc.add_page(&translucent_page()).unwrap();
+        doc.add_page(&translucent_page()).unwrap();
+
+        let mut out = std::io::Cursor::new(Vec::new());
+        doc.write_to_writer(&mut out).unwrap();
+        assert_eq!(count_ext_gstates(out.get_ref()), 1);
+    }
+
+    #[test]
+    fn test_ext_gstates_shared_across_pages_streaming() {
+        let path = std::env::temp_dir().join(format!("ext_gstates_{}.pdf", std::process::id()));
+        let mut doc = Document::streaming(path.to_str().unwrap()).unwrap();
+        doc.add_page(&translucent_page()).unwrap();
+        doc.add_page(&translucent_page()).unwrap();
+        doc.finalize().unwrap();
+        drop(doc);
+
+        let pdf = std::fs::read(&path).unwrap();
+        std::fs::remove_file(&path).unwrap();
+        assert_eq!(count_ext_gstates(&pdf), 1);
+    }
+}
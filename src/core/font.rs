@@ -4,6 +4,14 @@ use std::cell::RefCell;
 use std::collections::HashMap;
 use owned_ttf_parser::{OwnedFace, AsFaceRef};
 use std::io::{self, Error, ErrorKind};
+use crate::core::color::Color;
+use crate::core::color_glyph::{self, ColorGlyph};
+
+/// Shape cache key: (text, size in 1/100 pt, forced shaping direction)
+type ShapeCacheKey = (String, u32, Option<rustybuzz::Direction>);
+
+/// Color glyph cache key: (glyph, RGBA of the foreground COLR layers are painted with)
+type ColorCacheKey = (u16, [u8; 4]);
 
 /// Represents a loaded font with parsing and shaping capabilities
 #[derive(Clone)]
@@ -12,7 +20,9 @@ pub struct Font {
     pub(crate) name: String,
     pub(crate) units_per_em: u16,
     // Cache for shaped glyphs - uses RefCell for interior mutability
-    shape_cache: Arc<RefCell<HashMap<(String, u32), Vec<ShapedGlyph>>>>,
+    shape_cache: Arc<RefCell<HashMap<ShapeCacheKey, Vec<ShapedGlyph>>>>,
+    // Color glyph lookups (COLR layers / decoded bitmaps), None = monochrome glyph
+    color_cache: Arc<RefCell<HashMap<ColorCacheKey, Option<Arc<ColorGlyph>>>>>,
 }
 
 impl Font {
@@ -34,15 +44,32 @@ impl Font {
             name, 
             units_per_em,
             shape_cache: Arc::new(RefCell::new(HashMap::new())),
+            color_cache: Arc::new(RefCell::new(HashMap::new())),
         })
     }
     
     
     /// Shape text and return glyph IDs with positions
     pub fn shape_text(&self, text: &str, size: f64) -> Vec<ShapedGlyph> {
+        self.shape(text, size, None)
+    }
+
+    /// Shape a single-direction run. RTL runs come back in visual order (reversed).
+    pub fn shape_text_directed(&self, text: &str, size: f64, rtl: bool) -> Vec<ShapedGlyph> {
+        let direction = if rtl { rustybuzz::Direction::RightToLeft } else { rustybuzz::Direction::LeftToRight };
+        self.shape(text, size, Some(direction))
+    }
+
+    /// Shape text top-to-bottom. This applies the `vert`/`vrt2` alternates
+    /// (rotated punctuation, small kana) used in vertical writing.
+    pub fn shape_text_vertical(&self, text: &str, size: f64) -> Vec<ShapedGlyph> {
+        self.shape(text, size, Some(rustybuzz::Direction::TopToBottom))
+    }
+
+    fn shape(&self, text: &str, size: f64, direction: Option<rustybuzz::Direction>) -> Vec<ShapedGlyph> {
         // Convert size to u32 for cache key (precision to 0.01)
         let size_key = (size * 100.0) as u32;
-        let cache_key = (text.to_string(), size_key);
+        let cache_key = (text.to_string(), size_key, direction);
         
         // Check cache first
         {
@@ -55,6 +82,9 @@ impl Font {
         // Cache miss - shape the text
         let mut buffer = rustybuzz::UnicodeBuffer::new();
         buffer.push_str(text);
+        if let Some(direction) = direction {
+            buffer.set_direction(direction);
+        }
         
         // owned_ttf_parser uses Send+Sync, cloning Arc is fine
         let rb_face = rustybuzz::Face::from_face(self.face.as_face_ref().clone());
@@ -93,6 +123,16 @@ impl Font {
             .sum()
     }
     
+    /// Measure the length of a vertical (top-to-bottom) run using vmtx advances
+    pub fn measure_text_vertical(&self, text: &str, size: f64) -> f64 {
+        let glyphs = self.shape_text_vertical(text, size);
+        let scale = size / self.units_per_em as f64;
+        
+        glyphs.iter()
+            .map(|g| self.get_glyph_vertical_advance(g.glyph_id) as f64 * scale)
+            .sum()
+    }
+    
     /// Get font metrics
     pub fn get_name(&self) -> &str {
         &self.name
@@ -151,12 +191,128 @@ impl Font {
         face.italic_angle()
     }
 
+    /// Family name from the name table (typographic family preferred), falling back to the registered name
+    pub fn family_name(&self) -> String {
+        let face = self.face.as_face_ref();
+        let family = |id| face.names().into_iter().find(|n| n.name_id == id && n.is_unicode()).and_then(|n| n.to_string());
+        family(owned_ttf_parser::name_id::TYPOGRAPHIC_FAMILY)
+            .or_else(|| family(owned_ttf_parser::name_id::FAMILY))
+            .unwrap_or_else(|| self.name.clone())
+    }
+
+    /// Weight class from OS/2 (400 = regular, 700 = bold)
+    pub fn weight(&self) -> u16 {
+        self.face.as_face_ref().weight().to_number()
+    }
+
+    pub fn is_italic(&self) -> bool {
+        self.face.as_face_ref().is_italic()
+    }
+
+    /// Underline position and thickness (scaled to 1000 units).
+    /// Position is the top of the underline relative to the baseline (negative = below).
+    pub fn underline_metrics(&self) -> (i16, i16) {
+        let face = self.face.as_face_ref();
+        let scale = |v: i16| (v as i32 * 1000 / self.units_per_em as i32) as i16;
+        match face.underline_metrics() {
+            Some(m) if m.thickness > 0 => (scale(m.position), scale(m.thickness)),
+            _ => (-100, 50),
+        }
+    }
+
+    /// Strikeout position and thickness (scaled to 1000 units) from the OS/2 table.
+    /// Position is the top of the stroke relative to the baseline.
+    pub fn strikeout_metrics(&self) -> (i16, i16) {
+        let face = self.face.as_face_ref();
+        let scale = |v: i16| (v as i32 * 1000 / self.units_per_em as i32) as i16;
+        match face.strikeout_metrics() {
+            Some(m) if m.thickness > 0 => (scale(m.position), scale(m.thickness)),
+            // Fonts without OS/2 metrics: strike through the middle of lowercase letters
+            _ => {
+                let (_, thickness) = self.underline_metrics();
+                let x_height = face.x_height().map(scale).unwrap_or(500);
+                (x_height / 2 + thickness / 2, thickness)
+            }
+        }
+    }
+
+    /// Horizontal extent (scaled to 1000 units) of the parts of a glyph's outline that
+    /// reach below `y`, or None when the glyph stays above it. Used to gap underlines
+    /// around descenders.
+    pub fn glyph_ink_below(&self, glyph_id: u16, y: i16) -> Option<(f64, f64)> {
+        let face = self.face.as_face_ref();
+        let threshold = y as f64 * self.units_per_em as f64 / 1000.0;
+        let mut extent = InkExtent { threshold, range: None };
+        face.outline_glyph(owned_ttf_parser::GlyphId(glyph_id), &mut extent)?;
+        let scale = 1000.0 / self.units_per_em as f64;
+        extent.range.map(|(min, max)| (min * scale, max * scale))
+    }
+
     /// Get horizontal advance width for a glyph
     pub fn get_glyph_width(&self, glyph_id: u16) -> u16 {
         let face = self.face.as_face_ref();
         face.glyph_hor_advance(owned_ttf_parser::GlyphId(glyph_id)).unwrap_or(0)
     }
 
+    /// Get vertical advance height for a glyph (font units).
+    /// Falls back to ascender - descender when the font has no vmtx table.
+    pub fn get_glyph_vertical_advance(&self, glyph_id: u16) -> u16 {
+        let face = self.face.as_face_ref();
+        face.glyph_ver_advance(owned_ttf_parser::GlyphId(glyph_id))
+            .unwrap_or_else(|| (face.ascender() as i32 - face.descender() as i32) as u16)
+    }
+
+    /// Get the y coordinate of a glyph's vertical origin (font units).
+    /// Uses VORG, then vmtx top side bearing + glyph bbox, then the ascender.
+    pub fn get_glyph_vertical_origin(&self, glyph_id: u16) -> i16 {
+        let face = self.face.as_face_ref();
+        let gid = owned_ttf_parser::GlyphId(glyph_id);
+        if let Some(y) = face.glyph_y_origin(gid) {
+            return y;
+        }
+        if let (Some(tsb), Some(bbox)) = (face.glyph_ver_side_bearing(gid), face.glyph_bounding_box(gid)) {
+            return tsb.saturating_add(bbox.y_max);
+        }
+        face.ascender()
+    }
+
+    /// Whether the font carries real vertical metrics (vhea/vmtx)
+    pub fn has_vertical_metrics(&self) -> bool {
+        self.face.as_face_ref().vertical_height().is_some()
+    }
+
+    /// Whether the font has COLR, sbix or CBDT tables
+    pub fn has_color_glyphs(&self) -> bool {
+        let tables = self.face.as_face_ref().tables();
+        tables.colr.is_some() || tables.sbix.is_some() || tables.cbdt.is_some()
+    }
+
+    /// Get the color representation of a glyph, if it has one.
+    /// COLR layers that use the text color are painted in `foreground`.
+    pub fn color_glyph(&self, glyph_id: u16, foreground: &Color) -> Option<Arc<ColorGlyph>> {
+        if !self.has_color_glyphs() {
+            return None;
+        }
+        // Bitmaps look the same in any text color, so they are decoded once
+        let foreground_key = if self.face.as_face_ref().is_color_glyph(owned_ttf_parser::GlyphId(glyph_id)) {
+            color_glyph::rgba(foreground)
+        } else {
+            [0; 4]
+        };
+        let key = (glyph_id, foreground_key);
+        if let Some(cached) = self.color_cache.borrow().get(&key) {
+            return cached.clone();
+        }
+        let glyph = color_glyph::color_glyph(self, glyph_id, foreground).map(Arc::new);
+        self.color_cache.borrow_mut().insert(key, glyph.clone());
+        glyph
+    }
+
+    /// Glyph ID the font maps a character to
+    pub fn glyph_index(&self, ch: char) -> Option<u16> {
+        self.face.as_face_ref().glyph_index(ch).map(|g| g.0)
+    }
+
     /// Get number of glyphs in the font
     pub fn number_of_glyphs(&self) -> u16 {
         self.face.as_face_ref().number_of_glyphs()
@@ -192,3 +348,107 @@ impl GlyphUsage {
         self.used_gids.len()
     }
 }
+
+/// A registered font and the family, weight and style it answers to
+#[derive(Clone)]
+pub struct FontFace {
+    pub family: String,
+    pub weight: u16,
+    pub italic: bool,
+    pub font: Font,
+    /// Index of the font in the document (`/F{index + 2}`)
+    pub index: u32,
+}
+
+/// Fonts a paragraph can choose from, matched by family and weight
+#[derive(Clone, Default)]
+pub struct FontSet {
+    faces: Vec<FontFace>,
+}
+
+impl std::fmt::Debug for FontSet {
+    fn fmt(&self, f: &mut std::fmt::Formatter<'_>) -> std::fmt::Result {
+        f.debug_list()
+            .entries(self.faces.iter().map(|face| (&face.family, face.weight, face.italic, face.index)))
+            .finish()
+    }
+}
+
+impl FontSet {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Register a font under the family and weight found in its name and OS/2 tables
+    pub fn add(&mut self, font: &Font, index: u32) {
+        self.add_as(&font.family_name(), font.weight(), font.is_italic(), font, index);
+    }
+
+    /// Register a font under an explicit family, weight and style
+    pub fn add_as(&mut self, family: &str, weight: u16, italic: bool, font: &Font, index: u32) {
+        self.faces.push(FontFace { family: family.to_string(), weight, italic, font: font.clone(), index });
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.faces.is_empty()
+    }
+
+    /// Find the closest face: same family (any family if None or unknown), then the
+    /// matching style, then the nearest weight (heavier wins ties).
+    pub fn resolve(&self, family: Option<&str>, weight: u16, italic: bool) -> Option<&FontFace> {
+        let in_family: Vec<&FontFace> = match family {
+            Some(name) if self.faces.iter().any(|f| f.family.eq_ignore_ascii_case(name)) => {
+                self.faces.iter().filter(|f| f.family.eq_ignore_ascii_case(name)).collect()
+            }
+            _ => self.faces.iter().collect(),
+        };
+
+        in_family.into_iter().min_by_key(|f| {
+            let style_penalty = if f.italic == italic { 0 } else { 10_000 };
+            let distance = (f.weight as i32 - weight as i32).abs() * 2 - (f.weight > weight) as i32;
+            style_penalty + distance
+        })
+    }
+}
+
+/// Collects the horizontal range of outline points below a threshold.
+/// Control points are included, which errs on the side of a wider range.
+struct InkExtent {
+    threshold: f64,
+    range: Option<(f64, f64)>,
+}
+
+impl InkExtent {
+    fn point(&mut self, x: f32, y: f32) {
+        if (y as f64) < self.threshold {
+            let x = x as f64;
+            self.range = Some(match self.range {
+                Some((min, max)) => (min.min(x), max.max(x)),
+                None => (x, x),
+            });
+        }
+    }
+}
+
+impl owned_ttf_parser::OutlineBuilder for InkExtent {
+    fn move_to(&mut self, x: f32, y: f32) {
+        self.point(x, y);
+    }
+
+    fn line_to(&mut self, x: f32, y: f32) {
+        self.point(x, y);
+    }
+
+    fn quad_to(&mut self, x1: f32, y1: f32, x: f32, y: f32) {
+        self.point(x1, y1);
+        self.point(x, y);
+    }
+
+    fn curve_to(&mut self, x1: f32, y1: f32, x2: f32, y2: f32, x: f32, y: f32) {
+        self.point(x1, y1);
+        self.point(x2, y2);
+        self.point(x, y);
+    }
+
+    fn close(&mut self) {}
+}
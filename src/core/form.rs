@@ -0,0 +1,141 @@
+use crate::core::page::Page;
+use crate::core::font::Font;
+use crate::core::layout::{Constraints, LayoutNode, PageContext, Rect};
+use crate::core::transform::Matrix;
+use crate::core::graphics_state::ExtGState;
+use crate::core::writer::PdfObject;
+
+/// Drawing recorded once and written as a Form XObject, which pages place with `Do`.
+/// Content is drawn on `page` with the usual Page methods, in a space of
+/// `width` × `height` points with the origin at the bottom-left corner.
+/// Links added to `page` are not part of the form.
+#[derive(Debug, Clone)]
+pub struct Form {
+    pub width: f64,
+    pub height: f64,
+    pub page: Page,
+}
+
+impl Form {
+    pub fn new(width: f64, height: f64) -> Self {
+        Form { width, height, page: Page::new(width, height) }
+    }
+
+    /// Everything drawn on `page` so far, as a form of the page's size
+    pub fn from_page(page: &Page) -> Self {
+        Form { width: page.width as f64, height: page.height as f64, page: page.clone() }
+    }
+
+    /// A layout tree rendered into a `width` × `height` box
+    pub fn from_layout(node: &dyn LayoutNode, width: f64, height: f64, font: &Font, font_index: u32, context: &PageContext) -> Self {
+        let mut form = Form::new(width, height);
+        let area = Rect { x: 0.0, y: height, width, height };
+        node.render(&mut form.page, area, font, font_index, context);
+        form
+    }
+
+    /// A layout tree measured at up to `max_width` and rendered at its natural size
+    pub fn from_layout_measured(node: &dyn LayoutNode, max_width: f64, font: &Font, font_index: u32) -> Self {
+        let size = node.measure(Constraints::loose(max_width, f64::INFINITY), font);
+        Form::from_layout(node, size.width, size.height, font, font_index, &PageContext::default())
+    }
+
+    /// `from_layout` for a node repeated on every page of a `total`-page flow, when it
+    /// draws the same on each of those pages. None for page numbers and links, which
+    /// have to be drawn on each page.
+    pub fn from_static_layout(node: &dyn LayoutNode, width: f64, height: f64, font: &Font, font_index: u32, total: usize) -> Option<Self> {
+        let first = Form::from_layout(node, width, height, font, font_index, &PageContext { current: 1, total });
+        if !first.page.links.is_empty() {
+            return None;
+        }
+        (2..=total)
+            .all(|current| Form::from_layout(node, width, height, font, font_index, &PageContext { current, total }).page.content == first.page.content)
+            .then_some(first)
+    }
+
+    /// Transparency states, shadings and color spaces used by the content, as direct
+    /// objects for forms written on their own (SVG images). ICC-based colors fall back
+    /// to their device space.
+    pub fn inline_resources(&self) -> Vec<(String, PdfObject)> {
+        let mut resources = Vec::new();
+        let mut states: Vec<ExtGState> = self.page.used_ext_gstates.iter().copied().collect();
+        states.sort();
+        if !states.is_empty() {
+            let entries = states.iter().map(|s| (s.name(), s.to_pdf_object())).collect();
+            resources.push(("ExtGState".to_string(), PdfObject::Dictionary(entries)));
+        }
+        if !self.page.shadings.is_empty() {
+            let entries = self.page.shadings.iter().enumerate()
+                .map(|(i, s)| (format!("Sh{}", i), s.to_pdf_object()))
+                .collect();
+            resources.push(("Shading".to_string(), PdfObject::Dictionary(entries)));
+        }
+        if !self.page.used_color_spaces.is_empty() {
+            let entries = self.page.used_color_spaces.iter()
+                .map(|(name, space)| (name.clone(), space.to_pdf_object(None)))
+                .collect();
+            resources.push(("ColorSpace".to_string(), PdfObject::Dictionary(entries)));
+        }
+        resources
+    }
+
+    /// The Form XObject stream; `matrix` maps the form's space to the space it is drawn in
+    pub fn to_pdf_object(&self, matrix: &Matrix, resources: Vec<(String, PdfObject)>) -> PdfObject {
+        let m = [matrix.a, matrix.b, matrix.c, matrix.d, matrix.e, matrix.f];
+        PdfObject::Stream(vec![
+            ("Type".to_string(), PdfObject::Name("XObject".to_string())),
+            ("Subtype".to_string(), PdfObject::Name("Form".to_string())),
+            ("BBox".to_string(), PdfObject::Array(vec![
+                PdfObject::Integer(0),
+                PdfObject::Integer(0),
+                PdfObject::Real(self.width),
+                PdfObject::Real(self.height),
+            ])),
+            ("Matrix".to_string(), PdfObject::Array(m.iter().map(|v| PdfObject::Real(*v)).collect())),
+            ("Resources".to_string(), PdfObject::Dictionary(resources)),
+        ], self.page.content.clone())
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::document::Document;
+    use crate::core::layout::PageNumberNode;
+    use crate::core::text::TextAlign;
+
+    fn roboto() -> Font {
+        Font::from_bytes(include_bytes!("../../Roboto-Regular.ttf").to_vec(), "Roboto".to_string()).unwrap()
+    }
+
+    fn header(format: &str) -> PageNumberNode {
+        PageNumberNode { format: format.to_string(), size: 10.0, align: TextAlign::Left }
+    }
+
+    #[test]
+    fn test_static_header_drawn_as_one_form() {
+        let font = roboto();
+        let mut doc = Document::new();
+        let font_index = doc.add_font(&font);
+        let form = Form::from_static_layout(&header("Annual report"), 400.0, 20.0, &font, font_index, 3).unwrap();
+        let index = doc.add_form(&form);
+        for _ in 0..3 {
+            let mut page = Page::new(500.0, 500.0);
+            page.draw_form(index, 50.0, 470.0);
+            doc.add_page(&page).unwrap();
+        }
+
+        let mut out = std::io::Cursor::new(Vec::new());
+        doc.write_to_writer(&mut out).unwrap();
+        let pdf = String::from_utf8_lossy(out.get_ref());
+        assert_eq!(pdf.matches("/Subtype /Form").count(), 1);
+        assert_eq!(pdf.matches(" Do").count(), 3);
+    }
+
+    #[test]
+    fn test_page_numbered_header_not_a_form() {
+        let font = roboto();
+        assert!(Form::from_static_layout(&header("Page {page} of {total}"), 400.0, 20.0, &font, 0, 3).is_none());
+        assert!(Form::from_static_layout(&header("{total} pages"), 400.0, 20.0, &font, 0, 3).is_some());
+    }
+}
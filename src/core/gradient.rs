@@ -0,0 +1,230 @@
+use serde::{Deserialize, Serialize};
+use crate::core::color::{Color, ColorSpace};
+use crate::core::writer::PdfObject;
+
+/// A color at a position along a gradient, 0 at the start and 1 at the end
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct ColorStop {
+    pub offset: f64,
+    pub color: Color,
+}
+
+/// A smooth color transition filling a shape, placed relative to the box it fills.
+/// Stop colors are painted opaque; their alpha is ignored. Gradients between gray
+/// or between CMYK stops stay in that space, any other mix is drawn in RGB.
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum Gradient {
+    /// Colors change along a line through the box center, as in CSS `linear-gradient`:
+    /// `angle` 0 runs bottom to top, 90 left to right (the default 180 runs top to bottom)
+    Linear {
+        #[serde(default = "default_angle")]
+        angle: f64,
+        stops: Vec<ColorStop>,
+    },
+    /// Colors change outward from a center given as fractions of the box from its
+    /// top-left corner; `radius` (points) defaults to the distance to the farthest corner
+    Radial {
+        #[serde(default = "default_center")]
+        center_x: f64,
+        #[serde(default = "default_center")]
+        center_y: f64,
+        #[serde(default)]
+        radius: Option<f64>,
+        stops: Vec<ColorStop>,
+    },
+}
+
+fn default_angle() -> f64 { 180.0 }
+fn default_center() -> f64 { 0.5 }
+
+impl Gradient {
+    pub fn linear(angle: f64, stops: Vec<ColorStop>) -> Self {
+        Gradient::Linear { angle, stops }
+    }
+
+    pub fn radial(stops: Vec<ColorStop>) -> Self {
+        Gradient::Radial { center_x: 0.5, center_y: 0.5, radius: None, stops }
+    }
+
+    pub fn stops(&self) -> &[ColorStop] {
+        match self {
+            Gradient::Linear { stops, .. } | Gradient::Radial { stops, .. } => stops,
+        }
+    }
+
+    /// The shading that paints this gradient over the box with bottom-left corner (x, y)
+    pub fn shading(&self, x: f64, y: f64, width: f64, height: f64) -> Shading {
+        let (cx, cy) = (x + width / 2.0, y + height / 2.0);
+        match self {
+            Gradient::Linear { angle, stops } => {
+                let (dx, dy) = (angle.to_radians().sin(), angle.to_radians().cos());
+                // Long enough for the corners to reach the first and last stop
+                let half = (width * dx.abs() + height * dy.abs()) / 2.0;
+                Shading {
+                    shading_type: 2,
+                    coords: vec![cx - dx * half, cy - dy * half, cx + dx * half, cy + dy * half],
+                    stops: normalize_stops(stops),
+                }
+            }
+            Gradient::Radial { center_x, center_y, radius, stops } => {
+                let (px, py) = (x + center_x * width, y + height - center_y * height);
+                let farthest = [(x, y), (x + width, y), (x, y + height), (x + width, y + height)]
+                    .iter()
+                    .map(|(corner_x, corner_y)| (corner_x - px).hypot(corner_y - py))
+                    .fold(0.0, f64::max);
+                Shading {
+                    shading_type: 3,
+                    coords: vec![px, py, 0.0, px, py, radius.unwrap_or(farthest)],
+                    stops: normalize_stops(stops),
+                }
+            }
+        }
+    }
+}
+
+/// Stops sorted and clamped to 0..1, with the end colors extended to 0 and 1
+fn normalize_stops(stops: &[ColorStop]) -> Vec<ColorStop> {
+    let mut sorted: Vec<ColorStop> = stops.iter()
+        .map(|s| ColorStop { offset: s.offset.clamp(0.0, 1.0), color: s.color.clone() })
+        .collect();
+    sorted.sort_by(|a, b| a.offset.total_cmp(&b.offset));
+    match (sorted.first().cloned(), sorted.last().cloned()) {
+        (Some(first), Some(last)) => {
+            if first.offset > 0.0 {
+                sorted.insert(0, ColorStop { offset: 0.0, ..first });
+            }
+            if last.offset < 1.0 || sorted.len() == 1 {
+                sorted.push(ColorStop { offset: 1.0, ..last });
+            }
+            sorted
+        }
+        _ => vec![
+            ColorStop { offset: 0.0, color: Color::black() },
+            ColorStop { offset: 1.0, color: Color::black() },
+        ],
+    }
+}
+
+/// A gradient placed on a page: an axial (type 2) or radial (type 3) shading in page coordinates
+#[derive(Debug, Clone, PartialEq)]
+pub struct Shading {
+    pub shading_type: i64,
+    pub coords: Vec<f64>,
+    /// At least two stops, from offset 0 to offset 1
+    pub stops: Vec<ColorStop>,
+}
+
+impl Shading {
+    /// A shading of `shading_type` 2 (coords `[x0 y0 x1 y1]`) or 3 (`[x0 y0 r0 x1 y1 r1]`)
+    /// in the coordinates of whatever it is painted in
+    pub fn new(shading_type: i64, coords: Vec<f64>, stops: &[ColorStop]) -> Self {
+        Shading { shading_type, coords, stops: normalize_stops(stops) }
+    }
+
+    /// The device space shared by all stops, and the components of a stop in it
+    fn device_space(&self) -> (&'static str, fn(&Color) -> Vec<f64>) {
+        if self.stops.iter().all(|s| s.color.space == ColorSpace::Gray) {
+            ("DeviceGray", |c| vec![c.r])
+        } else if self.stops.iter().all(|s| matches!(s.color.space, ColorSpace::Cmyk { .. })) {
+            ("DeviceCMYK", |c| match c.space {
+                ColorSpace::Cmyk { c, m, y, k } => vec![c, m, y, k],
+                _ => vec![0.0; 4],
+            })
+        } else {
+            ("DeviceRGB", |c| vec![c.r, c.g, c.b])
+        }
+    }
+
+    pub fn to_pdf_object(&self) -> PdfObject {
+        let (space, components) = self.device_space();
+        let color = |c: &Color| PdfObject::Array(components(c).into_iter().map(PdfObject::Real).collect());
+        // One exponential interpolation per pair of neighboring stops
+        let pieces: Vec<PdfObject> = self.stops.windows(2)
+            .map(|pair| PdfObject::Dictionary(vec![
+                ("FunctionType".to_string(), PdfObject::Integer(2)),
+                ("Domain".to_string(), PdfObject::Array(vec![PdfObject::Integer(0), PdfObject::Integer(1)])),
+                ("C0".to_string(), color(&pair[0].color)),
+                ("C1".to_string(), color(&pair[1].color)),
+                ("N".to_string(), PdfObject::Integer(1)),
+            ]))
+            .collect();
+        let function = if pieces.len() == 1 {
+            pieces.into_iter().next().unwrap()
+        } else {
+            // Stitch the pieces together at the inner stop offsets
+            let bounds = self.stops[1..self.stops.len() - 1].iter().map(|s| PdfObject::Real(s.offset)).collect();
+            let encode = pieces.iter().flat_map(|_| [PdfObject::Integer(0), PdfObject::Integer(1)]).collect();
+            PdfObject::Dictionary(vec![
+                ("FunctionType".to_string(), PdfObject::Integer(3)),
+                ("Domain".to_string(), PdfObject::Array(vec![PdfObject::Integer(0), PdfObject::Integer(1)])),
+                ("Functions".to_string(), PdfObject::Array(pieces)),
+                ("Bounds".to_string(), PdfObject::Array(bounds)),
+                ("Encode".to_string(), PdfObject::Array(encode)),
+            ])
+        };
+
+        PdfObject::Dictionary(vec![
+            ("ShadingType".to_string(), PdfObject::Integer(self.shading_type)),
+            ("ColorSpace".to_string(), PdfObject::Name(space.to_string())),
+            ("Coords".to_string(), PdfObject::Array(self.coords.iter().map(|c| PdfObject::Real(*c)).collect())),
+            ("Function".to_string(), function),
+            ("Extend".to_string(), PdfObject::Array(vec![PdfObject::Boolean(true), PdfObject::Boolean(true)])),
+        ])
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn stop(offset: f64, color: Color) -> ColorStop {
+        ColorStop { offset, color }
+    }
+
+    fn pdf(object: &PdfObject) -> String {
+        let mut out = Vec::new();
+        object.serialize(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_normalize_stops_sorts_clamps_and_pads() {
+        let stops = normalize_stops(&[stop(0.8, Color::blue()), stop(0.2, Color::red()), stop(1.5, Color::green())]);
+        let offsets: Vec<f64> = stops.iter().map(|s| s.offset).collect();
+        assert_eq!(offsets, vec![0.0, 0.2, 0.8, 1.0]);
+        assert_eq!(stops[0].color, Color::red());
+        assert_eq!(stops[3].color, Color::green());
+
+        // Inner stops get their end colors extended to 0 and 1
+        let stops = normalize_stops(&[stop(0.3, Color::red()), stop(0.6, Color::blue())]);
+        assert_eq!(stops, vec![stop(0.0, Color::red()), stop(0.3, Color::red()), stop(0.6, Color::blue()), stop(1.0, Color::blue())]);
+
+        // A single stop is a flat color; no stops are black
+        assert_eq!(normalize_stops(&[stop(0.0, Color::red())]), vec![stop(0.0, Color::red()), stop(1.0, Color::red())]);
+        assert_eq!(normalize_stops(&[]), vec![stop(0.0, Color::black()), stop(1.0, Color::black())]);
+    }
+
+    #[test]
+    fn test_shading_pdf_object() {
+        let two = Shading::new(2, vec![0.0, 0.0, 100.0, 0.0], &[stop(0.0, Color::red()), stop(1.0, Color::blue())]);
+        assert_eq!(
+            pdf(&two.to_pdf_object()),
+            "<< /ShadingType 2 /ColorSpace /DeviceRGB /Coords [0 0 100 0] \
+             /Function << /FunctionType 2 /Domain [0 1] /C0 [1 0 0] /C1 [0 0 1] /N 1 >> /Extend [true true] >>",
+        );
+
+        // More stops are stitched at the inner offsets, in the stops' shared device space
+        let gray = Shading::new(3, vec![5.0, 5.0, 0.0, 5.0, 5.0, 10.0], &[stop(0.0, Color::gray(0.0)), stop(0.25, Color::gray(0.5)), stop(1.0, Color::gray(1.0))]);
+        let object = pdf(&gray.to_pdf_object());
+        assert!(object.starts_with("<< /ShadingType 3 /ColorSpace /DeviceGray /Coords [5 5 0 5 5 10]"));
+        assert!(object.contains("/FunctionType 3"));
+        assert!(object.contains("/Bounds [0.25] /Encode [0 1 0 1]"));
+        assert!(object.contains("/C0 [0.5] /C1 [1]"));
+
+        let cmyk = Shading::new(2, vec![0.0; 4], &[stop(0.0, Color::cmyk(1.0, 0.0, 0.0, 0.0)), stop(1.0, Color::cmyk(0.0, 0.0, 0.0, 1.0))]);
+        assert!(pdf(&cmyk.to_pdf_object()).contains("/ColorSpace /DeviceCMYK"));
+        let mixed = Shading::new(2, vec![0.0; 4], &[stop(0.0, Color::gray(0.5)), stop(1.0, Color::cmyk(0.0, 0.0, 0.0, 1.0))]);
+        assert!(pdf(&mixed.to_pdf_object()).contains("/ColorSpace /DeviceRGB"));
+    }
+}
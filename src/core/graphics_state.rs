@@ -0,0 +1,130 @@
+use serde::{Deserialize, Serialize};
+use crate::core::writer::PdfObject;
+use crate::core::parse::ParseValueError;
+use std::str::FromStr;
+
+/// How painted colors combine with what is already on the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum BlendMode {
+    #[default]
+    Normal,
+    Multiply,
+    Screen,
+    Overlay,
+    Darken,
+    Lighten,
+    ColorDodge,
+    ColorBurn,
+    HardLight,
+    SoftLight,
+    Difference,
+    Exclusion,
+    Hue,
+    Saturation,
+    Color,
+    Luminosity,
+}
+
+impl FromStr for BlendMode {
+    type Err = ParseValueError;
+
+    /// Accepts CSS names ("multiply", "color-dodge") as well as PDF names ("ColorDodge")
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().replace(['-', '_', ' '], "").as_str() {
+            "multiply" => Self::Multiply,
+            "screen" => Self::Screen,
+            "overlay" => Self::Overlay,
+            "darken" => Self::Darken,
+            "lighten" => Self::Lighten,
+            "colordodge" => Self::ColorDodge,
+            "colorburn" => Self::ColorBurn,
+            "hardlight" => Self::HardLight,
+            "softlight" => Self::SoftLight,
+            "difference" => Self::Difference,
+            "exclusion" => Self::Exclusion,
+            "hue" => Self::Hue,
+            "saturation" => Self::Saturation,
+            "color" => Self::Color,
+            "luminosity" => Self::Luminosity,
+            "normal" => Self::Normal,
+            _ => return Err(ParseValueError::new("blend mode", s)),
+        })
+    }
+}
+
+impl BlendMode {
+    /// Name of the mode in a `/BM` entry
+    pub fn pdf_name(self) -> &'static str {
+        match self {
+            Self::Normal => "Normal",
+            Self::Multiply => "Multiply",
+            Self::Screen => "Screen",
+            Self::Overlay => "Overlay",
+            Self::Darken => "Darken",
+            Self::Lighten => "Lighten",
+            Self::ColorDodge => "ColorDodge",
+            Self::ColorBurn => "ColorBurn",
+            Self::HardLight => "HardLight",
+            Self::SoftLight => "SoftLight",
+            Self::Difference => "Difference",
+            Self::Exclusion => "Exclusion",
+            Self::Hue => "Hue",
+            Self::Saturation => "Saturation",
+            Self::Color => "Color",
+            Self::Luminosity => "Luminosity",
+        }
+    }
+}
+
+/// Transparency parameters written as an `/ExtGState` resource.
+/// Opacities are kept in thousandths so equal states compare equal and share one object.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ExtGState {
+    pub fill_opacity: u16,
+    pub stroke_opacity: u16,
+    pub blend_mode: BlendMode,
+}
+
+impl Default for ExtGState {
+    fn default() -> Self {
+        ExtGState { fill_opacity: 1000, stroke_opacity: 1000, blend_mode: BlendMode::Normal }
+    }
+}
+
+fn thousandths(opacity: f64) -> u16 {
+    (opacity.clamp(0.0, 1.0) * 1000.0).round() as u16
+}
+
+impl ExtGState {
+    pub fn new(fill_opacity: f64, stroke_opacity: f64, blend_mode: BlendMode) -> Self {
+        ExtGState { fill_opacity: thousandths(fill_opacity), stroke_opacity: thousandths(stroke_opacity), blend_mode }
+    }
+
+    /// This state with color alphas multiplied into its opacities
+    pub fn with_alpha(self, fill: Option<f64>, stroke: Option<f64>) -> Self {
+        let scale = |opacity: u16, alpha: Option<f64>| match alpha {
+            Some(a) => thousandths(opacity as f64 / 1000.0 * a),
+            None => opacity,
+        };
+        ExtGState {
+            fill_opacity: scale(self.fill_opacity, fill),
+            stroke_opacity: scale(self.stroke_opacity, stroke),
+            ..self
+        }
+    }
+
+    /// Resource name, derived from the values so every page and document agrees on it
+    pub fn name(&self) -> String {
+        format!("GS{}_{}_{}", self.fill_opacity, self.stroke_opacity, self.blend_mode as u8)
+    }
+
+    pub fn to_pdf_object(&self) -> PdfObject {
+        PdfObject::Dictionary(vec![
+            ("Type".to_string(), PdfObject::Name("ExtGState".to_string())),
+            ("ca".to_string(), PdfObject::Real(self.fill_opacity as f64 / 1000.0)),
+            ("CA".to_string(), PdfObject::Real(self.stroke_opacity as f64 / 1000.0)),
+            ("BM".to_string(), PdfObject::Name(self.blend_mode.pdf_name().to_string())),
+        ])
+    }
+}
@@ -1,6 +1,9 @@
 use std::io::{self, Error, ErrorKind};
 use image::{ImageFormat, GenericImageView};
 use std::fs;
+use crate::core::font::Font;
+use crate::core::form::Form;
+use crate::core::svg;
 
 #[derive(Debug, Clone)]
 pub struct Image {
@@ -10,17 +13,31 @@ pub struct Image {
     pub bits_per_component: u8,
     pub data: Vec<u8>,
     pub filter: Option<String>,
+    pub alpha: Option<Vec<u8>>, // 8-bit soft mask, one byte per pixel
+    pub form: Option<Box<Form>>, // vector content (SVG) written as a Form XObject instead of pixels
 }
 
 impl Image {
     /// Load an image from a file path
-    /// Supports JPEG (passed through) and PNG (decompressed to raw RGB)
+    /// Supports JPEG (passed through), PNG (decompressed to raw RGB) and SVG (kept as vectors)
     pub fn from_file(path: &str) -> io::Result<Self> {
         let bytes = fs::read(path)?;
         Self::from_bytes(&bytes)
     }
 
+    /// Size in points when drawn unscaled: an SVG's own size, or one point per pixel
+    pub fn natural_size(&self) -> (f64, f64) {
+        match &self.form {
+            Some(form) => (form.width, form.height),
+            None => (self.width as f64, self.height as f64),
+        }
+    }
+
     pub fn from_bytes(data: &[u8]) -> io::Result<Self> {
+        if svg::is_svg(data) {
+            return Self::from_svg(data, &[]);
+        }
+
         // Use image crate to guess format
         let format = image::guess_format(data)
             .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Unknown image format: {}", e)))?;
@@ -28,10 +45,26 @@ impl Image {
         match format {
             ImageFormat::Jpeg => Self::load_jpeg(data),
             ImageFormat::Png => Self::load_png(data),
-            _ => Err(Error::new(ErrorKind::Unsupported, "Only JPEG and PNG are supported")),
+            _ => Err(Error::new(ErrorKind::Unsupported, "Only JPEG, PNG and SVG are supported")),
         }
     }
 
+    /// Load an SVG as vector graphics, sized in points by its width and height.
+    /// `<text>` is drawn with `fonts`, the first being the default family.
+    pub fn from_svg(data: &[u8], fonts: &[Font]) -> io::Result<Self> {
+        let form = svg::svg_to_form(data, fonts)?;
+        Ok(Image {
+            width: form.width.ceil() as u32,
+            height: form.height.ceil() as u32,
+            color_space: "DeviceRGB".to_string(),
+            bits_per_component: 8,
+            data: Vec::new(),
+            filter: None,
+            alpha: None,
+            form: Some(Box::new(form)),
+        })
+    }
+
     fn load_jpeg(data: &[u8]) -> io::Result<Self> {
         // For JPEG, we just read metadata and pass raw bytes (DCTDecode)
         let img = image::load_from_memory_with_format(data, ImageFormat::Jpeg)
@@ -53,6 +86,8 @@ impl Image {
             bits_per_component: 8,
             data: data.to_vec(),
             filter: Some("DCTDecode".to_string()),
+            alpha: None,
+            form: None,
         })
     }
 
@@ -66,6 +101,13 @@ impl Image {
         let (width, height) = img.dimensions();
         let raw_pixels = img.to_rgb8().into_raw();
         
+        // Keep transparency as a soft mask (emoji strikes and logos rely on it)
+        let alpha = if img.color().has_alpha() {
+            Some(img.to_rgba8().pixels().map(|p| p.0[3]).collect())
+        } else {
+            None
+        };
+        
         Ok(Image {
             width,
             height,
@@ -73,6 +115,8 @@ impl Image {
             bits_per_component: 8,
             data: raw_pixels,
             filter: Some("FlateDecode".to_string()), // We will compress this when writing
+            alpha,
+            form: None,
         })
     }
 }
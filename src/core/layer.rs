@@ -0,0 +1,140 @@
+use serde::{Deserialize, Serialize};
+use crate::core::writer::PdfObject;
+
+/// A named optional content group: content placed on it can be shown, hidden
+/// and printed separately in viewers that list the document's layers
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Layer {
+    pub name: String,
+    /// Shown when the document is opened
+    #[serde(default = "default_visible")]
+    pub visible: bool,
+    /// Whether the layer prints regardless of its on-screen state, e.g. false for
+    /// screen-only notes and true for print-only marks
+    #[serde(default)]
+    pub print: Option<bool>,
+    /// Whether the layer is shown on screen regardless of `visible`
+    #[serde(default)]
+    pub view: Option<bool>,
+    /// Layers sharing a group name are exclusive, like alternate languages:
+    /// turning one on turns the others off
+    #[serde(default)]
+    pub group: Option<String>,
+}
+
+fn default_visible() -> bool { true }
+
+impl Layer {
+    pub fn new(name: &str) -> Self {
+        Layer { name: name.to_string(), visible: true, print: None, view: None, group: None }
+    }
+
+    /// The OCG dictionary
+    pub fn to_pdf_object(&self) -> PdfObject {
+        let state = |on: bool| PdfObject::Name(if on { "ON" } else { "OFF" }.to_string());
+        let mut usage = Vec::new();
+        if let Some(print) = self.print {
+            usage.push(("Print".to_string(), PdfObject::Dictionary(vec![
+                ("PrintState".to_string(), state(print)),
+            ])));
+        }
+        if let Some(view) = self.view {
+            usage.push(("View".to_string(), PdfObject::Dictionary(vec![
+                ("ViewState".to_string(), state(view)),
+            ])));
+        }
+        let mut dict = vec![
+            ("Type".to_string(), PdfObject::Name("OCG".to_string())),
+            ("Name".to_string(), PdfObject::String(self.name.clone())),
+        ];
+        if !usage.is_empty() {
+            dict.push(("Usage".to_string(), PdfObject::Dictionary(usage)));
+        }
+        PdfObject::Dictionary(dict)
+    }
+}
+
+/// The catalog's /OCProperties for `layers` written as objects `ids`: the default
+/// configuration lists them in order with their initial state, and applies print and
+/// view usage automatically when printing or viewing
+pub fn oc_properties(layers: &[Layer], ids: &[u32]) -> PdfObject {
+    let refs = |filter: &dyn Fn(&Layer) -> bool| -> Vec<PdfObject> {
+        layers.iter().zip(ids).filter(|(layer, _)| filter(layer)).map(|(_, id)| PdfObject::Reference(*id)).collect()
+    };
+    let mut config = vec![
+        ("Order".to_string(), PdfObject::Array(refs(&|_| true))),
+        ("ON".to_string(), PdfObject::Array(refs(&|l| l.visible))),
+        ("OFF".to_string(), PdfObject::Array(refs(&|l| !l.visible))),
+    ];
+
+    let mut groups: Vec<&String> = Vec::new();
+    for group in layers.iter().filter_map(|l| l.group.as_ref()) {
+        if !groups.contains(&group) {
+            groups.push(group);
+        }
+    }
+    if !groups.is_empty() {
+        let arrays = groups.iter()
+            .map(|group| PdfObject::Array(refs(&|l| l.group.as_ref() == Some(*group))))
+            .collect();
+        config.push(("RBGroups".to_string(), PdfObject::Array(arrays)));
+    }
+
+    let mut auto_states = Vec::new();
+    for (event, used) in [("Print", refs(&|l| l.print.is_some())), ("View", refs(&|l| l.view.is_some()))] {
+        if !used.is_empty() {
+            auto_states.push(PdfObject::Dictionary(vec![
+                ("Event".to_string(), PdfObject::Name(event.to_string())),
+                ("OCGs".to_string(), PdfObject::Array(used)),
+                ("Category".to_string(), PdfObject::Array(vec![PdfObject::Name(event.to_string())])),
+            ]));
+        }
+    }
+    if !auto_states.is_empty() {
+        config.push(("AS".to_string(), PdfObject::Array(auto_states)));
+    }
+
+    PdfObject::Dictionary(vec![
+        ("OCGs".to_string(), PdfObject::Array(refs(&|_| true))),
+        ("D".to_string(), PdfObject::Dictionary(config)),
+    ])
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pdf(object: &PdfObject) -> String {
+        let mut out = Vec::new();
+        object.serialize(&mut out).unwrap();
+        String::from_utf8(out).unwrap()
+    }
+
+    #[test]
+    fn test_oc_properties_plain_layers() {
+        let hidden = Layer { visible: false, ..Layer::new("Notes") };
+        assert_eq!(
+            pdf(&oc_properties(&[Layer::new("Art"), hidden], &[4, 5])),
+            "<< /OCGs [4 0 R 5 0 R] /D << /Order [4 0 R 5 0 R] /ON [4 0 R] /OFF [5 0 R] >> >>",
+        );
+    }
+
+    #[test]
+    fn test_oc_properties_groups_and_auto_states() {
+        let group = |name: &str, lang: &str| Layer { group: Some(lang.to_string()), ..Layer::new(name) };
+        let layers = [
+            group("English", "language"),
+            Layer { print: Some(false), ..Layer::new("Screen notes") },
+            group("French", "language"),
+            Layer { print: Some(true), view: Some(false), ..group("Crop marks", "marks") },
+        ];
+        let object = pdf(&oc_properties(&layers, &[10, 11, 12, 13]));
+
+        // One radio-button group per group name, in first-use order
+        assert!(object.contains("/RBGroups [[10 0 R 12 0 R] [13 0 R]]"));
+        assert!(object.contains(
+            "/AS [<< /Event /Print /OCGs [11 0 R 13 0 R] /Category [/Print] >> \
+             << /Event /View /OCGs [13 0 R] /Category [/View] >>]"
+        ));
+    }
+}
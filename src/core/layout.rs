@@ -1,8 +1,16 @@
 use crate::core::page::Page;
-use crate::core::font::Font;
-use crate::core::text;
+use crate::core::font::{Font, FontSet};
+use crate::core::color::Color;
+use crate::core::text::{self, Hyphenation, LineBreakOptions, LineBreaker, LineHeight, LineMetrics, TabStop, TextAlign, TextDecoration, TextDirection, TextFit, TextOverflow, TextStyle, WhiteSpace, WritingMode};
 use crate::core::table::Table;
+use crate::core::path::{FillRule, Path};
+use crate::core::transform::Matrix;
 use std::sync::Arc;
+use unicode_bidi::{BidiInfo, Level};
+use serde::{Deserialize, Serialize};
+use std::convert::Infallible;
+use crate::core::parse::ParseValueError;
+use std::str::FromStr;
 
 #[derive(Debug, Clone, Copy)]
 pub struct PageContext {
@@ -80,6 +88,23 @@ pub trait LayoutNode {
 
     /// Draw the node onto the page within the given area.
     fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, context: &PageContext);
+
+    /// Stay on the same page as the node that follows.
+    fn keep_with_next(&self) -> bool {
+        false
+    }
+
+    /// Split at the top of a page, where pushing gains no room.
+    /// Keep rules that would leave the page empty are relaxed here.
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.split(available_width, available_height, font)
+    }
+
+    /// Distance from the top of the node down to the baseline of its first line of text,
+    /// used to line up list markers. None for nodes without text.
+    fn first_baseline(&self, _width: f64, _font: &Font) -> Option<f64> {
+        None
+    }
 }
 
 // --- Components ---
@@ -93,14 +118,17 @@ pub enum FlexAlign {
     Stretch,
 }
 
-impl FlexAlign {
-    pub fn from_str(s: &str) -> Self {
-        match s {
+impl FromStr for FlexAlign {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
             "center" => Self::Center,
             "flex-end" | "end" => Self::End,
             "stretch" => Self::Stretch,
-            _ => Self::Start,
-        }
+            "start" | "flex-start" => Self::Start,
+            _ => return Err(ParseValueError::new("flex alignment", s)),
+        })
     }
 }
 
@@ -115,16 +143,19 @@ pub enum FlexJustify {
     SpaceEvenly,
 }
 
-impl FlexJustify {
-    pub fn from_str(s: &str) -> Self {
-        match s {
+impl FromStr for FlexJustify {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
             "center" => Self::Center,
             "flex-end" | "end" => Self::End,
             "space-between" => Self::SpaceBetween,
             "space-around" => Self::SpaceAround,
             "space-evenly" => Self::SpaceEvenly,
-            _ => Self::Start,
-        }
+            "start" | "flex-start" => Self::Start,
+            _ => return Err(ParseValueError::new("flex justification", s)),
+        })
     }
 }
 
@@ -212,6 +243,26 @@ impl LayoutNode for Column {
     }
 
     fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.split_children(available_width, available_height, font, false)
+    }
+
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.split_children(available_width, available_height, font, true)
+    }
+
+    fn keep_with_next(&self) -> bool {
+        self.children.last().is_some_and(|c| c.keep_with_next())
+    }
+
+    fn first_baseline(&self, width: f64, font: &Font) -> Option<f64> {
+        self.children.first()?.first_baseline(width, font)
+    }
+}
+
+impl Column {
+    /// `at_page_top` lets the first child ignore its keep rules, so an oversized
+    /// kept block still breaks instead of overflowing the page.
+    fn split_children(&self, available_width: f64, available_height: f64, font: &Font, at_page_top: bool) -> SplitAction {
         let mut used_height = 0.0;
         let mut split_index = None;
         let mut split_node_parts = None; // (Head, Tail) if a node splits
@@ -241,7 +292,12 @@ impl LayoutNode for Column {
                     break;
                 }
 
-                match child.split(available_width, remaining_height, font) {
+                let action = if at_page_top && i == 0 {
+                    child.split_at_page_top(available_width, remaining_height, font)
+                } else {
+                    child.split(available_width, remaining_height, font)
+                };
+                match action {
                     SplitAction::Fit => {
                          used_height += spacing + size.height;
                     },
@@ -263,7 +319,15 @@ impl LayoutNode for Column {
             }
         }
 
-        if let Some(idx) = split_index {
+        if let Some(mut idx) = split_index {
+            if split_node_parts.is_none() {
+                // Children that keep with the pushed child follow it to the next page
+                let first = if at_page_top { 1 } else { 0 };
+                while idx > first && self.children[idx - 1].keep_with_next() {
+                    idx -= 1;
+                }
+            }
+
             // Create Head Column (children 0..idx, plus potential head part)
             let mut head_children = self.children[0..idx].to_vec();
             
@@ -304,6 +368,8 @@ pub struct Row {
     pub spacing: f64,
     pub align_items: FlexAlign,
     pub justify_content: FlexJustify,
+    /// `Rtl` mirrors child order so the first child sits at the right edge
+    pub direction: TextDirection,
 }
 
 impl LayoutNode for Row {
@@ -369,8 +435,15 @@ impl LayoutNode for Row {
                 FlexAlign::Stretch => area.y,
             };
             
+            // Mirror horizontally for right-to-left rows
+            let child_x = if self.direction.is_rtl() {
+                area.x + area.width - (x - area.x) - size.width
+            } else {
+                x
+            };
+            
             let child_area = Rect {
-                x,
+                x: child_x,
                 y,
                 width: size.width, 
                 height: if matches!(self.align_items, FlexAlign::Stretch) { area.height } else { size.height }, 
@@ -395,61 +468,602 @@ pub struct TextNode {
     pub size: f64,
     pub color: Option<crate::core::color::Color>,
     pub background_color: Option<crate::core::color::Color>,
+    /// Paragraph direction; RTL paragraphs fill the available width and align right
+    pub direction: TextDirection,
+    /// Vertical text is set in columns that are as tall as the height constraint
+    pub writing_mode: WritingMode,
+    /// Whitespace collapsing and wrapping behaviour
+    pub white_space: WhiteSpace,
+    /// Automatic hyphenation for the text's language
+    pub hyphenation: Option<Hyphenation>,
+    /// Line alignment; None aligns to the paragraph's start edge
+    pub align: Option<TextAlign>,
+    /// Fixed box width; lines wrap at this width instead of the available width
+    pub width: Option<f64>,
+    pub line_height: LineHeight,
+    /// Extra space after every character, in points
+    pub letter_spacing: f64,
+    /// Extra space after every space character, in points
+    pub word_spacing: f64,
+    /// Minimum lines left at the bottom of a page when the paragraph breaks
+    pub orphans: usize,
+    /// Minimum lines carried to the top of the next page when the paragraph breaks
+    pub widows: usize,
+    /// Lines after this many are dropped
+    pub max_lines: Option<usize>,
+    /// Handling of text that does not fit the box
+    pub overflow: TextOverflow,
+    /// Shrink the text down to this size until it fits the box
+    pub min_size: Option<f64>,
+    /// Greedy or total-fit (Knuth–Plass) line breaking
+    pub line_breaker: LineBreaker,
+    /// The paragraph goes on in another node; set on the head of a page split
+    pub continues: bool,
+    /// Underline, strikethrough, overline and highlight; horizontal text only
+    pub decoration: TextDecoration,
+    /// Positions tabs in the text advance to, measured from the left edge of each line
+    pub tab_stops: Vec<TabStop>,
+}
+
+impl TextNode {
+    fn line_break_options(&self) -> LineBreakOptions {
+        LineBreakOptions {
+            white_space: self.white_space,
+            hyphenation: self.hyphenation.clone(),
+            letter_spacing: self.letter_spacing,
+            word_spacing: self.word_spacing,
+            breaker: self.line_breaker,
+            continues: self.continues,
+            tab_stops: self.tab_stops.clone(),
+        }
+    }
+
+    fn line_metrics(&self, font: &Font) -> LineMetrics {
+        LineMetrics::new(font, self.size, self.line_height)
+    }
+
+    /// How lines are drawn at `size`, with the direction resolved for the whole paragraph
+    fn text_style<'a>(&self, font: &'a Font, font_index: u32, size: f64) -> TextStyle<'a> {
+        TextStyle {
+            direction: self.direction.resolve(&self.text),
+            align: self.align,
+            line_height: self.line_height,
+            word_spacing: self.word_spacing,
+            letter_spacing: self.letter_spacing,
+            decoration: self.decoration.clone(),
+            ..TextStyle::new(font, font_index, size)
+        }
+    }
+
+    fn text_fit(&self) -> TextFit {
+        TextFit { max_lines: self.max_lines, overflow: self.overflow, min_size: self.min_size }
+    }
+
+    /// A node with the same style showing `text`
+    fn with_text(&self, text: String) -> TextNode {
+        TextNode {
+            text,
+            size: self.size,
+            color: self.color.clone(),
+            background_color: self.background_color.clone(),
+            direction: self.direction,
+            writing_mode: self.writing_mode,
+            white_space: self.white_space,
+            hyphenation: self.hyphenation.clone(),
+            align: self.align,
+            width: self.width,
+            line_height: self.line_height,
+            letter_spacing: self.letter_spacing,
+            word_spacing: self.word_spacing,
+            orphans: self.orphans,
+            widows: self.widows,
+            max_lines: self.max_lines,
+            overflow: self.overflow,
+            min_size: self.min_size,
+            line_breaker: self.line_breaker,
+            continues: self.continues,
+            decoration: self.decoration.clone(),
+            tab_stops: self.tab_stops.clone(),
+        }
+    }
+
+    fn split_lines(&self, available_width: f64, available_height: f64, font: &Font, orphans: usize, widows: usize) -> SplitAction {
+        if self.writing_mode.is_vertical() {
+            return self.split_columns(available_width, available_height, font, orphans, widows);
+        }
+        
+        if self.text_fit().is_fixed() {
+            // Truncated or resized text is laid out for its box and never continues on the next page
+            let size = self.measure(Constraints::loose(available_width, f64::INFINITY), font);
+            return if size.height <= available_height { SplitAction::Fit } else { SplitAction::Push };
+        }
+        
+        let width = self.wrap_width(available_width);
+        let options = self.line_break_options();
+        let fitting = self.line_metrics(font).lines_in(available_height);
+        let total = text::calculate_text_lines(&self.text, width, self.size, font, &options);
+        if fitting >= total {
+            return SplitAction::Fit;
+        }
+
+        // If we can't keep enough lines on either side of the break, Push
+        let max_lines = text::lines_before_break(total, fitting, orphans, widows);
+        if max_lines == 0 {
+            return SplitAction::Push;
+        }
+
+        // text::split_text_at_lines will measure and return (Head, Tail)
+        let (head, tail_opt) = text::split_text_at_lines(&self.text, width, self.size, font, max_lines, &options);
+        
+        if let Some(tail) = tail_opt {
+            // The head keeps the lines chosen for the whole paragraph, last one included
+            let head_node: Arc<dyn LayoutNode> = Arc::new(TextNode { continues: true, ..self.with_text(head) });
+            let tail_node: Arc<dyn LayoutNode> = Arc::new(self.with_text(tail));
+            SplitAction::Split(head_node, tail_node)
+        } else {
+            // Fits completely
+            SplitAction::Fit
+        }
+    }
+
+    /// Width lines are wrapped at when `available` is offered
+    fn wrap_width(&self, available: f64) -> f64 {
+        match self.width {
+            Some(w) => w.min(available),
+            None => available,
+        }
+    }
+
+    /// Vertical text wraps into columns as tall as the space left and keeps
+    /// those that fit across the width; orphans and widows count columns
+    fn split_columns(&self, available_width: f64, available_height: f64, font: &Font, orphans: usize, widows: usize) -> SplitAction {
+        let size = self.measure(Constraints::loose(available_width, f64::INFINITY), font);
+        if size.height <= available_height {
+            return SplitAction::Fit;
+        }
+
+        let leading = self.line_metrics(font).leading;
+        let fitting = if leading > 0.0 { (available_width / leading).floor() as usize } else { usize::MAX };
+        let total = text::wrap_text_vertical(&self.text, available_height, self.size, font).len();
+        let max_columns = text::lines_before_break(total, fitting, orphans, widows);
+        if max_columns == 0 {
+            return SplitAction::Push;
+        }
+
+        // The head keeps its breaks, so it is drawn in the columns chosen here
+        let (head, tail) = text::split_text_at_columns(&self.text, available_height, self.size, font, max_columns);
+        let Some(tail) = tail else {
+            // Every column fits once wrapped to the available height
+            return SplitAction::Fit;
+        };
+        let head_node: Arc<dyn LayoutNode> = Arc::new(self.with_text(head));
+        let tail_node: Arc<dyn LayoutNode> = Arc::new(self.with_text(tail));
+        SplitAction::Split(head_node, tail_node)
+    }
+
+    fn measure_vertical(&self, constraints: Constraints, font: &Font) -> Size {
+        let leading = self.line_metrics(font).leading;
+        let columns = text::wrap_text_vertical(&self.text, constraints.max_height, self.size, font);
+        let height = columns.iter()
+            .map(|c| font.measure_text_vertical(c, self.size))
+            .fold(0.0, f64::max);
+        
+        Size { width: columns.len() as f64 * leading, height }
+    }
 }
 
 impl LayoutNode for TextNode {
     fn measure(&self, constraints: Constraints, font: &Font) -> Size {
-        // Compute raw width of text (unwrapped)
-        let raw_width = font.measure_text(&self.text, self.size);
+        if self.writing_mode.is_vertical() {
+            return self.measure_vertical(constraints, font);
+        }
+        
+        // Compute raw width of text (only mandatory breaks taken)
+        let raw_width = text::max_line_width(&self.text, self.size, font, &self.line_break_options());
         
         // Determine actual width to use
-        let width = if constraints.max_width.is_finite() {
-            raw_width.min(constraints.max_width)
+        let max_width = self.wrap_width(constraints.max_width);
+        let fills_line = match self.align {
+            Some(align) => align != TextAlign::Left,
+            // RTL text hugs the right edge
+            None => self.direction.resolve(&self.text).is_rtl(),
+        };
+        let width = if self.width.is_some() || (fills_line && max_width.is_finite()) {
+            max_width
         } else {
-            raw_width
+            raw_width.min(max_width)
         };
         
-        let lines = text::calculate_text_lines(&self.text, width, self.size, font);
-        let leading = self.size * 1.2;
+        let fit = self.text_fit();
+        if fit.is_fixed() {
+            let options = self.line_break_options();
+            let size = fit.fitted_size(&self.text, width, constraints.max_height, &self.text_style(font, 0, self.size), &options);
+            let lines = fit.lines(&self.text, width, constraints.max_height, &self.text_style(font, 0, size), &options).len().max(1);
+            return Size { width, height: LineMetrics::new(font, size, self.line_height).height(lines) };
+        }
+        
+        let lines = text::calculate_text_lines(&self.text, width, self.size, font, &self.line_break_options());
         
-        Size { width, height: lines as f64 * leading }
+        Size { width, height: self.line_metrics(font).height(lines) }
     }
 
     fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, _context: &PageContext) {
         // Draw background first if specified
         // area.y is TOP of text area, but PDF rectangles use bottom-left coordinates
-        if let Some(bg_color) = self.background_color {
+        if let Some(bg_color) = self.background_color.clone() {
             let bottom_y = area.y - area.height;
             page.draw_rect_filled(area.x, bottom_y, area.width, area.height, bg_color);
         }
         
         // Draw text with color on top of background
-        let color = self.color.unwrap_or(crate::core::color::Color::black());
-        page.text_multiline_colored(self.text.clone(), area.x, area.y, area.width, self.size, font_index, font, color);
+        let color = self.color.clone().unwrap_or(crate::core::color::Color::black());
+        page.set_fill_color(color);
+        if self.writing_mode.is_vertical() {
+            page.text_vertical_multiline(self.text.clone(), area.x, area.y, area.width, area.height, &self.text_style(font, font_index, self.size));
+            return;
+        }
+        
+        let fit = self.text_fit();
+        let options = self.line_break_options();
+        if !fit.is_fixed() {
+            let lines = text::break_lines(&self.text, area.width, self.size, font, &options);
+            page.text_lines(lines, area.x, area.y, area.width, &self.text_style(font, font_index, self.size), &options);
+            return;
+        }
+        
+        let size = fit.fitted_size(&self.text, area.width, area.height, &self.text_style(font, font_index, self.size), &options);
+        let style = self.text_style(font, font_index, size);
+        let lines = fit.lines(&self.text, area.width, area.height, &style, &options);
+        let clip = self.overflow == TextOverflow::Clip;
+        if clip {
+            page.save_state();
+            page.clip_rect(area.x, area.y - area.height, area.width, area.height);
+        }
+        page.text_lines(lines, area.x, area.y, area.width, &style, &options);
+        if clip {
+            page.restore_state();
+        }
     }
 
     fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
-        let leading = self.size * 1.2;
-        let max_lines = (available_height / leading).floor() as usize;
-        
-        // If we can't fit even one line, Push
-        if max_lines == 0 {
+        self.split_lines(available_width, available_height, font, self.orphans, self.widows)
+    }
+
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        // Moving on would not help, so break wherever the page ends
+        self.split_lines(available_width, available_height, font, 1, 1)
+    }
+
+    fn first_baseline(&self, _width: f64, font: &Font) -> Option<f64> {
+        if self.writing_mode.is_vertical() || self.text_fit().is_fixed() {
+            return None;
+        }
+        Some(self.line_metrics(font).baseline_offset())
+    }
+
+}
+
+/// An inline run of text inside a `RichTextNode`.
+/// Unset properties fall back to the paragraph's defaults.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextSpan {
+    pub text: String,
+    pub font_family: Option<String>,
+    /// CSS-style weight, 400 = regular, 700 = bold
+    pub weight: Option<u16>,
+    pub italic: bool,
+    pub size: Option<f64>,
+    pub color: Option<Color>,
+    /// `underline`, `overline`, `line_through`, ... sit directly on the span
+    #[serde(flatten)]
+    pub decoration: TextDecoration,
+    /// URI opened when the span is clicked
+    pub link: Option<String>,
+}
+
+/// A paragraph of differently styled spans that wrap together.
+/// Each line is as tall as its largest span and all spans on a line share one baseline.
+pub struct RichTextNode {
+    pub spans: Vec<TextSpan>,
+    /// Default size for spans without one
+    pub size: f64,
+    /// Default color for spans without one
+    pub color: Option<Color>,
+    /// Fonts spans pick from by family and weight; the render font is used when nothing matches
+    pub fonts: FontSet,
+    pub align: Option<TextAlign>,
+    pub white_space: WhiteSpace,
+    pub hyphenation: Option<Hyphenation>,
+    /// Line height relative to the size of each span
+    pub line_height: LineHeight,
+    /// Extra space after every character, in points
+    pub letter_spacing: f64,
+    /// Extra space after every space character, in points
+    pub word_spacing: f64,
+    /// Minimum lines left at the bottom of a page when the paragraph breaks
+    pub orphans: usize,
+    /// Minimum lines carried to the top of the next page when the paragraph breaks
+    pub widows: usize,
+    /// The paragraph goes on in another node; set on the head of a page split
+    pub continues: bool,
+}
+
+/// One line of a rich paragraph: its source line and the span runs drawn on it
+struct RichLine {
+    line: text::TextLine,
+    runs: Vec<(usize, String, bool)>, // (span index, text, right-to-left) in visual order
+    /// Direction of the paragraph the line belongs to
+    rtl: bool,
+    ascent: f64,
+    height: f64,
+}
+
+impl RichTextNode {
+    fn line_break_options(&self) -> LineBreakOptions {
+        LineBreakOptions {
+            white_space: self.white_space,
+            hyphenation: self.hyphenation.clone(),
+            letter_spacing: self.letter_spacing,
+            word_spacing: self.word_spacing,
+            continues: self.continues,
+            ..Default::default()
+        }
+    }
+
+    /// Concatenated paragraph text and the byte offset each span starts at
+    fn source(&self) -> (String, Vec<usize>) {
+        let mut text = String::new();
+        let mut starts = Vec::with_capacity(self.spans.len());
+        for span in &self.spans {
+            starts.push(text.len());
+            text.push_str(&span.text);
+        }
+        (text, starts)
+    }
+
+    fn span_size(&self, span: &TextSpan) -> f64 {
+        span.size.unwrap_or(self.size)
+    }
+
+    fn span_font<'a>(&'a self, span: &TextSpan, font: &'a Font, font_index: u32) -> (&'a Font, u32) {
+        if span.font_family.is_none() && span.weight.is_none() && !span.italic {
+            return (font, font_index);
+        }
+        let family = span.font_family.clone().unwrap_or_else(|| font.family_name());
+        match self.fonts.resolve(Some(&family), span.weight.unwrap_or(400), span.italic) {
+            Some(face) => (&face.font, face.index),
+            None => (font, font_index),
+        }
+    }
+
+    /// Group the characters of `text[start..end]` into runs of the same span and
+    /// bidi level, in logical order. `levels` has one level per byte of `text`.
+    fn runs(&self, text: &str, starts: &[usize], levels: &[Level], start: usize, end: usize, hyphen: bool) -> Vec<(usize, String, Level)> {
+        let mut runs: Vec<(usize, String, Level)> = Vec::new();
+        for (offset, ch) in text::line_chars(&text[start..end], self.white_space, hyphen, false) {
+            let span = starts.partition_point(|s| *s <= start + offset).saturating_sub(1);
+            let level = levels[start + offset];
+            match runs.last_mut() {
+                Some((idx, run, run_level)) if *idx == span && *run_level == level => run.push(ch),
+                _ => runs.push((span, ch.to_string(), level)),
+            }
+        }
+        runs
+    }
+
+    fn runs_width<T>(&self, runs: &[(usize, String, T)], font: &Font) -> f64 {
+        let spacing = LineBreakOptions { letter_spacing: self.letter_spacing, word_spacing: self.word_spacing, ..Default::default() };
+        runs.iter()
+            .map(|(idx, run, _)| {
+                let span = &self.spans[*idx];
+                let (span_font, _) = self.span_font(span, font, 0);
+                spacing.measure(span_font, run, self.span_size(span))
+            })
+            .sum()
+    }
+
+    fn span_metrics(&self, span: &TextSpan, font: &Font) -> LineMetrics {
+        let (span_font, _) = self.span_font(span, font, 0);
+        LineMetrics::new(span_font, self.span_size(span), self.line_height)
+    }
+
+    fn layout_lines(&self, width: f64, font: &Font) -> Vec<RichLine> {
+        let (text, starts) = self.source();
+        // Levels are resolved once for the whole paragraph, so a direction run may cross spans
+        let bidi = BidiInfo::new(&text, None);
+        let lines = text::break_lines_by(&text, width, &self.line_break_options(), |start, end, hyphen| {
+            self.runs_width(&self.runs(&text, &starts, &bidi.levels, start, end, hyphen), font)
+        });
+
+        lines.into_iter().map(|line| {
+            let para = bidi.paragraphs.iter()
+                .find(|p| p.range.contains(&line.start))
+                .or(bidi.paragraphs.last());
+            let (levels, rtl) = match para {
+                Some(para) => (bidi.reordered_levels(para, line.start..line.next), para.level.is_rtl()),
+                None => (bidi.levels.clone(), false),
+            };
+            let logical = self.runs(&text, &starts, &levels, line.start, line.next, line.hyphenated);
+            let run_levels: Vec<Level> = logical.iter().map(|(_, _, level)| *level).collect();
+            let runs: Vec<(usize, String, bool)> = BidiInfo::reorder_visual(&run_levels)
+                .into_iter()
+                .map(|i| {
+                    let (idx, run, level) = &logical[i];
+                    (*idx, run.clone(), level.is_rtl())
+                })
+                .collect();
+            // Empty lines keep the metrics of the span they sit in
+            let metrics: Vec<LineMetrics> = if runs.is_empty() {
+                let span = starts.partition_point(|s| *s <= line.start).saturating_sub(1);
+                vec![match self.spans.get(span) {
+                    Some(span) => self.span_metrics(span, font),
+                    None => LineMetrics::new(font, self.size, self.line_height),
+                }]
+            } else {
+                runs.iter().map(|(idx, _, _)| self.span_metrics(&self.spans[*idx], font)).collect()
+            };
+            // Line boxes of all runs are aligned on the shared baseline
+            let above = metrics.iter().map(|m| m.baseline_offset()).fold(0.0, f64::max);
+            let below = metrics.iter().map(|m| m.leading - m.baseline_offset()).fold(0.0, f64::max);
+            RichLine { line, runs, rtl, ascent: above, height: above + below }
+        }).collect()
+    }
+
+    fn natural_width(&self, font: &Font) -> f64 {
+        self.layout_lines(f64::INFINITY, font)
+            .iter()
+            .map(|l| self.runs_width(&l.runs, font))
+            .fold(0.0, f64::max)
+    }
+
+    /// Spans covering `range` of the concatenated text
+    fn slice_spans(&self, starts: &[usize], range: std::ops::Range<usize>) -> Vec<TextSpan> {
+        self.spans.iter().zip(starts).filter_map(|(span, start)| {
+            let from = range.start.max(*start);
+            let to = range.end.min(start + span.text.len());
+            (from < to).then(|| TextSpan { text: span.text[from - start..to - start].to_string(), ..span.clone() })
+        }).collect()
+    }
+
+    fn with_spans(&self, spans: Vec<TextSpan>) -> RichTextNode {
+        RichTextNode {
+            spans,
+            size: self.size,
+            color: self.color.clone(),
+            fonts: self.fonts.clone(),
+            align: self.align,
+            white_space: self.white_space,
+            hyphenation: self.hyphenation.clone(),
+            line_height: self.line_height,
+            letter_spacing: self.letter_spacing,
+            word_spacing: self.word_spacing,
+            orphans: self.orphans,
+            widows: self.widows,
+            continues: self.continues,
+        }
+    }
+
+    fn split_lines(&self, available_width: f64, available_height: f64, font: &Font, orphans: usize, widows: usize) -> SplitAction {
+        let lines = self.layout_lines(available_width, font);
+
+        let mut used = 0.0;
+        let mut fitting = 0;
+        for line in &lines {
+            if used + line.height > available_height {
+                break;
+            }
+            used += line.height;
+            fitting += 1;
+        }
+
+        if fitting == lines.len() {
+            return SplitAction::Fit;
+        }
+        let fitting = text::lines_before_break(lines.len(), fitting, orphans, widows);
+        if fitting == 0 {
             return SplitAction::Push;
         }
 
-        // Use helper to split
-        // text::split_text_at_lines will measure and return (Head, Tail)
-        let (head, tail_opt) = text::split_text_at_lines(&self.text, available_width, self.size, font, max_lines);
-        
-        if let Some(tail) = tail_opt {
-            let head_node: Arc<dyn LayoutNode> = Arc::new(TextNode { text: head, size: self.size, color: self.color, background_color: self.background_color });
-            let tail_node: Arc<dyn LayoutNode> = Arc::new(TextNode { text: tail, size: self.size, color: self.color, background_color: self.background_color });
-            SplitAction::Split(head_node, tail_node)
+        let (text, starts) = self.source();
+        let last = &lines[fitting - 1].line;
+        let mut head = self.slice_spans(&starts, 0..last.next);
+        // Keep the hyphen when the head is wrapped again on its own
+        if last.hyphenated
+            && let Some(span) = head.last_mut()
+            && !span.text.ends_with('\u{AD}')
+        {
+            span.text.push('\u{AD}');
+        }
+        let tail = self.slice_spans(&starts, lines[fitting].line.start..text.len());
+
+        // The head keeps the lines chosen for the whole paragraph, last one included
+        let head_node: Arc<dyn LayoutNode> = Arc::new(RichTextNode { continues: true, ..self.with_spans(head) });
+        SplitAction::Split(head_node, Arc::new(self.with_spans(tail)))
+    }
+}
+
+impl LayoutNode for RichTextNode {
+    fn measure(&self, constraints: Constraints, font: &Font) -> Size {
+        let natural = self.natural_width(font);
+        let fills_line = match self.align {
+            Some(align) => align != TextAlign::Left,
+            // RTL paragraphs hug the right edge
+            None => TextDirection::Auto.resolve(&self.source().0).is_rtl(),
+        };
+        let width = if fills_line && constraints.max_width.is_finite() {
+            constraints.max_width
         } else {
-            // Fits completely
-            SplitAction::Fit
+            natural.min(constraints.max_width)
+        };
+
+        let lines = self.layout_lines(width, font);
+        let height = if lines.is_empty() {
+            LineMetrics::new(font, self.size, self.line_height).leading
+        } else {
+            lines.iter().map(|l| l.height).sum()
+        };
+        Size { width, height }
+    }
+
+    fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, _context: &PageContext) {
+        let lines = self.layout_lines(area.width, font);
+        let mut top = area.y;
+
+        for line in &lines {
+            // Lines start at the paragraph's start edge unless aligned otherwise
+            let align = self.align.unwrap_or(if line.rtl { TextAlign::Right } else { TextAlign::Left });
+            let baseline = top - line.ascent;
+            let slack = (area.width - self.runs_width(&line.runs, font)).max(0.0);
+            let spaces: usize = line.runs.iter().map(|(_, r, _)| r.chars().filter(|c| *c == ' ').count()).sum();
+            let justify = align == TextAlign::Justify && !line.line.ends_paragraph && spaces > 0;
+            let justify_spacing = if justify { slack / spaces as f64 } else { 0.0 };
+
+            let mut pen = match align {
+                TextAlign::Center => area.x + slack / 2.0,
+                TextAlign::Right => area.x + slack,
+                // The last line of a justified RTL paragraph sits at the right edge
+                TextAlign::Justify if line.rtl => area.x + slack,
+                TextAlign::Left | TextAlign::Justify => area.x,
+            };
+
+            for (idx, run, rtl) in &line.runs {
+                let span = &self.spans[*idx];
+                let (span_font, span_index) = self.span_font(span, font, font_index);
+                let size = self.span_size(span);
+                let color = span.color.clone().or_else(|| self.color.clone()).unwrap_or_else(Color::black);
+                let run_spaces = run.chars().filter(|c| *c == ' ').count();
+                let width = self.runs_width(&[(*idx, run.clone(), *rtl)], font) + run_spaces as f64 * justify_spacing;
+                let style = TextStyle {
+                    direction: if *rtl { TextDirection::Rtl } else { TextDirection::Ltr },
+                    word_spacing: self.word_spacing + justify_spacing,
+                    letter_spacing: self.letter_spacing,
+                    decoration: span.decoration.clone(),
+                    ..TextStyle::new(span_font, span_index, size)
+                };
+
+                page.set_fill_color(color);
+                page.text_styled(run.clone(), pen, baseline, &style);
+                if let Some(uri) = &span.link {
+                    page.add_link(pen, top - line.height, width, line.height, uri.clone());
+                }
+                pen += width;
+            }
+            top -= line.height;
         }
     }
+
+    fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.split_lines(available_width, available_height, font, self.orphans, self.widows)
+    }
+
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.split_lines(available_width, available_height, font, 1, 1)
+    }
+
+    fn first_baseline(&self, width: f64, font: &Font) -> Option<f64> {
+        self.layout_lines(width, font).first().map(|line| line.ascent)
+    }
 }
 
 #[derive(Debug, Clone, Copy, PartialEq, Default)]
@@ -483,6 +1097,10 @@ pub struct Container {
     pub width: f64,
     pub height: f64,
     pub background_color: Option<crate::core::color::Color>,
+    /// Painted instead of `background_color` when set
+    pub background_gradient: Option<crate::core::gradient::Gradient>,
+    /// Cut the child off at the (rounded) edge of the box
+    pub clip: bool,
 }
 
 impl LayoutNode for Container {
@@ -530,7 +1148,17 @@ impl LayoutNode for Container {
         
         let max_border = self.border_width.left.max(self.border_width.top).max(self.border_width.right).max(self.border_width.bottom);
         
-        if self.background_color.is_some() || max_border > 0.0 {
+        let background = match &self.background_gradient {
+            Some(gradient) => {
+                page.draw_rect_gradient(actual_area.x, bottom_y, actual_area.width, draw_height, self.border_radius, gradient);
+                None
+            }
+            None => self.background_color.clone(),
+        };
+
+        // A clipped child could paint over the border, so it is drawn afterwards
+        let border_color = if self.clip { None } else { self.border_color.clone() };
+        if background.is_some() || (max_border > 0.0 && border_color.is_some()) {
             page.draw_rect_rounded(
                 actual_area.x, 
                 bottom_y, 
@@ -538,8 +1166,8 @@ impl LayoutNode for Container {
                 draw_height, 
                 self.border_radius, 
                 max_border, 
-                self.border_color, 
-                self.background_color
+                border_color, 
+                background
             );
         }
         
@@ -550,7 +1178,18 @@ impl LayoutNode for Container {
             height: actual_area.height - (self.border_width.vertical() + self.padding.vertical()),
         };
         
-        self.child.render(page, child_area, font, font_index, context);
+        if self.clip {
+            let mut clip = Path::new();
+            clip.rounded_rect(actual_area.x, bottom_y, actual_area.width, draw_height, self.border_radius);
+            page.push_clip(&clip, FillRule::NonZero);
+            self.child.render(page, child_area, font, font_index, context);
+            page.pop_clip();
+            if max_border > 0.0 && self.border_color.is_some() {
+                page.draw_rect_rounded(actual_area.x, bottom_y, actual_area.width, draw_height, self.border_radius, max_border, self.border_color.clone(), None);
+            }
+        } else {
+            self.child.render(page, child_area, font, font_index, context);
+        }
     }
 
     fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
@@ -564,22 +1203,442 @@ impl LayoutNode for Container {
              return SplitAction::Push; 
         }
 
-        match self.child.split(child_avail_w, child_avail_h, font) {
+        self.wrap_split(self.child.split(child_avail_w, child_avail_h, font))
+    }
+
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        let reduction_h = self.padding.vertical() + self.margin.vertical() + self.border_width.vertical();
+        let reduction_w = self.padding.horizontal() + self.margin.horizontal() + self.border_width.horizontal();
+
+        if available_height - reduction_h <= 0.0 {
+             return SplitAction::Push;
+        }
+
+        self.wrap_split(self.child.split_at_page_top(available_width - reduction_w, available_height - reduction_h, font))
+    }
+
+    fn first_baseline(&self, width: f64, font: &Font) -> Option<f64> {
+        let inner_width = width - self.padding.horizontal() - self.margin.horizontal() - self.border_width.horizontal();
+        let top = self.margin.top + self.border_width.top + self.padding.top;
+        self.child.first_baseline(inner_width, font).map(|baseline| top + baseline)
+    }
+
+    fn keep_with_next(&self) -> bool {
+        self.child.keep_with_next()
+    }
+}
+
+impl Container {
+    /// Give the parts of a split child this container's box
+    fn wrap_split(&self, action: SplitAction) -> SplitAction {
+        match action {
             SplitAction::Fit => SplitAction::Fit,
             SplitAction::Push => SplitAction::Push,
             SplitAction::Split(head, tail) => {
-                let head_container: Arc<dyn LayoutNode> = Arc::new(Container { child: head, padding: self.padding, margin: self.margin, border_width: self.border_width, border_color: self.border_color, border_radius: self.border_radius, width: self.width, height: 0.0, background_color: self.background_color });
-                let tail_container: Arc<dyn LayoutNode> = Arc::new(Container { child: tail, padding: self.padding, margin: self.margin, border_width: self.border_width, border_color: self.border_color, border_radius: self.border_radius, width: self.width, height: 0.0, background_color: self.background_color });
+                let head_container: Arc<dyn LayoutNode> = Arc::new(Container { child: head, padding: self.padding, margin: self.margin, border_width: self.border_width, border_color: self.border_color.clone(), border_radius: self.border_radius, width: self.width, height: 0.0, background_color: self.background_color.clone(), background_gradient: self.background_gradient.clone(), clip: self.clip });
+                let tail_container: Arc<dyn LayoutNode> = Arc::new(Container { child: tail, padding: self.padding, margin: self.margin, border_width: self.border_width, border_color: self.border_color.clone(), border_radius: self.border_radius, width: self.width, height: 0.0, background_color: self.background_color.clone(), background_gradient: self.background_gradient.clone(), clip: self.clip });
                 SplitAction::Split(head_container, tail_container)
             }
         }
     }
 }
 
+/// Page break rules for any node: `together` moves it to the next page whole rather
+/// than splitting it, `with_next` keeps it on the same page as the following sibling.
+pub struct Keep {
+    pub child: Arc<dyn LayoutNode>,
+    pub together: bool,
+    pub with_next: bool,
+}
+
+impl Keep {
+    /// The tail of a split child still keeps with the node after it
+    fn wrap_split(&self, action: SplitAction) -> SplitAction {
+        match action {
+            SplitAction::Split(head, tail) => {
+                let tail: Arc<dyn LayoutNode> = Arc::new(Keep { child: tail, together: false, with_next: self.with_next });
+                SplitAction::Split(head, tail)
+            }
+            other => other,
+        }
+    }
+}
+
+impl LayoutNode for Keep {
+    fn measure(&self, constraints: Constraints, font: &Font) -> Size {
+        self.child.measure(constraints, font)
+    }
+
+    fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, context: &PageContext) {
+        self.child.render(page, area, font, font_index, context);
+    }
+
+    fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        if !self.together {
+            return self.wrap_split(self.child.split(available_width, available_height, font));
+        }
+        let size = self.child.measure(Constraints::loose(available_width, f64::INFINITY), font);
+        if size.height <= available_height { SplitAction::Fit } else { SplitAction::Push }
+    }
+
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.wrap_split(self.child.split_at_page_top(available_width, available_height, font))
+    }
+
+    fn keep_with_next(&self) -> bool {
+        self.with_next || self.child.keep_with_next()
+    }
+
+    fn first_baseline(&self, width: f64, font: &Font) -> Option<f64> {
+        self.child.first_baseline(width, font)
+    }
+}
+
+/// How list items are marked.
+#[derive(Debug, Clone, PartialEq)]
+pub enum ListMarker {
+    /// A bullet; None picks •, ◦ or ▪ by nesting level
+    Bullet(Option<String>),
+    /// Item numbers between a prefix and a suffix, as in "1.", "a)" or "(iv)"
+    Numbered { style: NumberStyle, prefix: String, suffix: String },
+    None,
+}
+
+impl Default for ListMarker {
+    fn default() -> Self {
+        Self::Bullet(None)
+    }
+}
+
+impl FromStr for ListMarker {
+    type Err = Infallible;
+
+    /// Parses a sample marker ("1.", "a)", "(i)", "A.", "I.") or a bullet
+    /// ("disc", "circle", "square", "none" or any text, used as is).
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let s = s.trim();
+        let numbered = |style| Self::Numbered { style, prefix: String::new(), suffix: ".".to_string() };
+        match s.to_ascii_lowercase().as_str() {
+            "" | "bullet" => return Ok(Self::Bullet(None)),
+            "disc" => return Ok(Self::Bullet(Some("•".to_string()))),
+            "circle" => return Ok(Self::Bullet(Some("◦".to_string()))),
+            "square" => return Ok(Self::Bullet(Some("▪".to_string()))),
+            "none" => return Ok(Self::None),
+            "decimal" => return Ok(numbered(NumberStyle::Decimal)),
+            "lower-alpha" => return Ok(numbered(NumberStyle::LowerAlpha)),
+            "upper-alpha" => return Ok(numbered(NumberStyle::UpperAlpha)),
+            "lower-roman" => return Ok(numbered(NumberStyle::LowerRoman)),
+            "upper-roman" => return Ok(numbered(NumberStyle::UpperRoman)),
+            _ => {}
+        }
+
+        let style = |ch: char| match ch {
+            '1' => Some(NumberStyle::Decimal),
+            'a' => Some(NumberStyle::LowerAlpha),
+            'A' => Some(NumberStyle::UpperAlpha),
+            'i' => Some(NumberStyle::LowerRoman),
+            'I' => Some(NumberStyle::UpperRoman),
+            _ => None,
+        };
+        Ok(match s.char_indices().find_map(|(i, ch)| style(ch).map(|style| (i, ch, style))) {
+            Some((i, ch, style)) if !s[..i].chars().any(char::is_alphanumeric) && !s[i + ch.len_utf8()..].chars().any(char::is_alphanumeric) => {
+                Self::Numbered { style, prefix: s[..i].to_string(), suffix: s[i + ch.len_utf8()..].to_string() }
+            }
+            _ => Self::Bullet(Some(s.to_string())),
+        })
+    }
+}
+
+impl ListMarker {
+    pub fn is_numbered(&self) -> bool {
+        matches!(self, Self::Numbered { .. })
+    }
+
+    /// Marker text for item `number` of a list nested `level` deep.
+    /// Default bullets fall back to ones the font can draw.
+    pub fn text(&self, number: usize, level: usize, font: &Font) -> String {
+        match self {
+            Self::Bullet(Some(bullet)) => bullet.clone(),
+            Self::Bullet(None) => {
+                let preferred = ['•', '◦', '▪'][level % 3];
+                [preferred, '•', '-'].into_iter()
+                    .find(|ch| font.glyph_index(*ch).is_some())
+                    .unwrap_or('-')
+                    .to_string()
+            }
+            Self::Numbered { style, prefix, suffix } => format!("{}{}{}", prefix, style.format(number), suffix),
+            Self::None => String::new(),
+        }
+    }
+}
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default)]
+pub enum NumberStyle {
+    #[default]
+    Decimal,
+    /// a, b, ..., z, aa, ab, ...
+    LowerAlpha,
+    UpperAlpha,
+    LowerRoman,
+    UpperRoman,
+}
+
+impl NumberStyle {
+    pub fn format(&self, number: usize) -> String {
+        match self {
+            Self::Decimal => number.to_string(),
+            Self::LowerAlpha => alpha(number),
+            Self::UpperAlpha => alpha(number).to_uppercase(),
+            Self::LowerRoman => roman(number),
+            Self::UpperRoman => roman(number).to_uppercase(),
+        }
+    }
+}
+
+/// Bijective base 26: 1 = a, 26 = z, 27 = aa. Zero has no letter and stays a digit.
+fn alpha(mut number: usize) -> String {
+    if number == 0 {
+        return "0".to_string();
+    }
+    let mut letters = Vec::new();
+    while number > 0 {
+        number -= 1;
+        letters.push((b'a' + (number % 26) as u8) as char);
+        number /= 26;
+    }
+    letters.iter().rev().collect()
+}
+
+/// Lowercase roman numerals; numbers outside 1..4000 stay digits.
+fn roman(number: usize) -> String {
+    if number == 0 || number >= 4000 {
+        return number.to_string();
+    }
+    const NUMERALS: [(usize, &str); 13] = [
+        (1000, "m"), (900, "cm"), (500, "d"), (400, "cd"), (100, "c"), (90, "xc"),
+        (50, "l"), (40, "xl"), (10, "x"), (9, "ix"), (5, "v"), (4, "iv"), (1, "i"),
+    ];
+    let mut rest = number;
+    let mut out = String::new();
+    for (value, numeral) in NUMERALS {
+        while rest >= value {
+            out.push_str(numeral);
+            rest -= value;
+        }
+    }
+    out
+}
+
+pub struct ListItem {
+    pub content: Arc<dyn LayoutNode>,
+    /// False for the rest of an item continued from the previous page
+    pub marker: bool,
+}
+
+/// Items with bullets or numbers in a hanging indent. Items wrap and split
+/// inside the indent, and nested lists are items containing another `ListNode`.
+pub struct ListNode {
+    pub items: Vec<ListItem>,
+    pub marker: ListMarker,
+    /// Number of the first item
+    pub start: usize,
+    /// Nesting depth, which picks the default bullet
+    pub level: usize,
+    /// Distance from the list's edge to the item content
+    pub indent: f64,
+    /// Space between a marker and its item; markers are right-aligned against it
+    pub marker_gap: f64,
+    /// Space between items
+    pub spacing: f64,
+    pub marker_size: f64,
+    pub marker_color: Option<Color>,
+}
+
+impl ListNode {
+    fn content_width(&self, width: f64) -> f64 {
+        (width - self.indent).max(0.0)
+    }
+
+    fn marker_metrics(&self, font: &Font) -> LineMetrics {
+        LineMetrics::new(font, self.marker_size, LineHeight::Normal)
+    }
+
+    fn item_height(&self, item: &ListItem, width: f64, font: &Font) -> f64 {
+        let height = item.content.measure(Constraints::loose(width, f64::INFINITY), font).height;
+        if item.marker { height.max(self.marker_metrics(font).leading) } else { height }
+    }
+
+    /// A list with the same style holding `items`, numbered from `start`
+    fn with_items(&self, items: Vec<ListItem>, start: usize) -> ListNode {
+        ListNode {
+            items,
+            marker: self.marker.clone(),
+            start,
+            level: self.level,
+            indent: self.indent,
+            marker_gap: self.marker_gap,
+            spacing: self.spacing,
+            marker_size: self.marker_size,
+            marker_color: self.marker_color.clone(),
+        }
+    }
+
+    fn split_items(&self, available_width: f64, available_height: f64, font: &Font, at_page_top: bool) -> SplitAction {
+        let width = self.content_width(available_width);
+        let mut used_height = 0.0;
+        let mut split_index = None;
+        let mut split_parts = None;
+
+        for (i, item) in self.items.iter().enumerate() {
+            let height = self.item_height(item, width, font);
+            let spacing = if i > 0 { self.spacing } else { 0.0 };
+            // Same margin as Column, so lists split where their siblings would
+            let safety_margin = 5.0;
+
+            if used_height + spacing + height <= available_height - safety_margin {
+                used_height += spacing + height;
+                continue;
+            }
+
+            let remaining_height = available_height - (used_height + spacing);
+            split_index = Some(i);
+            if remaining_height <= 0.0 {
+                break;
+            }
+            let action = if at_page_top && i == 0 {
+                item.content.split_at_page_top(width, remaining_height, font)
+            } else {
+                item.content.split(width, remaining_height, font)
+            };
+            match action {
+                SplitAction::Fit => {
+                    used_height += spacing + height;
+                    split_index = None;
+                }
+                SplitAction::Push => break,
+                SplitAction::Split(head, tail) => {
+                    split_parts = Some((head, tail));
+                    break;
+                }
+            }
+        }
+
+        let Some(idx) = split_index else {
+            return SplitAction::Fit;
+        };
+
+        let mut head_items: Vec<ListItem> = self.items[..idx].iter()
+            .map(|item| ListItem { content: item.content.clone(), marker: item.marker })
+            .collect();
+        let mut tail_items = Vec::new();
+        if let Some((head, tail)) = split_parts {
+            head_items.push(ListItem { content: head, marker: self.items[idx].marker });
+            tail_items.push(ListItem { content: tail, marker: false });
+            tail_items.extend(self.items[idx + 1..].iter().map(|item| ListItem { content: item.content.clone(), marker: item.marker }));
+        } else {
+            tail_items.extend(self.items[idx..].iter().map(|item| ListItem { content: item.content.clone(), marker: item.marker }));
+        }
+
+        if head_items.is_empty() {
+            return SplitAction::Push;
+        }
+
+        // Numbering carries on after the items shown on this page
+        let tail_start = self.start + head_items.iter().filter(|item| item.marker).count();
+        let head: Arc<dyn LayoutNode> = Arc::new(self.with_items(head_items, self.start));
+        let tail: Arc<dyn LayoutNode> = Arc::new(self.with_items(tail_items, tail_start));
+        SplitAction::Split(head, tail)
+    }
+}
+
+impl LayoutNode for ListNode {
+    fn measure(&self, constraints: Constraints, font: &Font) -> Size {
+        let width = self.content_width(constraints.max_width);
+        let mut content_width: f64 = 0.0;
+        let mut height = 0.0;
+        for item in &self.items {
+            content_width = content_width.max(item.content.measure(Constraints::loose(width, f64::INFINITY), font).width);
+            height += self.item_height(item, width, font);
+        }
+        if !self.items.is_empty() {
+            height += self.spacing * (self.items.len() as f64 - 1.0);
+        }
+        Size { width: (self.indent + content_width).max(constraints.min_width), height }
+    }
+
+    fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, context: &PageContext) {
+        let width = self.content_width(area.width);
+        let marker_metrics = self.marker_metrics(font);
+        let mut number = self.start;
+        let mut y = area.y;
+
+        for item in &self.items {
+            let height = self.item_height(item, width, font);
+            if item.marker {
+                let text = self.marker.text(number, self.level, font);
+                if !text.is_empty() {
+                    // Markers sit on the baseline of the item's first line
+                    let baseline = y - item.content.first_baseline(width, font).unwrap_or_else(|| marker_metrics.baseline_offset());
+                    let marker_x = area.x + self.indent - self.marker_gap - font.measure_text(&text, self.marker_size);
+                    page.save_state();
+                    page.set_fill_color(self.marker_color.clone().unwrap_or_else(Color::black));
+                    page.text_with_font(text, marker_x, baseline, self.marker_size, font_index, font);
+                    page.restore_state();
+                }
+                number += 1;
+            }
+            let item_area = Rect { x: area.x + self.indent, y, width, height };
+            item.content.render(page, item_area, font, font_index, context);
+            y -= height + self.spacing;
+        }
+    }
+
+    fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.split_items(available_width, available_height, font, false)
+    }
+
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.split_items(available_width, available_height, font, true)
+    }
+
+    fn first_baseline(&self, width: f64, font: &Font) -> Option<f64> {
+        let item = self.items.first()?;
+        item.content.first_baseline(self.content_width(width), font)
+            .or_else(|| item.marker.then(|| self.marker_metrics(font).baseline_offset()))
+    }
+}
+
 pub struct ImageNode {
     pub image_index: u32,
     pub width: f64,
     pub height: f64,
+    pub mask: Option<ImageMask>,
+}
+
+/// Shape an image is cut to
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum ImageMask {
+    /// The largest circle centered in the image
+    Circle,
+    /// The image rectangle with corners rounded by `radius` points
+    Rounded { radius: f64 },
+    /// Any outline, in points relative to the image's bottom-left corner,
+    /// e.g. `{"type": "path", "segments": [{"move_to": [0, 0]}, {"line_to": [50, 100]}, "close"]}`
+    Path(Path),
+}
+
+impl ImageMask {
+    /// The mask outline for an image drawn with bottom-left corner (x, y)
+    pub fn to_path(&self, x: f64, y: f64, width: f64, height: f64) -> Path {
+        let mut path = Path::new();
+        match self {
+            ImageMask::Circle => { path.circle(x + width / 2.0, y + height / 2.0, width.min(height) / 2.0); }
+            ImageMask::Rounded { radius } => { path.rounded_rect(x, y, width, height, *radius); }
+            ImageMask::Path(shape) => path = shape.translated(x, y),
+        }
+        path
+    }
 }
 
 impl LayoutNode for ImageNode {
@@ -611,7 +1670,16 @@ impl LayoutNode for ImageNode {
         
         // So:
         let bottom_y = area.y - area.height;
-        page.draw_image(self.image_index, area.x, bottom_y, area.width, area.height);
+        match &self.mask {
+            Some(mask) => {
+                page.push_clip(&mask.to_path(area.x, bottom_y, area.width, area.height), FillRule::NonZero);
+                page.draw_image(self.image_index, area.x, bottom_y, area.width, area.height);
+                page.pop_clip();
+            }
+            None => {
+                page.draw_image(self.image_index, area.x, bottom_y, area.width, area.height);
+            }
+        }
     }
 
     fn split(&self, _available_width: f64, available_height: f64, _: &Font) -> SplitAction {
@@ -623,6 +1691,127 @@ impl LayoutNode for ImageNode {
     }
 }
 
+/// Rotates, scales and skews its child. The bounding box of the transformed child
+/// is what takes up space, so neighbors make room for rotated content.
+///
+/// The child is transformed about its bottom-left corner and the bounding box is then
+/// moved to the top-left of the area. Any other origin would only shift the box before
+/// that move, so unlike `Page::push_transform` there is no origin to set.
+pub struct TransformNode {
+    pub child: Arc<dyn LayoutNode>,
+    /// Degrees, counterclockwise
+    pub rotate: f64,
+    pub scale_x: f64,
+    pub scale_y: f64,
+    /// Degrees; positive `skew_x` leans the top to the right
+    pub skew_x: f64,
+    pub skew_y: f64,
+}
+
+impl TransformNode {
+    /// Scale first, then skew, then rotate
+    pub fn matrix(&self) -> Matrix {
+        Matrix::scale(self.scale_x, self.scale_y)
+            .then(&Matrix::skew(self.skew_x, self.skew_y))
+            .then(&Matrix::rotate(self.rotate))
+    }
+
+    /// Untransformed size of the child. Only scaled children wrap to the available width;
+    /// rotated or skewed ones keep their natural width.
+    fn child_size(&self, matrix: &Matrix, max_width: f64, font: &Font) -> Size {
+        let wrap_width = if matrix.is_axis_aligned() && matrix.a.abs() > 0.0 {
+            max_width / matrix.a.abs()
+        } else {
+            f64::INFINITY
+        };
+        let size = self.child.measure(Constraints::loose(wrap_width, f64::INFINITY), font);
+        if size.width.is_finite() {
+            size
+        } else {
+            self.child.measure(Constraints::loose(max_width, f64::INFINITY), font)
+        }
+    }
+}
+
+impl LayoutNode for TransformNode {
+    fn measure(&self, constraints: Constraints, font: &Font) -> Size {
+        let matrix = self.matrix();
+        let child = self.child_size(&matrix, constraints.max_width, font);
+        let (_, _, width, height) = matrix.transform_rect(0.0, 0.0, child.width, child.height);
+        Size { width, height }
+    }
+
+    fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, context: &PageContext) {
+        let matrix = self.matrix();
+        let child = self.child_size(&matrix, area.width, font);
+        // The child is drawn with its bottom-left corner at the origin, then the
+        // transformed bounding box is moved to the top-left corner of the area
+        let (min_x, min_y, _, height) = matrix.transform_rect(0.0, 0.0, child.width, child.height);
+        let placed = matrix.then(&Matrix::translate(area.x - min_x, area.y - (min_y + height)));
+        page.push_transform(&placed);
+        let child_area = Rect { x: 0.0, y: child.height, width: child.width, height: child.height };
+        self.child.render(page, child_area, font, font_index, context);
+        page.pop_transform();
+    }
+
+    fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        let size = self.measure(Constraints::loose(available_width, f64::INFINITY), font);
+        if size.height <= available_height {
+            SplitAction::Fit
+        } else {
+            SplitAction::Push
+        }
+    }
+}
+
+/// Puts its child on a layer registered with `Document::add_layer`, so viewers can
+/// show, hide or skip printing it. Halves of a split child stay on the layer.
+pub struct LayerNode {
+    pub child: Arc<dyn LayoutNode>,
+    pub layer: u32,
+}
+
+impl LayerNode {
+    fn wrap_split(&self, action: SplitAction) -> SplitAction {
+        match action {
+            SplitAction::Split(head, tail) => {
+                let head: Arc<dyn LayoutNode> = Arc::new(LayerNode { child: head, layer: self.layer });
+                let tail: Arc<dyn LayoutNode> = Arc::new(LayerNode { child: tail, layer: self.layer });
+                SplitAction::Split(head, tail)
+            }
+            other => other,
+        }
+    }
+}
+
+impl LayoutNode for LayerNode {
+    fn measure(&self, constraints: Constraints, font: &Font) -> Size {
+        self.child.measure(constraints, font)
+    }
+
+    fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, context: &PageContext) {
+        page.begin_layer(self.layer);
+        self.child.render(page, area, font, font_index, context);
+        page.end_layer();
+    }
+
+    fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.wrap_split(self.child.split(available_width, available_height, font))
+    }
+
+    fn split_at_page_top(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        self.wrap_split(self.child.split_at_page_top(available_width, available_height, font))
+    }
+
+    fn keep_with_next(&self) -> bool {
+        self.child.keep_with_next()
+    }
+
+    fn first_baseline(&self, width: f64, font: &Font) -> Option<f64> {
+        self.child.first_baseline(width, font)
+    }
+}
+
 // TableNode implementation
 #[derive(Debug, Clone)]
 pub struct TableNode {
@@ -636,11 +1825,10 @@ impl LayoutNode for TableNode {
         
         let s = &self.table.settings;
         let mut height = s.header_height;
-        let font_size = s.font_size;
-        let leading = font_size * 1.2;
+        let metrics = s.line_metrics(font);
 
         for row in &self.table.rows {
-             let mut max_lines = 1;
+             let mut content_height = metrics.height(1);
              let mut c_i = 0;
              for cell in row.iter() {
                 let cell_text = &cell.content;
@@ -652,11 +1840,9 @@ impl LayoutNode for TableNode {
                 if col_width == 0.0 { col_width = 100.0; } // Fallback
                 
                 let available_width = (col_width - (2.0 * s.padding)).max(1.0);
-                let lines = text::calculate_text_lines(cell_text, available_width, font_size, font);
-                max_lines = max_lines.max(lines);
+                content_height = content_height.max(s.cell_text_height(cell_text, available_width, font));
                 c_i += cell.colspan;
              }
-             let content_height = max_lines as f64 * leading;
              // Minimum height for a row is cell_content + padding
              let row_height = content_height + (2.0 * s.padding) + 8.0;
              height += row_height;
@@ -681,15 +1867,14 @@ impl LayoutNode for TableNode {
              return SplitAction::Push; 
          }
 
-         let font_size = s.font_size;
-         let leading = font_size * 1.2;
+         let metrics = s.line_metrics(font);
          
          let mut current_height = 0.0;
          let mut split_index = None;
          
          for (i, row) in self.table.rows.iter().enumerate() {
              // Calculate row height
-             let mut max_lines = 1;
+             let mut content_height = metrics.height(1);
              let mut c_i = 0;
              for cell in row.iter() {
                 let cell_text = &cell.content;
@@ -701,11 +1886,9 @@ impl LayoutNode for TableNode {
                 if col_width == 0.0 { col_width = 100.0; } // Fallback
                 
                 let available_width = (col_width - (2.0 * s.padding)).max(1.0);
-                let lines = text::calculate_text_lines(cell_text, available_width, font_size, font);
-                max_lines = max_lines.max(lines);
+                content_height = content_height.max(s.cell_text_height(cell_text, available_width, font));
                 c_i += cell.colspan;
              }
-             let content_height = max_lines as f64 * leading;
              let row_height = content_height + (2.0 * s.padding) + 8.0;
              
              if current_height + row_height > data_available {
@@ -747,7 +1930,7 @@ impl LayoutNode for TableNode {
 pub struct PageNumberNode {
     pub format: String,
     pub size: f64,
-    pub align: String, // "left", "center", "right"
+    pub align: TextAlign,
 }
 
 impl LayoutNode for PageNumberNode {
@@ -761,9 +1944,9 @@ impl LayoutNode for PageNumberNode {
             .replace("{{total}}", "999")
             .replace("{page}", "999")
             .replace("{total}", "999");
-        let lines = text::calculate_text_lines(&sample_text, constraints.max_width, self.size, font);
-        let leading = self.size * 1.2;
-        Size { width: constraints.max_width, height: lines as f64 * leading }
+        let lines = text::calculate_text_lines(&sample_text, constraints.max_width, self.size, font, &LineBreakOptions::default());
+        let metrics = LineMetrics::new(font, self.size, LineHeight::Normal);
+        Size { width: constraints.max_width, height: metrics.height(lines) }
     }
 
     fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, context: &PageContext) {
@@ -781,10 +1964,10 @@ impl LayoutNode for PageNumberNode {
         let mut x = area.x;
         
         // Calculate position based on alignment
-        if self.align == "right" {
+        if self.align == TextAlign::Right {
             let text_width = font.measure_text(&resolved_text, self.size);
             x = area.x + area.width - text_width;
-        } else if self.align == "center" {
+        } else if self.align == TextAlign::Center {
             let text_width = font.measure_text(&resolved_text, self.size);
             x = area.x + (area.width - text_width) / 2.0;
         }
@@ -794,9 +1977,8 @@ impl LayoutNode for PageNumberNode {
 
     fn split(&self, _available_width: f64, available_height: f64, font: &Font) -> SplitAction {
         // Calculate height
-        let lines = text::calculate_text_lines(&self.format, _available_width, self.size, font);
-        let leading = self.size * 1.2;
-        let height = lines as f64 * leading;
+        let lines = text::calculate_text_lines(&self.format, _available_width, self.size, font, &LineBreakOptions::default());
+        let height = LineMetrics::new(font, self.size, LineHeight::Normal).height(lines);
         
         if height <= available_height {
             SplitAction::Fit
@@ -855,3 +2037,311 @@ impl LayoutNode for Canvas {
         }
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::collections::HashSet;
+
+    fn roboto() -> Font {
+        Font::from_bytes(include_bytes!("../../Roboto-Regular.ttf").to_vec(), "Roboto".to_string()).unwrap()
+    }
+
+    fn text_node(text: &str) -> TextNode {
+        TextNode {
+            text: text.to_string(),
+            size: 10.0,
+            color: None,
+            background_color: None,
+            direction: TextDirection::Auto,
+            writing_mode: WritingMode::HorizontalTb,
+            white_space: WhiteSpace::Normal,
+            hyphenation: None,
+            align: None,
+            width: None,
+            line_height: LineHeight::Normal,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            orphans: 1,
+            widows: 1,
+            max_lines: None,
+            overflow: TextOverflow::Visible,
+            min_size: None,
+            line_breaker: LineBreaker::Greedy,
+            continues: false,
+            decoration: TextDecoration::default(),
+            tab_stops: Vec::new(),
+        }
+    }
+
+    fn rich_text(spans: &[&str]) -> RichTextNode {
+        RichTextNode {
+            spans: spans.iter().map(|text| TextSpan { text: text.to_string(), ..Default::default() }).collect(),
+            size: 10.0,
+            color: None,
+            fonts: FontSet::new(),
+            align: None,
+            white_space: WhiteSpace::Normal,
+            hyphenation: None,
+            line_height: LineHeight::Normal,
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            orphans: 1,
+            widows: 1,
+            continues: false,
+        }
+    }
+
+    fn list(items: Vec<Arc<dyn LayoutNode>>, marker: ListMarker, level: usize) -> ListNode {
+        ListNode {
+            items: items.into_iter().map(|content| ListItem { content, marker: true }).collect(),
+            marker,
+            start: 1,
+            level,
+            indent: 20.0,
+            marker_gap: 5.0,
+            spacing: 0.0,
+            marker_size: 10.0,
+            marker_color: None,
+        }
+    }
+
+    /// Glyphs drawn when `node` renders on its own page
+    fn rendered_glyphs(node: &dyn LayoutNode, font: &Font) -> HashSet<u16> {
+        let mut page = Page::new(300.0, 300.0);
+        let area = Rect { x: 0.0, y: 300.0, width: 300.0, height: 300.0 };
+        node.render(&mut page, area, font, 0, &PageContext::default());
+        page.used_glyphs.remove(&0).unwrap_or_default()
+    }
+
+    #[test]
+    fn test_list_numbering_continues_across_split() {
+        let font = roboto();
+        let items: Vec<Arc<dyn LayoutNode>> = vec![Arc::new(text_node("x")), Arc::new(text_node("y")), Arc::new(text_node("z"))];
+        let node = list(items, "1.".parse().unwrap(), 0);
+        let item_height = node.item_height(&node.items[0], node.content_width(300.0), &font);
+
+        // Two items fit, within Column's safety margin
+        let SplitAction::Split(head, tail) = node.split(300.0, item_height * 2.0 + 5.5, &font) else {
+            panic!("expected a split");
+        };
+        let glyph = |ch| font.glyph_index(ch).unwrap();
+        let head_glyphs = rendered_glyphs(head.as_ref(), &font);
+        assert!(head_glyphs.contains(&glyph('1')) && head_glyphs.contains(&glyph('2')));
+        assert!(!head_glyphs.contains(&glyph('3')));
+        let tail_glyphs = rendered_glyphs(tail.as_ref(), &font);
+        assert!(tail_glyphs.contains(&glyph('3')));
+        assert!(!tail_glyphs.contains(&glyph('1')));
+    }
+
+    #[test]
+    fn test_nested_list_bullet_levels() {
+        let font = roboto();
+        let bullet = ListMarker::Bullet(None);
+        let levels: Vec<String> = (0..4).map(|level| bullet.text(1, level, &font)).collect();
+        assert_eq!(levels, vec!["•", "◦", "▪", "•"]);
+
+        // A nested list draws its own level's bullet next to the outer one
+        let inner = list(vec![Arc::new(text_node("y"))], ListMarker::Bullet(None), 1);
+        let outer = list(vec![Arc::new(text_node("x")), Arc::new(inner)], ListMarker::Bullet(None), 0);
+        let glyphs = rendered_glyphs(&outer, &font);
+        assert!(glyphs.contains(&font.glyph_index('•').unwrap()));
+        assert!(glyphs.contains(&font.glyph_index('◦').unwrap()));
+        assert!(!glyphs.contains(&font.glyph_index('▪').unwrap()));
+    }
+
+    #[test]
+    fn test_rich_text_runs_in_visual_order() {
+        let font = roboto();
+        // An RTL paragraph whose Hebrew words span two spans, with a Latin span between them
+        let node = rich_text(&["שלום ", "abc", " עולם"]);
+        let lines = node.layout_lines(f64::INFINITY, &font);
+        assert!(lines[0].rtl);
+        let runs: Vec<(usize, &str, bool)> = lines[0].runs.iter().map(|(idx, run, rtl)| (*idx, run.as_str(), *rtl)).collect();
+        assert_eq!(runs, vec![(2, " עולם", true), (1, "abc", false), (0, "שלום ", true)]);
+
+        // RTL paragraphs fill the line so they can sit at the right edge
+        let size = node.measure(Constraints::loose(300.0, f64::INFINITY), &font);
+        assert_eq!(size.width, 300.0);
+        assert!(!rich_text(&["abc ", "def"]).layout_lines(f64::INFINITY, &font)[0].rtl);
+    }
+
+    #[test]
+    fn test_rich_text_split_head_stays_justified() {
+        let font = roboto();
+        let node = RichTextNode { align: Some(TextAlign::Justify), ..rich_text(&["lorem ipsum dolor ", "sit amet consectetur adipiscing elit sed do"]) };
+        let width = 80.0;
+        let lines = node.layout_lines(width, &font);
+        assert!(lines.len() > 3);
+
+        let SplitAction::Split(head, tail) = node.split(width, lines[0].height * 2.0 + 0.1, &font) else {
+            panic!("expected a split");
+        };
+        let render = |node: &dyn LayoutNode| {
+            let mut page = Page::new(300.0, 300.0);
+            node.render(&mut page, Rect { x: 0.0, y: 300.0, width, height: 300.0 }, &font, 0, &PageContext::default());
+            String::from_utf8(page.content).unwrap()
+        };
+        // Justified lines are drawn with TJ word spacing; the head's last line is one of them
+        let head = render(head.as_ref());
+        assert_eq!(head.matches(" TJ ").count(), 2);
+        assert!(!head.contains(" Tj "));
+        // The paragraph still ends unjustified
+        assert!(render(tail.as_ref()).ends_with("Tj ET Q "));
+    }
+
+    /// Horizontal start of every text operator drawn on `page`
+    fn text_starts(page: &Page) -> Vec<f64> {
+        let content = String::from_utf8(page.content.clone()).unwrap();
+        content.split(" Td ").filter_map(|before| {
+            let mut numbers = before.rsplit(' ');
+            numbers.next()?;
+            numbers.next()?.parse().ok()
+        }).collect()
+    }
+
+    #[test]
+    fn test_text_alignment() {
+        let font = roboto();
+        let text_width = font.measure_text("abc", 10.0);
+        let area = Rect { x: 10.0, y: 300.0, width: 200.0, height: 100.0 };
+        let start = |align| {
+            let mut page = Page::new(300.0, 300.0);
+            TextNode { align: Some(align), ..text_node("abc") }.render(&mut page, area, &font, 0, &PageContext::default());
+            text_starts(&page)[0]
+        };
+        assert_eq!(start(TextAlign::Left), 10.0);
+        assert!((start(TextAlign::Center) - (10.0 + (200.0 - text_width) / 2.0)).abs() < 1e-9);
+        assert!((start(TextAlign::Right) - (210.0 - text_width)).abs() < 1e-9);
+
+        // Justified lines fill the box with word spacing, except the paragraph's last line
+        let node = TextNode { align: Some(TextAlign::Justify), ..text_node("aa bb cc dd ee ff gg hh ii jj kk ll mm") };
+        let narrow = Rect { width: 50.0, ..area };
+        let lines = text::break_lines(&node.text, narrow.width, node.size, &font, &node.line_break_options()).len();
+        assert!(lines > 2);
+        let mut page = Page::new(300.0, 300.0);
+        node.render(&mut page, narrow, &font, 0, &PageContext::default());
+        let content = String::from_utf8(page.content.clone()).unwrap();
+        assert_eq!(content.matches(" TJ ").count(), lines - 1);
+        assert_eq!(content.matches(" Tj ").count(), 1);
+        assert!(text_starts(&page).iter().all(|x| *x == 10.0));
+    }
+
+    #[test]
+    fn test_text_fixed_width() {
+        let font = roboto();
+        let node = TextNode { width: Some(120.0), ..text_node("lorem ipsum dolor sit amet consectetur") };
+        let size = node.measure(Constraints::loose(500.0, f64::INFINITY), &font);
+        assert_eq!(size.width, 120.0);
+        // Wrapped to the fixed width, not the wider box
+        let lines = text::calculate_text_lines(&node.text, 120.0, node.size, &font, &node.line_break_options());
+        assert!(lines > 1);
+        assert_eq!(size.height, node.line_metrics(&font).height(lines));
+        // A short text still takes the fixed width, which a narrower box limits
+        let short = TextNode { width: Some(120.0), ..text_node("abc") };
+        assert_eq!(short.measure(Constraints::loose(500.0, f64::INFINITY), &font).width, 120.0);
+        assert_eq!(short.measure(Constraints::loose(60.0, f64::INFINITY), &font).width, 60.0);
+    }
+
+    #[test]
+    fn test_text_decoration_on_every_line() {
+        let font = roboto();
+        let decoration = TextDecoration { underline: true, ..Default::default() };
+        let node = TextNode { decoration, ..text_node("aa bb cc dd ee ff") };
+        let area = Rect { x: 0.0, y: 300.0, width: 30.0, height: 300.0 };
+        let lines = text::break_lines(&node.text, area.width, node.size, &font, &node.line_break_options()).len();
+        assert!(lines > 1);
+
+        let mut page = Page::new(300.0, 300.0);
+        node.render(&mut page, area, &font, 0, &PageContext::default());
+        let content = String::from_utf8(page.content.clone()).unwrap();
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+        let tops: Vec<f64> = tokens.iter().enumerate()
+            .filter(|(_, token)| **token == "re")
+            .map(|(i, _)| tokens[i - 3].parse::<f64>().unwrap() + tokens[i - 1].parse::<f64>().unwrap())
+            .collect();
+
+        // One underline per line, each the same distance below its baseline
+        let metrics = node.line_metrics(&font);
+        let (position, _) = font.underline_metrics();
+        assert_eq!(tops.len(), lines);
+        for (i, top) in tops.iter().enumerate() {
+            let baseline = area.y - metrics.baseline_offset() - i as f64 * metrics.leading;
+            assert!((top - (baseline + position as f64 * node.size / 1000.0)).abs() < 1e-9);
+        }
+    }
+
+    #[test]
+    fn test_vertical_measure_uses_line_height() {
+        let font = roboto();
+        let node = TextNode { writing_mode: WritingMode::VerticalRl, line_height: LineHeight::Absolute(15.0), ..text_node("abcdef") };
+        let column = font.measure_text_vertical("abc", 10.0);
+        let size = node.measure(Constraints::loose(f64::INFINITY, column + 0.01), &font);
+        assert_eq!(size.width, 30.0);
+        assert!((size.height - column).abs() < 1e-9);
+    }
+
+    #[test]
+    fn test_vertical_split_by_columns() {
+        let font = roboto();
+        let node = TextNode { writing_mode: WritingMode::VerticalRl, ..text_node("abcdefghi") };
+        let leading = node.line_metrics(&font).leading;
+        let column = font.measure_text_vertical("abc", 10.0) + 0.01;
+
+        // Three columns fit the height but only two the width
+        match node.split_at_page_top(leading * 2.5, column, &font) {
+            SplitAction::Split(head, tail) => {
+                let head_size = head.measure(Constraints::loose(f64::INFINITY, f64::INFINITY), &font);
+                assert!((head_size.width - leading * 2.0).abs() < 1e-9);
+                assert!(head_size.height <= column);
+                let tail_size = tail.measure(Constraints::loose(f64::INFINITY, f64::INFINITY), &font);
+                assert!((tail_size.width - leading).abs() < 1e-9);
+            }
+            _ => panic!("expected a split"),
+        }
+
+        // No column fits across the width
+        assert!(matches!(node.split(leading / 2.0, column, &font), SplitAction::Push));
+        assert!(matches!(node.split(leading, f64::INFINITY, &font), SplitAction::Fit));
+        // Taller than the page in one column, but every wrapped column fits across it
+        assert!(matches!(node.split(leading * 3.5, column, &font), SplitAction::Fit));
+    }
+
+    #[test]
+    fn test_rotated_child_bounding_box() {
+        let font = roboto();
+        let image = Arc::new(ImageNode { image_index: 0, width: 40.0, height: 20.0, mask: None });
+        let rotated = |rotate| TransformNode { child: image.clone(), rotate, scale_x: 1.0, scale_y: 1.0, skew_x: 0.0, skew_y: 0.0 };
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        let loose = Constraints::loose(300.0, f64::INFINITY);
+
+        let quarter = rotated(90.0).measure(loose, &font);
+        assert!(close(quarter.width, 20.0) && close(quarter.height, 40.0));
+        let eighth = rotated(45.0).measure(loose, &font);
+        let diagonal = 60.0 / 2f64.sqrt();
+        assert!(close(eighth.width, diagonal) && close(eighth.height, diagonal));
+
+        // The transformed box lands at the top-left corner of the area
+        let mut page = Page::new(300.0, 300.0);
+        rotated(30.0).render(&mut page, Rect { x: 50.0, y: 250.0, width: 200.0, height: 200.0 }, &font, 0, &PageContext::default());
+        let content = String::from_utf8(page.content).unwrap();
+        let numbers: Vec<f64> = content.split(" cm ").next().unwrap().split_whitespace().rev().take(6)
+            .map(|n| n.parse().unwrap()).collect();
+        let placed = Matrix { a: numbers[5], b: numbers[4], c: numbers[3], d: numbers[2], e: numbers[1], f: numbers[0] };
+        let corners = [(0.0, 0.0), (40.0, 0.0), (0.0, 20.0), (40.0, 20.0)].map(|(x, y)| placed.apply(x, y));
+        assert!(close(corners.iter().map(|c| c.0).fold(f64::INFINITY, f64::min), 50.0));
+        assert!(close(corners.iter().map(|c| c.1).fold(f64::NEG_INFINITY, f64::max), 250.0));
+    }
+
+    #[test]
+    fn test_path_mask_round_trips_through_json() {
+        let json = r#"{"type": "path", "segments": [{"move_to": [0, 0]}, {"line_to": [50, 100]}, {"line_to": [100, 0]}, "close"]}"#;
+        let mask: ImageMask = serde_json::from_str(json).unwrap();
+        let mut triangle = Path::new();
+        triangle.move_to(0.0, 0.0).line_to(50.0, 100.0).line_to(100.0, 0.0).close();
+        assert_eq!(mask, ImageMask::Path(triangle));
+
+        let again: ImageMask = serde_json::from_str(&serde_json::to_string(&mask).unwrap()).unwrap();
+        assert_eq!(again, mask);
+    }
+}
@@ -0,0 +1,581 @@
+use std::collections::HashMap;
+use std::iter::Peekable;
+use std::sync::Arc;
+use pulldown_cmark::{Alignment, Event, HeadingLevel, Options, Parser, Tag, TagEnd};
+use crate::core::color::Color;
+use crate::core::font::{Font, FontSet};
+use crate::core::layout::{Column, Constraints, Container, FlexAlign, FlexJustify, Keep, LayoutNode, ListItem, ListMarker, ListNode, PageContext, Rect, RichTextNode, Size, Spacing, SplitAction, TableNode, TextSpan};
+use crate::core::page::Page;
+use crate::core::table::{Table, TableCell, TableColumn, TableSettings};
+use crate::core::template::Style;
+use crate::core::text::{Hyphenation, LineHeight, TextAlign, TextDecoration, WhiteSpace};
+
+/// Heading sizes relative to the body size, h1 to h6
+const HEADING_SCALE: [f64; 6] = [2.0, 1.5, 1.25, 1.0, 0.875, 0.85];
+
+/// Converts Markdown into a layout tree. Each element is styled by the style named
+/// after it ("h1".."h6", "p", "strong", "em", "code", "pre", "a", "ul", "ol", "li",
+/// "blockquote", "hr", "table"), then by the elements around it, then by `base`.
+pub struct Markdown<'a> {
+    pub styles: &'a HashMap<String, Style>,
+    /// Style for whatever the element styles leave unset
+    pub base: Option<&'a Style>,
+    /// Prepended to element names when looking up their styles
+    pub prefix: &'a str,
+    pub fonts: &'a FontSet,
+    /// Body text size; headings scale from it
+    pub size: f64,
+    pub color: Option<Color>,
+    /// Space between blocks
+    pub spacing: f64,
+}
+
+impl Markdown<'_> {
+    pub fn to_layout_node(&self, source: &str) -> Arc<dyn LayoutNode> {
+        let options = Options::ENABLE_TABLES | Options::ENABLE_STRIKETHROUGH;
+        let mut builder = Builder {
+            markdown: self,
+            events: Parser::new_ext(source, options).peekable(),
+            context: Vec::new(),
+            list_level: 0,
+        };
+        let blocks = builder.blocks();
+        column(blocks, self.spacing)
+    }
+
+    fn style(&self, element: &str) -> Option<&Style> {
+        self.styles.get(&format!("{}{}", self.prefix, element))
+    }
+}
+
+/// Inline formatting open at the current text
+#[derive(Default)]
+struct InlineState {
+    strong: usize,
+    emphasis: usize,
+    strikethrough: usize,
+    link: Option<String>,
+}
+
+struct Builder<'m, 'a, I: Iterator<Item = Event<'a>>> {
+    markdown: &'m Markdown<'m>,
+    events: Peekable<I>,
+    /// Enclosing block elements, innermost last
+    context: Vec<&'static str>,
+    list_level: usize,
+}
+
+impl<'a, I: Iterator<Item = Event<'a>>> Builder<'_, 'a, I> {
+    /// Property set on the element itself
+    fn own<T>(&self, element: &str, f: impl Fn(&Style) -> Option<T>) -> Option<T> {
+        self.markdown.style(element).and_then(f)
+    }
+
+    /// Property set on the element or inherited from the elements around it
+    fn inherited<T>(&self, element: &str, f: impl Fn(&Style) -> Option<T>) -> Option<T> {
+        self.own(element, &f)
+            .or_else(|| self.context.iter().rev().find_map(|name| self.own(name, &f)))
+    }
+
+    /// Inherited property, falling back to the node's base style
+    fn prop<T>(&self, element: &str, f: impl Fn(&Style) -> Option<T>) -> Option<T> {
+        self.inherited(element, &f).or_else(|| self.markdown.base.and_then(&f))
+    }
+
+    /// Block nodes up to the end of the enclosing block, which is consumed
+    fn blocks(&mut self) -> Vec<Arc<dyn LayoutNode>> {
+        let mut nodes = Vec::new();
+        while let Some(event) = self.events.peek() {
+            match event {
+                Event::End(_) => {
+                    self.events.next();
+                    break;
+                }
+                Event::Start(tag) if !is_inline(tag) => {
+                    let Some(Event::Start(tag)) = self.events.next() else { unreachable!() };
+                    nodes.extend(self.block(tag));
+                }
+                Event::Rule => {
+                    self.events.next();
+                    nodes.push(self.rule());
+                }
+                // Text directly inside a tight list item
+                _ => {
+                    let spans = self.inlines("p");
+                    if !spans.is_empty() {
+                        let size = self.inherited("p", |s| s.size).unwrap_or(self.markdown.size);
+                        nodes.push(self.paragraph("p", spans, size));
+                    }
+                }
+            }
+        }
+        nodes
+    }
+
+    fn block(&mut self, tag: Tag<'a>) -> Option<Arc<dyn LayoutNode>> {
+        match tag {
+            Tag::Paragraph => {
+                let spans = self.inlines("p");
+                self.events.next();
+                let size = self.inherited("p", |s| s.size).unwrap_or(self.markdown.size);
+                (!spans.is_empty()).then(|| self.paragraph("p", spans, size))
+            }
+            Tag::Heading { level, .. } => {
+                let (element, index) = heading(level);
+                let spans = self.inlines(element);
+                self.events.next();
+                let size = self.own(element, |s| s.size).unwrap_or(self.markdown.size * HEADING_SCALE[index]);
+                let node = self.paragraph(element, spans, size);
+                Some(Arc::new(Keep { child: node, together: true, with_next: true }))
+            }
+            Tag::BlockQuote(_) => {
+                self.context.push("blockquote");
+                let children = self.blocks();
+                self.context.pop();
+                Some(self.quote(children))
+            }
+            Tag::CodeBlock(_) => {
+                let mut code = String::new();
+                for event in self.events.by_ref() {
+                    match event {
+                        Event::Text(text) => code.push_str(&text),
+                        Event::End(_) => break,
+                        _ => {}
+                    }
+                }
+                Some(self.code_block(code.trim_end_matches('\n').to_string()))
+            }
+            Tag::List(start) => Some(self.list(start)),
+            Tag::Table(alignments) => Some(self.table(alignments)),
+            // Raw HTML, footnotes and the like: keep whatever text they hold
+            _ => {
+                let children = self.blocks();
+                (!children.is_empty()).then(|| column(children, self.markdown.spacing))
+            }
+        }
+    }
+
+    /// Spans up to the end of the enclosing block, which is left unconsumed
+    fn inlines(&mut self, element: &str) -> Vec<TextSpan> {
+        let base = TextSpan {
+            font_family: self.prop(element, |s| s.font_family.clone()),
+            weight: self.own(element, |s| s.weight)
+                .or_else(|| matches!(element.as_bytes(), [b'h', b'1'..=b'6']).then_some(700))
+                .or_else(|| self.prop(element, |s| s.weight)),
+            italic: self.prop(element, |s| s.italic).unwrap_or(false),
+            decoration: self.prop(element, |s| s.decoration.clone()).unwrap_or_default(),
+            ..Default::default()
+        };
+        let mut state = InlineState::default();
+        let mut spans = Vec::new();
+
+        while let Some(event) = self.events.peek() {
+            let block_boundary = match event {
+                Event::Start(tag) => !is_inline(tag),
+                Event::End(tag) => !is_inline_end(tag),
+                Event::Rule => true,
+                _ => false,
+            };
+            if block_boundary {
+                break;
+            }
+            match self.events.next() {
+                Some(Event::Text(text)) => spans.push(self.span(&base, &state, text.into_string())),
+                Some(Event::Code(text)) | Some(Event::InlineMath(text)) => {
+                    let mut span = self.span(&base, &state, text.into_string());
+                    span.font_family = self.own("code", |s| s.font_family.clone()).or(Some("monospace".to_string()));
+                    span.size = self.own("code", |s| s.size).or(span.size);
+                    span.color = self.own("code", |s| s.color.clone()).or(span.color);
+                    span.decoration.highlight = self.own("code", |s| s.background_color.clone()).or(span.decoration.highlight);
+                    spans.push(span);
+                }
+                Some(Event::FootnoteReference(label)) => spans.push(self.span(&base, &state, format!("[{}]", label))),
+                Some(Event::SoftBreak) => spans.push(self.span(&base, &state, " ".to_string())),
+                Some(Event::HardBreak) => spans.push(self.span(&base, &state, "\n".to_string())),
+                Some(Event::Start(Tag::Strong)) => state.strong += 1,
+                Some(Event::Start(Tag::Emphasis)) => state.emphasis += 1,
+                Some(Event::Start(Tag::Strikethrough)) => state.strikethrough += 1,
+                Some(Event::Start(Tag::Link { dest_url, .. })) => state.link = Some(dest_url.into_string()),
+                Some(Event::End(TagEnd::Strong)) => state.strong = state.strong.saturating_sub(1),
+                Some(Event::End(TagEnd::Emphasis)) => state.emphasis = state.emphasis.saturating_sub(1),
+                Some(Event::End(TagEnd::Strikethrough)) => state.strikethrough = state.strikethrough.saturating_sub(1),
+                Some(Event::End(TagEnd::Link)) => state.link = None,
+                // Images show their alt text, inline HTML is dropped
+                _ => {}
+            }
+        }
+        spans
+    }
+
+    fn span(&self, base: &TextSpan, state: &InlineState, text: String) -> TextSpan {
+        let mut span = TextSpan { text, ..base.clone() };
+        if state.strong > 0 {
+            span.weight = Some(self.own("strong", |s| s.weight).unwrap_or(700));
+            span.color = self.own("strong", |s| s.color.clone()).or(span.color);
+        }
+        if state.emphasis > 0 {
+            span.italic = self.own("em", |s| s.italic).unwrap_or(true);
+            span.color = self.own("em", |s| s.color.clone()).or(span.color);
+        }
+        if state.strikethrough > 0 {
+            span.decoration.line_through = true;
+        }
+        if let Some(uri) = &state.link {
+            span.link = Some(uri.clone());
+            span.color = self.own("a", |s| s.color.clone()).or(Some(Color::rgb(0.0, 0.27, 0.8)));
+            match self.own("a", |s| s.decoration.clone()) {
+                Some(decoration) => span.decoration = TextDecoration { line_through: span.decoration.line_through, ..decoration },
+                None => span.decoration.underline = true,
+            }
+        }
+        span
+    }
+
+    fn paragraph(&self, element: &str, spans: Vec<TextSpan>, size: f64) -> Arc<dyn LayoutNode> {
+        let hyphenate = self.prop(element, |s| s.hyphenate).unwrap_or(true);
+        Arc::new(RichTextNode {
+            spans,
+            size,
+            color: self.inherited(element, |s| s.color.clone()).or_else(|| self.markdown.color.clone()),
+            fonts: self.markdown.fonts.clone(),
+            align: self.prop(element, |s| s.align),
+            white_space: WhiteSpace::Normal,
+            hyphenation: self.prop(element, |s| s.lang.clone()).filter(|_| hyphenate).as_deref().map(Hyphenation::new),
+            line_height: self.prop(element, |s| s.line_height).unwrap_or(LineHeight::Normal),
+            letter_spacing: self.prop(element, |s| s.letter_spacing).unwrap_or(0.0),
+            word_spacing: self.prop(element, |s| s.word_spacing).unwrap_or(0.0),
+            orphans: self.prop(element, |s| s.orphans).unwrap_or(2),
+            widows: self.prop(element, |s| s.widows).unwrap_or(2),
+            continues: false,
+        })
+    }
+
+    fn code_block(&self, code: String) -> Arc<dyn LayoutNode> {
+        let span = TextSpan {
+            text: code,
+            font_family: self.own("pre", |s| s.font_family.clone())
+                .or_else(|| self.own("code", |s| s.font_family.clone()))
+                .or(Some("monospace".to_string())),
+            ..Default::default()
+        };
+        let text = RichTextNode {
+            spans: vec![span],
+            size: self.inherited("pre", |s| s.size).unwrap_or(self.markdown.size),
+            color: self.inherited("pre", |s| s.color.clone()).or_else(|| self.markdown.color.clone()),
+            fonts: self.markdown.fonts.clone(),
+            align: None,
+            white_space: WhiteSpace::Pre,
+            hyphenation: None,
+            line_height: self.own("pre", |s| s.line_height).unwrap_or(LineHeight::Normal),
+            letter_spacing: 0.0,
+            word_spacing: 0.0,
+            orphans: 2,
+            widows: 2,
+            continues: false,
+        };
+        let border = self.own("pre", |s| s.border).unwrap_or(0.0);
+        Arc::new(Container {
+            child: Arc::new(text),
+            padding: Spacing::uniform(self.own("pre", |s| s.padding).unwrap_or(6.0)),
+            margin: Spacing::default(),
+            border_width: Spacing::uniform(border),
+            border_color: None,
+            border_radius: 0.0,
+            width: 0.0,
+            height: 0.0,
+            background_color: self.own("pre", |s| s.background_color.clone()).or(Some(Color::rgb(0.95, 0.95, 0.95))),
+            background_gradient: self.own("pre", |s| s.gradient.clone()),
+            clip: false,
+        })
+    }
+
+    fn quote(&self, children: Vec<Arc<dyn LayoutNode>>) -> Arc<dyn LayoutNode> {
+        let padding = self.own("blockquote", |s| s.padding).unwrap_or(6.0);
+        let indent = self.own("blockquote", |s| s.indent).unwrap_or(12.0);
+        Arc::new(Container {
+            child: column(children, self.own("blockquote", |s| s.spacing).unwrap_or(self.markdown.spacing)),
+            padding: Spacing::new(padding, padding, padding, indent),
+            margin: Spacing::default(),
+            border_width: Spacing::uniform(self.own("blockquote", |s| s.border).unwrap_or(0.0)),
+            border_color: None,
+            border_radius: 0.0,
+            width: 0.0,
+            height: 0.0,
+            background_color: self.own("blockquote", |s| s.background_color.clone()).or(Some(Color::rgb(0.95, 0.95, 0.95))),
+            background_gradient: self.own("blockquote", |s| s.gradient.clone()),
+            clip: false,
+        })
+    }
+
+    /// A full-width bar as thick as the "hr" style's `border`
+    fn rule(&self) -> Arc<dyn LayoutNode> {
+        Arc::new(Container {
+            child: column(Vec::new(), 0.0),
+            padding: Spacing::default(),
+            margin: Spacing::default(),
+            border_width: Spacing::default(),
+            border_color: None,
+            border_radius: 0.0,
+            width: 0.0,
+            height: self.own("hr", |s| s.border).unwrap_or(0.75),
+            background_color: self.own("hr", |s| s.color.clone()).or(Some(Color::rgb(0.7, 0.7, 0.7))),
+            background_gradient: self.own("hr", |s| s.gradient.clone()),
+            clip: false,
+        })
+    }
+
+    fn list(&mut self, start: Option<u64>) -> Arc<dyn LayoutNode> {
+        let element = if start.is_some() { "ol" } else { "ul" };
+        let level = self.list_level;
+        let item_spacing = self.own(element, |s| s.spacing).unwrap_or(self.markdown.spacing / 2.0);
+        let mut items = Vec::new();
+
+        self.list_level += 1;
+        self.context.push("li");
+        while let Some(event) = self.events.next() {
+            match event {
+                Event::Start(Tag::Item) => {
+                    let blocks = self.blocks();
+                    items.push(ListItem { content: column(blocks, item_spacing), marker: true });
+                }
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        self.context.pop();
+        self.list_level -= 1;
+
+        let marker = match self.own(element, |s| s.marker.clone()) {
+            Some(marker) => marker.parse().unwrap_or_default(),
+            None if start.is_some() => "1.".parse().unwrap_or_default(),
+            None => ListMarker::default(),
+        };
+        Arc::new(ListNode {
+            items,
+            marker,
+            start: start.unwrap_or(1) as usize,
+            level,
+            indent: self.prop(element, |s| s.indent).unwrap_or(18.0),
+            marker_gap: self.prop(element, |s| s.marker_gap).unwrap_or(6.0),
+            spacing: item_spacing,
+            marker_size: self.inherited("li", |s| s.size).unwrap_or(self.markdown.size),
+            marker_color: self.inherited(element, |s| s.color.clone()).or_else(|| self.markdown.color.clone()),
+        })
+    }
+
+    fn table(&mut self, alignments: Vec<Alignment>) -> Arc<dyn LayoutNode> {
+        let mut header = Vec::new();
+        let mut rows = Vec::new();
+        while let Some(event) = self.events.next() {
+            match event {
+                Event::Start(Tag::TableHead) => header = self.table_cells(),
+                Event::Start(Tag::TableRow) => rows.push(self.table_cells()),
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+
+        let count = rows.iter().map(Vec::len).chain([header.len(), alignments.len()]).max().unwrap_or(0);
+        let columns = (0..count).map(|i| TableColumn {
+            header: header.get(i).cloned().unwrap_or_default(),
+            width: 0.0,
+            align: match alignments.get(i) {
+                Some(Alignment::Center) => TextAlign::Center,
+                Some(Alignment::Right) => TextAlign::Right,
+                _ => TextAlign::Left,
+            },
+            field: None,
+        }).collect();
+
+        let mut settings = TableSettings {
+            font_size: self.inherited("p", |s| s.size).unwrap_or(self.markdown.size),
+            ..TableSettings::default()
+        };
+        if let Some(color) = self.inherited("p", |s| s.color.clone()).or_else(|| self.markdown.color.clone()) {
+            settings.font_color = color;
+        }
+        if let Some(style) = self.markdown.style("table") {
+            style.apply_to_table(&mut settings);
+        }
+
+        let mut table = Table { columns, rows: Vec::new(), settings };
+        for row in rows {
+            table.add_row(row.into_iter().map(|content| TableCell { content, colspan: 1, rowspan: 1 }).collect());
+        }
+        Arc::new(FitTable { table })
+    }
+
+    /// Plain text of each cell up to the end of the row
+    fn table_cells(&mut self) -> Vec<String> {
+        let mut cells = Vec::new();
+        while let Some(event) = self.events.next() {
+            match event {
+                Event::Start(Tag::TableCell) => {
+                    let spans = self.inlines("table");
+                    self.events.next();
+                    cells.push(spans.into_iter().map(|span| span.text).collect::<String>().trim().to_string());
+                }
+                Event::End(_) => break,
+                _ => {}
+            }
+        }
+        cells
+    }
+}
+
+fn is_inline(tag: &Tag) -> bool {
+    matches!(tag, Tag::Emphasis | Tag::Strong | Tag::Strikethrough | Tag::Superscript | Tag::Subscript | Tag::Link { .. } | Tag::Image { .. })
+}
+
+fn is_inline_end(tag: &TagEnd) -> bool {
+    matches!(tag, TagEnd::Emphasis | TagEnd::Strong | TagEnd::Strikethrough | TagEnd::Superscript | TagEnd::Subscript | TagEnd::Link | TagEnd::Image)
+}
+
+fn heading(level: HeadingLevel) -> (&'static str, usize) {
+    match level {
+        HeadingLevel::H1 => ("h1", 0),
+        HeadingLevel::H2 => ("h2", 1),
+        HeadingLevel::H3 => ("h3", 2),
+        HeadingLevel::H4 => ("h4", 3),
+        HeadingLevel::H5 => ("h5", 4),
+        HeadingLevel::H6 => ("h6", 5),
+    }
+}
+
+/// Blocks stacked at full width, so backgrounds and rules span the whole column
+fn column(mut children: Vec<Arc<dyn LayoutNode>>, spacing: f64) -> Arc<dyn LayoutNode> {
+    if children.len() == 1 {
+        return children.remove(0);
+    }
+    Arc::new(Column {
+        children,
+        spacing,
+        align_items: FlexAlign::Stretch,
+        justify_content: FlexJustify::Start,
+    })
+}
+
+/// A table whose columns share the width it is laid out in equally
+struct FitTable {
+    table: Table,
+}
+
+impl FitTable {
+    fn at_width(&self, width: f64) -> TableNode {
+        let mut table = self.table.clone();
+        let count = table.columns.len().max(1) as f64;
+        let column_width = if width.is_finite() { width / count } else { 100.0 };
+        for column in &mut table.columns {
+            column.width = column_width;
+        }
+        TableNode { table }
+    }
+}
+
+impl LayoutNode for FitTable {
+    fn measure(&self, constraints: Constraints, font: &Font) -> Size {
+        self.at_width(constraints.max_width).measure(constraints, font)
+    }
+
+    fn render(&self, page: &mut Page, area: Rect, font: &Font, font_index: u32, context: &PageContext) {
+        self.at_width(area.width).render(page, area, font, font_index, context);
+    }
+
+    fn split(&self, available_width: f64, available_height: f64, font: &Font) -> SplitAction {
+        // The parts keep the column widths of this page
+        self.at_width(available_width).split(available_width, available_height, font)
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roboto() -> Font {
+        Font::from_bytes(include_bytes!("../../Roboto-Regular.ttf").to_vec(), "Roboto".to_string()).unwrap()
+    }
+
+    fn with_markdown<R>(f: impl FnOnce(&Markdown) -> R) -> R {
+        let styles = HashMap::new();
+        let fonts = FontSet::new();
+        f(&Markdown { styles: &styles, base: None, prefix: "", fonts: &fonts, size: 12.0, color: None, spacing: 6.0 })
+    }
+
+    fn convert(source: &str) -> Arc<dyn LayoutNode> {
+        with_markdown(|markdown| markdown.to_layout_node(source))
+    }
+
+    /// Spans of the first block of `source`
+    fn spans(source: &str) -> Vec<TextSpan> {
+        with_markdown(|markdown| {
+            let mut builder = Builder {
+                markdown,
+                events: Parser::new_ext(source, Options::ENABLE_STRIKETHROUGH).peekable(),
+                context: Vec::new(),
+                list_level: 0,
+            };
+            let element = match builder.events.next() {
+                Some(Event::Start(Tag::Heading { level, .. })) => heading(level).0,
+                _ => "p",
+            };
+            builder.inlines(element)
+        })
+    }
+
+    fn render(node: &dyn LayoutNode, font: &Font) -> Page {
+        let mut page = Page::new(300.0, 300.0);
+        let size = node.measure(Constraints::loose(300.0, f64::INFINITY), font);
+        node.render(&mut page, Rect { x: 0.0, y: 300.0, width: 300.0, height: size.height }, font, 0, &PageContext::default());
+        page
+    }
+
+    #[test]
+    fn test_headings() {
+        let font = roboto();
+        let heading = spans("## Section");
+        assert_eq!(heading.len(), 1);
+        assert_eq!(heading[0].text, "Section");
+        assert_eq!(heading[0].weight, Some(700));
+
+        // Headings scale from the body size and stay with the block after them
+        let title = convert("# Title");
+        assert!(title.keep_with_next());
+        let content = String::from_utf8(render(title.as_ref(), &font).content).unwrap();
+        assert!(content.contains(" 24 Tf "));
+        let content = String::from_utf8(render(convert("# Title\n\nBody").as_ref(), &font).content).unwrap();
+        assert!(content.contains(" 24 Tf ") && content.contains(" 12 Tf "));
+    }
+
+    #[test]
+    fn test_inline_emphasis() {
+        let spans = spans("plain **bold** *italic* ~~gone~~ [link](https://example.com)");
+        let texts: Vec<&str> = spans.iter().map(|span| span.text.as_str()).collect();
+        assert_eq!(texts, vec!["plain ", "bold", " ", "italic", " ", "gone", " ", "link"]);
+
+        assert_eq!((spans[0].weight, spans[0].italic), (None, false));
+        assert_eq!((spans[1].weight, spans[1].italic), (Some(700), false));
+        assert_eq!((spans[3].weight, spans[3].italic), (None, true));
+        assert!(spans[5].decoration.line_through);
+        assert_eq!(spans[7].link.as_deref(), Some("https://example.com"));
+        assert!(spans[7].decoration.underline);
+        assert!(spans.iter().take(7).all(|span| span.link.is_none() && !span.decoration.underline));
+    }
+
+    #[test]
+    fn test_lists() {
+        let font = roboto();
+        let glyphs = |source: &str| render(convert(source).as_ref(), &font).used_glyphs.remove(&0).unwrap_or_default();
+        let glyph = |ch| font.glyph_index(ch).unwrap();
+
+        // Ordered lists count from their first number
+        let ordered = glyphs("3. x\n4. y\n");
+        assert!(ordered.contains(&glyph('3')) && ordered.contains(&glyph('4')));
+        assert!(!ordered.contains(&glyph('1')));
+
+        // Nested lists take the next level's bullet
+        let nested = glyphs("- x\n  - y\n");
+        assert!(nested.contains(&glyph('•')) && nested.contains(&glyph('◦')));
+        let flat = glyphs("- x\n- y\n");
+        assert!(!flat.contains(&glyph('◦')));
+    }
+}
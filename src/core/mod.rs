@@ -1,11 +1,22 @@
 pub mod font;
 pub mod writer;
 pub mod page;
+pub mod path;
 pub mod document;
 pub mod image;
 pub mod table;
 pub mod layout;
 pub mod text;
 pub mod color;
+pub mod graphics_state;
+pub mod gradient;
+pub mod transform;
+pub mod form;
+pub mod svg;
+pub mod watermark;
+pub mod layer;
 pub mod glyph_cache;
+pub mod color_glyph;
+pub mod markdown;
 pub mod template;
+pub mod parse;
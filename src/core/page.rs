@@ -1,8 +1,15 @@
-use std::collections::{HashMap, HashSet};
+use std::collections::{BTreeMap, HashMap, HashSet};
 use crate::core::font::Font;
 use crate::core::writer::escape_string;
 use crate::core::table::Table;
-use crate::core::text;
+use crate::core::color::{Color, ColorSpaceResource};
+use crate::core::text::{self, LineBreakOptions, LineMetrics, TextAlign, TextDecoration, TextDirection, TextLine, TextOverflow, TextStyle};
+use crate::core::color_glyph::ColorGlyph;
+use crate::core::image::Image;
+use crate::core::path::{FillRule, Path, PathStyle};
+use crate::core::gradient::{Gradient, Shading};
+use crate::core::transform::Matrix;
+use crate::core::graphics_state::{BlendMode, ExtGState};
 
 /// Represents a single page in a PDF document
 #[derive(Debug, Clone)]
@@ -12,6 +19,33 @@ pub struct Page {
     pub content: Vec<u8>,
     pub used_glyphs: HashMap<usize, HashSet<u16>>,  // font_index -> glyph_ids
     pub used_images: HashSet<u32>, // image_index
+    pub used_color_bitmaps: HashMap<(usize, u16), Image>, // (font_index, glyph_id) -> emoji bitmap
+    pub links: Vec<Link>, // URI link annotations
+    pub used_ext_gstates: HashSet<ExtGState>, // transparency states referenced by /GS names
+    pub shadings: Vec<Shading>, // gradients, referenced as /Sh{index}
+    pub used_color_spaces: BTreeMap<String, ColorSpaceResource>, // spot and ICC spaces by resource name
+    pub used_forms: HashSet<u32>, // form_index
+    pub used_layers: HashSet<u32>, // layer_index, referenced as /OC{index}
+    /// Opacity and blend mode set with `set_opacity`/`set_blend_mode`
+    opacity: ExtGState,
+    /// Transparency state in effect outside q/Q-wrapped drawing
+    applied: ExtGState,
+    /// Transformation applied with `transform`, so link areas land where their content does
+    ctm: Matrix,
+    /// Color set with `set_fill_color`; COLR glyph layers that use the text color take it
+    fill_color: Color,
+    /// (opacity, applied, ctm, fill_color) at each `save_state`
+    state_stack: Vec<(ExtGState, ExtGState, Matrix, Color)>,
+}
+
+/// A clickable area that opens a URI. `y` is the bottom edge in PDF coordinates.
+#[derive(Debug, Clone)]
+pub struct Link {
+    pub x: f64,
+    pub y: f64,
+    pub width: f64,
+    pub height: f64,
+    pub uri: String,
 }
 
 impl Page {
@@ -23,6 +57,18 @@ impl Page {
             content: Vec::new(),
             used_glyphs: HashMap::new(),
             used_images: HashSet::new(),
+            used_color_bitmaps: HashMap::new(),
+            links: Vec::new(),
+            used_ext_gstates: HashSet::new(),
+            shadings: Vec::new(),
+            used_color_spaces: BTreeMap::new(),
+            used_forms: HashSet::new(),
+            used_layers: HashSet::new(),
+            opacity: ExtGState::default(),
+            applied: ExtGState::default(),
+            ctm: Matrix::identity(),
+            fill_color: Color::black(),
+            state_stack: Vec::new(),
         }
     }
     
@@ -37,8 +83,28 @@ impl Page {
     /// /F1 is reserved for built-in Helvetica
     /// Requires font reference to track glyph usage for subsetting
     pub fn text_with_font(&mut self, text: String, x: f64, y: f64, size: f64, font_index: u32, font: &Font) -> &mut Self {
-        // Shape text to get glyph IDs
-        let shaped = font.shape_text(&text, size);
+        self.text_styled(text, x, y, &TextStyle::new(font, font_index, size))
+    }
+
+    /// Add a single line of text set in `style`. The bidi algorithm runs with the style's
+    /// paragraph direction so mixed LTR/RTL runs display in visual order, and the
+    /// style's spacing and decoration are applied.
+    pub fn text_styled(&mut self, text: String, x: f64, y: f64, style: &TextStyle) -> &mut Self {
+        if style.decoration.is_none() {
+            self.text_spaced(text, x, y, style);
+        } else {
+            self.text_decorated(text, x, y, style);
+        }
+        self
+    }
+
+    /// Draw a line with the style's word and letter spacing, without decoration.
+    /// Type0 fonts ignore the Tw operator, so word spacing is written as TJ adjustments;
+    /// letter spacing uses Tc, which applies to every glyph.
+    fn text_spaced(&mut self, text: String, x: f64, y: f64, style: &TextStyle) {
+        let TextStyle { font, font_index, size, word_spacing, letter_spacing, .. } = *style;
+        // Shape text to get glyph IDs (in visual order)
+        let shaped = text::shape_line(font, &text, size, style.direction);
         
         // Track used glyphs for subsetting
         self.used_glyphs
@@ -49,6 +115,31 @@ impl Page {
         // Font names: /F1 = Helvetica (built-in), /F2 = first custom font, /F3 = second, etc.
         let font_name = format!("F{}", font_index + 2);
         
+        if shaped.iter().any(|g| font.color_glyph(g.glyph_id, &self.fill_color).is_some()) {
+            self.text_glyphs_with_color(&shaped, x, y, style);
+            return;
+        }
+        
+        let char_spacing = if letter_spacing != 0.0 { format!("{} Tc ", letter_spacing) } else { String::new() };
+        
+        if word_spacing != 0.0 {
+            // TJ adjustments are in thousandths of an em and move the pen backwards
+            let space = font.glyph_index(' ');
+            let adjustment = -word_spacing / size * 1000.0;
+            let mut array = String::from("[<");
+            for g in &shaped {
+                array.push_str(&format!("{:04x}", g.glyph_id));
+                if Some(g.glyph_id) == space {
+                    array.push_str(&format!("> {} <", adjustment));
+                }
+            }
+            array.push_str(">]");
+            
+            let content = format!("q BT /{} {} Tf {}{} {} Td {} TJ ET Q ", font_name, size, char_spacing, x, y, array);
+            self.content.extend(content.into_bytes());
+            return;
+        }
+        
         // Convert glyph IDs to Hex string (Big Endian)
         let mut hex_content = String::new();
         hex_content.push('<');
@@ -59,121 +150,425 @@ impl Page {
         hex_content.push('>');
         
         // Render text (color should be set before calling this method)
+        let content = format!("q BT /{} {} Tf {}{} {} Td {} Tj ET Q ", font_name, size, char_spacing, x, y, hex_content);
+        self.content.extend(content.into_bytes());
+    }
+
+    /// Render a shaped line that contains color glyphs.
+    /// Monochrome glyphs are emitted as text runs; color glyphs are drawn in their place.
+    /// Every glyph is placed by its shaped advance and offset, so kerning and marks
+    /// land where the shaper put them.
+    fn text_glyphs_with_color(&mut self, shaped: &[crate::core::font::ShapedGlyph], x: f64, y: f64, style: &TextStyle) {
+        let TextStyle { font, font_index, size, word_spacing, letter_spacing, .. } = *style;
+        let scale = size / font.units_per_em() as f64;
+        let font_name = format!("F{}", font_index + 2);
+        let char_spacing = if letter_spacing != 0.0 { format!("{} Tc ", letter_spacing) } else { String::new() };
+        let space = font.glyph_index(' ');
+        let mut pen = x;
+        // TJ array of the pending monochrome run and where it starts
+        let mut run = String::new();
+        let mut run_origin = (x, y);
+        
+        for g in shaped {
+            let extra = if Some(g.glyph_id) == space { word_spacing } else { 0.0 };
+            let origin = (pen + g.x_offset, y + g.y_offset);
+            
+            let Some(color_glyph) = font.color_glyph(g.glyph_id, &self.fill_color) else {
+                // A glyph moved off the baseline or the run's pen starts a new run
+                let glyph_advance = font.get_glyph_width(g.glyph_id) as f64 * scale;
+                if !run.is_empty() && (g.x_offset != 0.0 || g.y_offset != 0.0) {
+                    self.glyph_run(&font_name, size, &char_spacing, run_origin, &run);
+                    run.clear();
+                }
+                if run.is_empty() {
+                    run_origin = origin;
+                }
+                run.push_str(&format!("<{:04x}>", g.glyph_id));
+                // TJ adjustments are in thousandths of an em and move the pen backwards
+                let adjustment = (glyph_advance - g.x_advance - extra) / size * 1000.0;
+                if adjustment.abs() > 1e-6 {
+                    run.push_str(&format!(" {} ", adjustment));
+                }
+                if g.x_offset != 0.0 || g.y_offset != 0.0 {
+                    self.glyph_run(&font_name, size, &char_spacing, run_origin, &run);
+                    run.clear();
+                }
+                pen += g.x_advance + letter_spacing + extra;
+                continue;
+            };
+            
+            // Flush pending monochrome glyphs
+            if !run.is_empty() {
+                self.glyph_run(&font_name, size, &char_spacing, run_origin, &run);
+                run.clear();
+            }
+            
+            let (glyph_x, glyph_y) = origin;
+            match color_glyph.as_ref() {
+                ColorGlyph::Layers(layers) => {
+                    // Layers are in font units: scale and move to the glyph origin
+                    let mut content = format!("q {} 0 0 {} {} {} cm ", scale, scale, glyph_x, glyph_y);
+                    let mut current = self.applied;
+                    for layer in layers {
+                        content.push_str(&self.paint_ops(&mut current, Some(&layer.color), None));
+                        content.push_str(&layer.path);
+                        content.push_str("f ");
+                    }
+                    content.push_str("Q ");
+                    self.content.extend(content.into_bytes());
+                }
+                ColorGlyph::Bitmap { image, x: bx, y: by, width, height } => {
+                    let key = (font_index as usize, g.glyph_id);
+                    self.used_color_bitmaps.entry(key).or_insert_with(|| image.clone());
+                    let content = format!(
+                        "q {} 0 0 {} {} {} cm /CG{}_{} Do Q ",
+                        width * size, height * size, glyph_x + bx * size, glyph_y + by * size, font_index, g.glyph_id
+                    );
+                    self.content.extend(content.into_bytes());
+                }
+            }
+            pen += g.x_advance + letter_spacing + extra;
+        }
+        
+        if !run.is_empty() {
+            self.glyph_run(&font_name, size, &char_spacing, run_origin, &run);
+        }
+    }
+
+    /// Write one TJ text object starting at `origin`
+    fn glyph_run(&mut self, font_name: &str, size: f64, char_spacing: &str, origin: (f64, f64), array: &str) {
+        let content = format!("q BT /{} {} Tf {}{} {} Td [{}] TJ ET Q ", font_name, size, char_spacing, origin.0, origin.1, array);
+        self.content.extend(content.into_bytes());
+    }
+
+    /// Draw a line with its decorations: the highlight is painted first, then the text,
+    /// then underline, strikethrough and overline in `decoration.color` or the fill color.
+    fn text_decorated(&mut self, text: String, x: f64, y: f64, style: &TextStyle) {
+        let TextStyle { font, size, word_spacing, letter_spacing, .. } = *style;
+        let decoration = &style.decoration;
+        // Pen position of every glyph, advancing as the text operators do
+        let shaped = text::shape_line(font, &text, size, style.direction);
+        let scale = size / font.units_per_em() as f64;
+        let space = font.glyph_index(' ');
+        let mut pens = Vec::with_capacity(shaped.len());
+        let mut pen = x;
+        for g in &shaped {
+            pens.push(pen);
+            pen += font.get_glyph_width(g.glyph_id) as f64 * scale + letter_spacing;
+            if Some(g.glyph_id) == space {
+                pen += word_spacing;
+            }
+        }
+        let width = pen - x;
+
+        let units = |v: i16| v as f64 * size / 1000.0;
+        if let Some(highlight) = decoration.highlight.clone() {
+            let bottom = y + units(font.descent());
+            self.draw_rect_filled(x, bottom, width, y + units(font.ascent()) - bottom, highlight);
+        }
+
+        self.text_spaced(text, x, y, style);
+
+        // Each stroke is a filled band given by the y of its top edge
+        let (underline_position, thickness) = font.underline_metrics();
+        let mut rects = Vec::new();
+        if decoration.underline {
+            let top = y + units(underline_position);
+            let mut start = x;
+            if decoration.skip_ink {
+                // Gap the line around descenders, padded by the stroke thickness on both sides
+                let pad = units(thickness);
+                for (g, glyph_x) in shaped.iter().zip(&pens) {
+                    if let Some((min, max)) = font.glyph_ink_below(g.glyph_id, underline_position) {
+                        let (gap_start, gap_end) = (glyph_x + min * size / 1000.0 - pad, glyph_x + max * size / 1000.0 + pad);
+                        if gap_start > start {
+                            rects.push((start, top, gap_start - start, units(thickness)));
+                        }
+                        start = start.max(gap_end);
+                    }
+                }
+            }
+            if x + width > start {
+                rects.push((start, top, x + width - start, units(thickness)));
+            }
+        }
+        if decoration.line_through {
+            let (position, thickness) = font.strikeout_metrics();
+            rects.push((x, y + units(position), width, units(thickness)));
+        }
+        if decoration.overline {
+            rects.push((x, y + units(font.ascent()), width, units(thickness)));
+        }
+
+        if !rects.is_empty() {
+            let mut content = format!("q {}", self.scoped_paint(Some(&decoration.color.clone().unwrap_or_else(|| self.fill_color.clone())), None));
+            for (rx, top, w, h) in rects {
+                content.push_str(&format!("{} {} {} {} re ", rx, top - h, w, h));
+            }
+            content.push_str("f Q ");
+            self.content.extend(content.into_bytes());
+        }
+    }
+
+    /// Add a single vertical (top-to-bottom) column of text using a custom font.
+    /// `x` is the column's center line and `y` the top of the column.
+    /// Uses the font's Identity-V resource (/FV2, /FV3, ...) so the viewer advances downwards via W2.
+    pub fn text_vertical(&mut self, text: String, x: f64, y: f64, size: f64, font_index: u32, font: &Font) -> &mut Self {
+        let shaped = font.shape_text_vertical(&text, size);
+        
+        // Vertical and horizontal resources share one CIDFont, so glyph usage is tracked together
+        self.used_glyphs
+            .entry(font_index as usize)
+            .or_insert_with(HashSet::new)
+            .extend(shaped.iter().map(|g| g.glyph_id));
+        
+        let font_name = format!("FV{}", font_index + 2);
+        
+        let mut hex_content = String::new();
+        hex_content.push('<');
+        for g in &shaped {
+            hex_content.push_str(&format!("{:04x}", g.glyph_id));
+        }
+        hex_content.push('>');
+        
         let content = format!("q BT /{} {} Tf {} {} Td {} Tj ET Q ", font_name, size, x, y, hex_content);
         self.content.extend(content.into_bytes());
         self
     }
 
+    /// Add vertical text wrapped into columns inside the box (x, y, width, height).
+    /// Columns are one line height of `style` wide and flow from the right edge towards the left.
+    pub fn text_vertical_multiline(&mut self, text: String, x: f64, y: f64, width: f64, height: f64, style: &TextStyle) -> &mut Self {
+        let leading = LineMetrics::new(style.font, style.size, style.line_height).leading;
+        let mut column_x = x + width - leading / 2.0;
+        
+        for column in text::wrap_text_vertical(&text, height, style.size, style.font) {
+            self.text_vertical(column, column_x, y, style.size, style.font_index, style.font);
+            column_x -= leading;
+        }
+        
+        self
+    }
     
     // calculate_text_lines moved to crate::core::text::calculate_text_lines
     
     /// Add multiline text with wrapping
     pub fn text_multiline(&mut self, text: String, x: f64, y: f64, width: f64, size: f64, font_index: u32, font: &Font) -> &mut Self {
-        let leading = size * 1.2;
-        // Basic split by words
-        // We need to implement wrapping based on width
-        let words: Vec<&str> = text.split_whitespace().collect();
-        let mut buffer = Vec::new();
-        // Start rendering BELOW the top Y coordinate (assuming y is Top-Left of text box)
-        // PDF coordinates: y is baseline. We want text to be INSIDE the box starting at y (Top).
-        // So first baseline is at y - size (approx ascent).
-        let mut current_y = y - size; 
-        
-        for word in words {
-            // Check if word alone is wider than available width
-            let word_width = font.measure_text(word, size);
+        self.text_paragraph(text, x, y, width, &TextStyle::new(font, font_index, size), &LineBreakOptions::default())
+    }
+
+    /// Add text wrapped to `width` with `options` and set in `style`.
+    /// Lines are aligned within `width`; without an alignment they go to the start edge
+    /// of the paragraph (right for RTL text).
+    /// `y` is the top of the first line box; baselines are placed from the font's ascent and descent.
+    pub fn text_paragraph(&mut self, text: String, x: f64, y: f64, width: f64, style: &TextStyle, options: &LineBreakOptions) -> &mut Self {
+        let options = style.line_break_options(options);
+        let lines = text::break_lines(&text, width, style.size, style.font, &options);
+        let style = TextStyle { direction: style.direction.resolve(&text), ..style.clone() };
+        self.text_lines(lines, x, y, width, &style, &options)
+    }
+
+    /// Draw lines that were already broken (and possibly truncated) by the caller.
+    /// The style's direction should be resolved against the whole paragraph.
+    pub fn text_lines(&mut self, lines: Vec<TextLine>, x: f64, y: f64, width: f64, style: &TextStyle, options: &LineBreakOptions) -> &mut Self {
+        let options = style.line_break_options(options);
+        let TextStyle { font, size, direction, .. } = *style;
+        let metrics = LineMetrics::new(font, size, style.line_height);
+        let align = style.align.unwrap_or(if direction.is_rtl() { TextAlign::Right } else { TextAlign::Left });
+        let mut current_y = y - metrics.baseline_offset();
+        
+        for line in lines {
+            let slack = if width.is_finite() { (width - options.measure(font, &line.text, size)).max(0.0) } else { 0.0 };
+            let spaces = line.text.chars().filter(|c| *c == ' ').count();
+            // Tab stops fix where text goes, so tabbed lines are not justified
+            let tabbed = options.has_tabs(&line.text);
+            let justify = align == TextAlign::Justify && !line.ends_paragraph && spaces > 0 && !tabbed;
             
-            if word_width > width {
-                // Word is too long - need to break it at character level
-                // First, flush current buffer
-                if !buffer.is_empty() {
-                    let line_text = buffer.join(" ");
-                    self.text_with_font(line_text, x, current_y, size, font_index, font);
-                    current_y -= leading;
-                    buffer.clear();
+            let line_x = match align {
+                TextAlign::Left => x,
+                TextAlign::Center => x + slack / 2.0,
+                TextAlign::Right => x + slack,
+                TextAlign::Justify if justify || !direction.is_rtl() => x,
+                TextAlign::Justify => x + slack,
+            };
+            let line_style = TextStyle {
+                word_spacing: style.word_spacing + if justify { slack / spaces as f64 } else { 0.0 },
+                ..style.clone()
+            };
+            if tabbed {
+                let layout = options.layout_tabs(font, &line.text, size);
+                let leader_style = TextStyle { direction: TextDirection::Ltr, word_spacing: 0.0, decoration: TextDecoration::default(), ..*style };
+                for (offset, leader) in layout.leaders {
+                    self.text_styled(leader, line_x + offset, current_y, &leader_style);
                 }
-                
-                // Break the word character by character
-                let chars: Vec<char> = word.chars().collect();
-                let mut char_buffer = String::new();
-                
-                for ch in chars {
-                    let test_str = format!("{}{}", char_buffer, ch);
-                    let test_width = font.measure_text(&test_str, size);
-                    
-                    if test_width <= width {
-                        char_buffer.push(ch);
-                    } else {
-                        // Render current char_buffer and start new line
-                        if !char_buffer.is_empty() {
-                            self.text_with_font(char_buffer.clone(), x, current_y, size, font_index, font);
-                            current_y -= leading;
-                        }
-                        char_buffer.clear();
-                        char_buffer.push(ch);
-                    }
-                }
-                
-                // Render remaining characters
-                if !char_buffer.is_empty() {
-                    self.text_with_font(char_buffer, x, current_y, size, font_index, font);
-                    current_y -= leading;
+                for (offset, piece) in layout.pieces {
+                    self.text_styled(piece, line_x + offset, current_y, &line_style);
                 }
             } else {
-                // Try adding this word to the buffer
-                let mut test_line = buffer.clone();
-                test_line.push(word);
-                let test_text = test_line.join(" ");
-                let test_width = font.measure_text(&test_text, size);
-                
-                if test_width <= width {
-                    // Word fits, add it to buffer
-                    buffer.push(word);
-                } else {
-                    // Buffer with this word doesn't fit
-                    if !buffer.is_empty() {
-                        // Draw current buffer first
-                        let line_text = buffer.join(" ");
-                        self.text_with_font(line_text, x, current_y, size, font_index, font);
-                        current_y -= leading;
-                        buffer.clear();
-                    }
-                    
-                    // Add word to new line
-                    buffer.push(word);
-                }
+                self.text_styled(line.text, line_x, current_y, &line_style);
             }
+            current_y -= metrics.leading;
         }
         
-        // Draw last line
-        if !buffer.is_empty() {
-            let line_text = buffer.join(" ");
-            self.text_with_font(line_text, x, current_y, size, font_index, font);
+        self
+    }
+
+    /// Save the graphics state (q); pair with `restore_state`
+    pub fn save_state(&mut self) -> &mut Self {
+        self.state_stack.push((self.opacity, self.applied, self.ctm, self.fill_color.clone()));
+        self.content.extend(b"q ");
+        self
+    }
+
+    /// Restore the graphics state saved by `save_state` (Q)
+    pub fn restore_state(&mut self) -> &mut Self {
+        if let Some((opacity, applied, ctm, fill_color)) = self.state_stack.pop() {
+            self.opacity = opacity;
+            self.applied = applied;
+            self.ctm = ctm;
+            self.fill_color = fill_color;
         }
-        
+        self.content.extend(b"Q ");
+        self
+    }
+
+    /// Opacity of everything drawn after this, until the state is restored.
+    /// Color alphas multiply with it.
+    pub fn set_opacity(&mut self, fill: f64, stroke: f64) -> &mut Self {
+        self.opacity = ExtGState::new(fill, stroke, self.opacity.blend_mode);
+        self.apply_opacity()
+    }
+
+    /// Blend mode of everything drawn after this, until the state is restored
+    pub fn set_blend_mode(&mut self, mode: BlendMode) -> &mut Self {
+        self.opacity.blend_mode = mode;
+        self.apply_opacity()
+    }
+
+    fn apply_opacity(&mut self) -> &mut Self {
+        if self.opacity != self.applied {
+            let op = self.gs_op(self.opacity);
+            self.content.extend(op.into_bytes());
+            self.applied = self.opacity;
+        }
+        self
+    }
+
+    /// `gs` operator selecting `state`, registered as a page resource
+    fn gs_op(&mut self, state: ExtGState) -> String {
+        self.used_ext_gstates.insert(state);
+        format!("/{} gs ", state.name())
+    }
+
+    /// Color operators, preceded by the ExtGState their alphas need when it differs
+    /// from `current`, which is updated. Only for drawing wrapped in q/Q.
+    fn paint_ops(&mut self, current: &mut ExtGState, fill: Option<&Color>, stroke: Option<&Color>) -> String {
+        let state = self.opacity.with_alpha(fill.map(|c| c.a), stroke.map(|c| c.a));
+        let mut ops = String::new();
+        if state != *current {
+            ops.push_str(&self.gs_op(state));
+            *current = state;
+        }
+        for space in [fill, stroke].into_iter().flatten().filter_map(|c| c.color_space_resource()) {
+            self.used_color_spaces.entry(space.resource_name()).or_insert(space);
+        }
+        if let Some(fill) = fill {
+            ops.push_str(&fill.to_pdf_fill());
+            ops.push(' ');
+        }
+        if let Some(stroke) = stroke {
+            ops.push_str(&stroke.to_pdf_stroke());
+            ops.push(' ');
+        }
+        ops
+    }
+
+    /// `paint_ops` for a single paint inside q/Q
+    fn scoped_paint(&mut self, fill: Option<&Color>, stroke: Option<&Color>) -> String {
+        let mut current = self.applied;
+        self.paint_ops(&mut current, fill, stroke)
+    }
+
+    /// Limit drawing to a rectangle until the graphics state is restored. `y` is the bottom edge.
+    pub fn clip_rect(&mut self, x: f64, y: f64, width: f64, height: f64) -> &mut Self {
+        let content = format!("{} {} {} {} re W n ", x, y, width, height);
+        self.content.extend(content.into_bytes());
+        self
+    }
+    
+    /// Transform everything drawn after this, until the state is restored (cm)
+    pub fn transform(&mut self, matrix: &Matrix) -> &mut Self {
+        self.ctm = matrix.then(&self.ctm);
+        self.content.extend(matrix.to_pdf().into_bytes());
+        self
+    }
+
+    /// Save the graphics state and apply a transformation; `pop_transform` undoes it
+    pub fn push_transform(&mut self, matrix: &Matrix) -> &mut Self {
+        self.save_state();
+        self.transform(matrix)
+    }
+
+    /// End the transformation started by the matching `push_transform`
+    pub fn pop_transform(&mut self) -> &mut Self {
+        self.restore_state()
+    }
+
+    /// Intersect the clip region with a path, until the state is restored
+    pub fn clip_path(&mut self, path: &Path, rule: FillRule) -> &mut Self {
+        let clip = if rule == FillRule::EvenOdd { "W*" } else { "W" };
+        let content = format!("{}{} n ", path.to_pdf(), clip);
+        self.content.extend(content.into_bytes());
+        self
+    }
+
+    /// Save the graphics state and clip to a path; `pop_clip` removes the clip again
+    pub fn push_clip(&mut self, path: &Path, rule: FillRule) -> &mut Self {
+        self.save_state();
+        self.clip_path(path, rule)
+    }
+
+    /// End the clip started by the matching `push_clip`
+    pub fn pop_clip(&mut self) -> &mut Self {
+        self.restore_state()
+    }
+
+    /// Set the fill color used by subsequent text and fill operations.
+    /// Its alpha applies to them too, until another fill color is set.
+    pub fn set_fill_color(&mut self, color: crate::core::color::Color) -> &mut Self {
+        let mut applied = self.applied;
+        let ops = self.paint_ops(&mut applied, Some(&color), None);
+        self.applied = applied;
+        self.fill_color = color;
+        self.content.extend(ops.into_bytes());
         self
     }
     
     /// Add multiline text with color support
     pub fn text_multiline_colored(&mut self, text: String, x: f64, y: f64, width: f64, size: f64, font_index: u32, font: &Font, color: crate::core::color::Color) -> &mut Self {
         // Set text color using PDF operator
-        let color_op = color.to_pdf_fill();
-        self.content.extend(color_op.as_bytes());
-        self.content.push(b' ');
+        self.set_fill_color(color);
         
         // Call standard text_multiline
         self.text_multiline(text, x, y, width, size, font_index, font)
     }
     
+    /// Make a rectangle clickable, opening `uri`. `y` is the bottom edge.
+    /// Under a rotation or skew the link covers the bounding box of the transformed rectangle.
+    pub fn add_link(&mut self, x: f64, y: f64, width: f64, height: f64, uri: String) -> &mut Self {
+        let (x, y, width, height) = self.ctm.transform_rect(x, y, width, height);
+        self.links.push(Link { x, y, width, height, uri });
+        self
+    }
+    
     /// Draw a filled rectangle with specified color
     pub fn draw_rect_filled(&mut self, x: f64, y: f64, width: f64, height: f64, color: crate::core::color::Color) -> &mut Self {
         // Save graphics state
         self.content.extend(b"q ");
         
         // Set fill color and draw rectangle
-        let color_op = color.to_pdf_fill();
+        let color_op = self.scoped_paint(Some(&color), None);
         self.content.extend(color_op.as_bytes());
-        self.content.push(b' ');
         
         // Draw filled rectangle: x y width height re f
         let rect_cmd = format!("{} {} {} {} re f ", x, y, width, height);
@@ -200,6 +595,53 @@ impl Page {
         self
     }
 
+    /// Draw a form at its own size with its bottom-left corner at (x, y)
+    /// form_index is the index returned by document.add_form()
+    pub fn draw_form(&mut self, form_index: u32, x: f64, y: f64) -> &mut Self {
+        self.used_forms.insert(form_index);
+        let content = format!("q 1 0 0 1 {} {} cm /Fm{} Do Q ", x, y, form_index);
+        self.content.extend(content.into_bytes());
+        self
+    }
+
+    /// `draw_form` underneath everything already on the page
+    pub fn draw_form_behind(&mut self, form_index: u32, x: f64, y: f64) -> &mut Self {
+        self.used_forms.insert(form_index);
+        let mut content = format!("q 1 0 0 1 {} {} cm /Fm{} Do Q ", x, y, form_index).into_bytes();
+        content.append(&mut self.content);
+        self.content = content;
+        self
+    }
+
+    /// `draw_form` on top of everything already on the page, outside any
+    /// transformation or transparency state the content left in effect
+    pub fn draw_form_over(&mut self, form_index: u32, x: f64, y: f64) -> &mut Self {
+        let mut content = b"q ".to_vec();
+        content.append(&mut self.content);
+        content.extend(b"Q ");
+        self.content = content;
+        self.opacity = ExtGState::default();
+        self.applied = ExtGState::default();
+        self.ctm = Matrix::identity();
+        self.fill_color = Color::black();
+        self.state_stack.clear();
+        self.draw_form(form_index, x, y)
+    }
+
+    /// Put everything drawn until the matching `end_layer` on a layer
+    /// layer_index is the index returned by document.add_layer()
+    pub fn begin_layer(&mut self, layer_index: u32) -> &mut Self {
+        self.used_layers.insert(layer_index);
+        self.content.extend(format!("/OC /OC{} BDC ", layer_index).into_bytes());
+        self
+    }
+
+    /// End the layer started by the matching `begin_layer`
+    pub fn end_layer(&mut self) -> &mut Self {
+        self.content.extend(b"EMC ");
+        self
+    }
+
     /// Draw a line from (x1, y1) to (x2, y2)
     pub fn draw_line(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, width: f64) -> &mut Self {
         let content = format!(
@@ -222,9 +664,9 @@ impl Page {
     
     /// Draw a colored rectangle (stroke)
     pub fn draw_rect_colored(&mut self, x: f64, y: f64, w: f64, h: f64, width: f64, color: crate::core::color::Color) -> &mut Self {
-        let stroke_op = color.to_pdf_stroke();
+        let stroke_op = self.scoped_paint(None, Some(&color));
         let content = format!(
-            "q {} {} w {} {} {} {} re S Q ",
+            "q {}{} w {} {} {} {} re S Q ",
             stroke_op, width, x, y, w, h
         );
         self.content.extend(content.into_bytes());
@@ -235,13 +677,9 @@ impl Page {
     pub fn draw_rect_rounded(&mut self, x: f64, y: f64, w: f64, h: f64, radius: f64, stroke_width: f64, stroke_color: Option<crate::core::color::Color>, fill_color: Option<crate::core::color::Color>) -> &mut Self {
         self.content.extend(b"q ");
         
-        if let Some(fill) = fill_color {
-            self.content.extend(fill.to_pdf_fill().as_bytes());
-            self.content.push(b' ');
-        }
-        if let Some(stroke) = stroke_color {
-            self.content.extend(stroke.to_pdf_stroke().as_bytes());
-            self.content.push(b' ');
+        let color_ops = self.scoped_paint(fill_color.as_ref(), stroke_color.as_ref());
+        self.content.extend(color_ops.as_bytes());
+        if stroke_color.is_some() {
             let w_cmd = format!("{} w ", stroke_width);
             self.content.extend(w_cmd.as_bytes());
         }
@@ -296,6 +734,48 @@ impl Page {
         self
     }
     
+    /// Paint a path with its own stroke and fill settings
+    pub fn draw_path(&mut self, path: &Path, style: &PathStyle) -> &mut Self {
+        if path.is_empty() {
+            return self;
+        }
+        let color_ops = self.scoped_paint(style.fill.as_ref(), style.stroke.as_ref());
+        let content = format!("q {}{}{}{} Q ", color_ops, style.to_pdf_line_state(), path.to_pdf(), style.paint_operator());
+        self.content.extend(content.into_bytes());
+        self
+    }
+
+    /// Fill a path with a gradient laid out over the path's bounding box
+    pub fn fill_gradient(&mut self, path: &Path, gradient: &Gradient, rule: FillRule) -> &mut Self {
+        let Some((x, y, width, height)) = path.bounds() else { return self };
+        let clip = if rule == FillRule::EvenOdd { "W*" } else { "W" };
+        let content = format!("q {}{} n ", path.to_pdf(), clip);
+        self.content.extend(content.into_bytes());
+        self.paint_shading(gradient.shading(x, y, width, height));
+        self.content.extend(b"Q ");
+        self
+    }
+
+    /// Paint a shading over the whole clip region (sh)
+    pub fn paint_shading(&mut self, shading: Shading) -> &mut Self {
+        let index = match self.shadings.iter().position(|s| *s == shading) {
+            Some(index) => index,
+            None => {
+                self.shadings.push(shading);
+                self.shadings.len() - 1
+            }
+        };
+        self.content.extend(format!("/Sh{} sh ", index).into_bytes());
+        self
+    }
+
+    /// Fill a rectangle, rounded when `radius` is positive, with a gradient
+    pub fn draw_rect_gradient(&mut self, x: f64, y: f64, w: f64, h: f64, radius: f64, gradient: &Gradient) -> &mut Self {
+        let mut path = Path::new();
+        path.rounded_rect(x, y, w, h, radius);
+        self.fill_gradient(&path, gradient, FillRule::NonZero)
+    }
+
     /// Draw a filled rectangle (gray)
     pub fn draw_fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, gray: f64) -> &mut Self {
         let content = format!(
@@ -318,15 +798,13 @@ impl Page {
         let total_width: f64 = table.columns.iter().map(|c| c.width).sum();
         
         // Header background
-        self.draw_rect_filled(x, current_y - header_height, total_width, header_height, s.header_bg);
-        self.draw_rect_colored(x, current_y - header_height, total_width, header_height, s.border_width, s.border_color);
+        self.draw_rect_filled(x, current_y - header_height, total_width, header_height, s.header_bg.clone());
+        self.draw_rect_colored(x, current_y - header_height, total_width, header_height, s.border_width, s.border_color.clone());
         
         // Header Content
         let mut current_x = x;
         // Set text color for header
-        let color_op = s.header_color.to_pdf_fill();
-        self.content.extend(color_op.as_bytes());
-        self.content.push(b' ');
+        self.set_fill_color(s.header_color.clone());
 
         for col in &table.columns {
             // Draw text centered vertically in header
@@ -336,7 +814,7 @@ impl Page {
             self.text_with_font(col.header.clone(), current_x + s.padding, text_y, 10.0, font_index, font);
             
             // Vertical border
-            self.draw_rect_colored(current_x, current_y - header_height, col.width, header_height, s.border_width, s.border_color);
+            self.draw_rect_colored(current_x, current_y - header_height, col.width, header_height, s.border_width, s.border_color.clone());
             current_x += col.width;
         }
         current_y -= header_height;
@@ -346,11 +824,12 @@ impl Page {
         let num_rows = table.rows.len();
         let mut row_heights = vec![0.0; num_rows];
         let mut active_rowspans = vec![0; num_cols];
-        let font_size = s.font_size;
-        let leading = font_size * 1.2;
+        let metrics = s.line_metrics(font);
+        let line_break_options = s.line_break_options();
+        let text_fit = s.text_fit();
 
         for (r_i, row) in table.rows.iter().enumerate() {
-            let mut max_lines = 1;
+            let mut content_height = metrics.height(1);
             let mut c_i = 0;
             
             for cell in row {
@@ -368,8 +847,7 @@ impl Page {
                         width += table.columns[c].width;
                     }
                     let avail = (width - (2.0 * s.padding)).max(1.0);
-                    let lines = text::calculate_text_lines(&cell.content, avail, font_size, font);
-                    max_lines = max_lines.max(lines);
+                    content_height = content_height.max(s.cell_text_height(&cell.content, avail, font));
                 } else {
                     // Mark as spanning future rows
                     for c in c_i..(c_i + colspan).min(num_cols) {
@@ -385,7 +863,7 @@ impl Page {
                 c_i += 1;
             }
 
-            row_heights[r_i] = (max_lines as f64 * leading) + (2.0 * s.padding) + 8.0;
+            row_heights[r_i] = content_height + (2.0 * s.padding) + 8.0;
         }
 
         // 3. Render Cells (Pass 2)
@@ -396,7 +874,7 @@ impl Page {
             
             // Draw Striped Background
             if s.striped && r_i % 2 == 1 {
-                self.draw_rect_filled(x, current_y - row_height, total_width, row_height, s.alternate_row_color);
+                self.draw_rect_filled(x, current_y - row_height, total_width, row_height, s.alternate_row_color.clone());
             }
             
             let mut current_x = x;
@@ -428,19 +906,25 @@ impl Page {
                 }
 
                 // Draw Text
-                self.text_multiline_colored(
-                    cell.content.clone(),
-                    current_x + s.padding,
-                    current_y - s.padding - 8.0,
-                    cell_width - (2.0 * s.padding),
-                    font_size,
-                    font_index,
-                    font,
-                    s.font_color
-                );
+                self.set_fill_color(s.font_color.clone());
+                let text_width = cell_width - (2.0 * s.padding);
+                let cell_font_size = s.cell_font_size(&cell.content, text_width, font);
+                let cell_style = TextStyle {
+                    direction: TextDirection::Auto.resolve(&cell.content),
+                    ..s.cell_style(font, font_index, cell_font_size)
+                };
+                let lines = text_fit.lines(&cell.content, text_width, f64::INFINITY, &cell_style, &line_break_options);
+                if text_fit.overflow == TextOverflow::Clip {
+                    self.save_state();
+                    self.clip_rect(current_x, current_y - cell_height, cell_width, cell_height);
+                }
+                self.text_lines(lines, current_x + s.padding, current_y - s.padding - 8.0, text_width, &cell_style, &line_break_options);
+                if text_fit.overflow == TextOverflow::Clip {
+                    self.restore_state();
+                }
 
                 // Draw Border Box spanning the full dimension
-                self.draw_rect_colored(current_x, current_y - cell_height, cell_width, cell_height, s.border_width, s.border_color);
+                self.draw_rect_colored(current_x, current_y - cell_height, cell_width, cell_height, s.border_width, s.border_color.clone());
 
                 if rowspan > 1 {
                     for c in c_i..end_col { active_rowspans[c] = rowspan - 1; }
@@ -462,3 +946,79 @@ impl Page {
         current_y
     }
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn roboto() -> Font {
+        Font::from_bytes(include_bytes!("../../Roboto-Regular.ttf").to_vec(), "Roboto".to_string()).unwrap()
+    }
+
+    /// (x, y, width, height) of every `re` operator, in order
+    fn rects(page: &Page) -> Vec<(f64, f64, f64, f64)> {
+        let content = String::from_utf8(page.content.clone()).unwrap();
+        let tokens: Vec<&str> = content.split_whitespace().collect();
+        tokens.iter().enumerate()
+            .filter(|(_, token)| **token == "re")
+            .map(|(i, _)| {
+                let n = |k: usize| tokens[i - k].parse::<f64>().unwrap();
+                (n(4), n(3), n(2), n(1))
+            })
+            .collect()
+    }
+
+    fn decorated(font: &Font, text: &str, decoration: TextDecoration) -> Page {
+        let mut page = Page::new(300.0, 300.0);
+        let style = TextStyle { decoration, ..TextStyle::new(font, 0, 10.0) };
+        page.text_styled(text.to_string(), 10.0, 100.0, &style);
+        page
+    }
+
+    fn assert_rect(actual: (f64, f64, f64, f64), expected: (f64, f64, f64, f64)) {
+        let close = |a: f64, b: f64| (a - b).abs() < 1e-9;
+        assert!(close(actual.0, expected.0) && close(actual.1, expected.1) && close(actual.2, expected.2) && close(actual.3, expected.3), "{:?} != {:?}", actual, expected);
+    }
+
+    #[test]
+    fn test_decoration_line_positions() {
+        let font = roboto();
+        let units = |v: i16| v as f64 * 10.0 / 1000.0;
+        let width: f64 = text::shape_line(&font, "abc", 10.0, TextDirection::Ltr).iter()
+            .map(|g| font.get_glyph_width(g.glyph_id) as f64 * 10.0 / font.units_per_em() as f64)
+            .sum();
+        let decoration = TextDecoration { underline: true, line_through: true, overline: true, ..Default::default() };
+        let rects = rects(&decorated(&font, "abc", decoration));
+        assert_eq!(rects.len(), 3);
+
+        // Bands hang down from their top edge, relative to the baseline at y = 100
+        let (underline, thickness) = font.underline_metrics();
+        assert_rect(rects[0], (10.0, 100.0 + units(underline) - units(thickness), width, units(thickness)));
+        let (strikeout, strikeout_thickness) = font.strikeout_metrics();
+        assert_rect(rects[1], (10.0, 100.0 + units(strikeout) - units(strikeout_thickness), width, units(strikeout_thickness)));
+        assert_rect(rects[2], (10.0, 100.0 + units(font.ascent()) - units(thickness), width, units(thickness)));
+        assert!(rects[1].1 > rects[0].1 && rects[2].1 > rects[1].1);
+    }
+
+    #[test]
+    fn test_underline_skip_ink() {
+        let font = roboto();
+        let underline = |skip_ink| TextDecoration { underline: true, skip_ink, ..Default::default() };
+
+        // Without descenders the underline stays whole
+        assert_eq!(rects(&decorated(&font, "abc", underline(true))).len(), 1);
+
+        let solid = rects(&decorated(&font, "agaap", underline(false)));
+        let gapped = rects(&decorated(&font, "agaap", underline(true)));
+        assert_eq!(solid.len(), 1);
+        // One gap under each of g and p
+        assert_eq!(gapped.len(), 3);
+        assert_eq!(gapped[0].0, solid[0].0);
+        let end = |r: &(f64, f64, f64, f64)| r.0 + r.2;
+        assert!((end(&gapped[2]) - end(&solid[0])).abs() < 1e-9);
+        for pair in gapped.windows(2) {
+            assert!(end(&pair[0]) < pair[1].0);
+        }
+        assert!(gapped.iter().all(|r| r.1 == solid[0].1 && r.3 == solid[0].3));
+    }
+}
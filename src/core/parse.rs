@@ -0,0 +1,24 @@
+use std::fmt;
+
+/// A setting given as a string that names none of its values, such as
+/// `"centre"` for a text alignment
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct ParseValueError {
+    /// What the string was meant to name, e.g. "text alignment"
+    pub kind: &'static str,
+    pub value: String,
+}
+
+impl ParseValueError {
+    pub fn new(kind: &'static str, value: &str) -> Self {
+        ParseValueError { kind, value: value.to_string() }
+    }
+}
+
+impl fmt::Display for ParseValueError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "unknown {} \"{}\"", self.kind, self.value)
+    }
+}
+
+impl std::error::Error for ParseValueError {}
@@ -0,0 +1,494 @@
+use serde::{Deserialize, Serialize};
+use crate::core::color::Color;
+use crate::core::parse::ParseValueError;
+use std::str::FromStr;
+
+/// Control point distance for a quarter-circle cubic Bézier, relative to the radius
+const KAPPA: f64 = 0.552284749831;
+
+/// One drawing command of a `Path`. Coordinates are PDF points, y up.
+/// Serialized as `{"line_to": [x, y]}`, or `"close"`.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum PathSegment {
+    MoveTo(f64, f64),
+    LineTo(f64, f64),
+    /// Two control points, then the end point
+    CubicTo(f64, f64, f64, f64, f64, f64),
+    Rect(f64, f64, f64, f64),
+    Close,
+}
+
+/// A vector outline built from lines, curves and shapes.
+/// Quadratic curves and arcs are stored as cubic Béziers, the only curve PDF knows.
+#[derive(Debug, Clone, Default, PartialEq, Serialize, Deserialize)]
+#[serde(from = "PathData", into = "PathData")]
+pub struct Path {
+    pub segments: Vec<PathSegment>,
+    current: Option<(f64, f64)>,
+    start: (f64, f64),
+}
+
+/// Serialized form of a `Path`: just its segments, the pen state is replayed on load
+#[derive(Serialize, Deserialize)]
+struct PathData {
+    segments: Vec<PathSegment>,
+}
+
+impl From<Path> for PathData {
+    fn from(path: Path) -> Self {
+        PathData { segments: path.segments }
+    }
+}
+
+impl From<PathData> for Path {
+    fn from(data: PathData) -> Self {
+        let mut path = Path::new();
+        for segment in data.segments {
+            match segment {
+                PathSegment::MoveTo(x, y) => path.move_to(x, y),
+                PathSegment::LineTo(x, y) => path.line_to(x, y),
+                PathSegment::CubicTo(x1, y1, x2, y2, x, y) => path.cubic_to(x1, y1, x2, y2, x, y),
+                PathSegment::Rect(x, y, w, h) => path.rect(x, y, w, h),
+                PathSegment::Close => path.close(),
+            };
+        }
+        path
+    }
+}
+
+impl Path {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.segments.is_empty()
+    }
+
+    /// Start a new subpath at (x, y)
+    pub fn move_to(&mut self, x: f64, y: f64) -> &mut Self {
+        self.segments.push(PathSegment::MoveTo(x, y));
+        self.current = Some((x, y));
+        self.start = (x, y);
+        self
+    }
+
+    /// Straight line to (x, y); starts a subpath there if none is open
+    pub fn line_to(&mut self, x: f64, y: f64) -> &mut Self {
+        if self.current.is_none() {
+            return self.move_to(x, y);
+        }
+        self.segments.push(PathSegment::LineTo(x, y));
+        self.current = Some((x, y));
+        self
+    }
+
+    /// Cubic Bézier to (x, y) with control points (x1, y1) and (x2, y2)
+    pub fn cubic_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64) -> &mut Self {
+        if self.current.is_none() {
+            self.move_to(x1, y1);
+        }
+        self.segments.push(PathSegment::CubicTo(x1, y1, x2, y2, x, y));
+        self.current = Some((x, y));
+        self
+    }
+
+    /// Quadratic Bézier to (x, y) with control point (cx, cy)
+    pub fn quad_to(&mut self, cx: f64, cy: f64, x: f64, y: f64) -> &mut Self {
+        let (x0, y0) = self.current.unwrap_or((cx, cy));
+        self.cubic_to(
+            x0 + 2.0 / 3.0 * (cx - x0), y0 + 2.0 / 3.0 * (cy - y0),
+            x + 2.0 / 3.0 * (cx - x), y + 2.0 / 3.0 * (cy - y),
+            x, y,
+        )
+    }
+
+    /// Close the current subpath with a line back to its start
+    pub fn close(&mut self) -> &mut Self {
+        if self.current.is_some() {
+            self.segments.push(PathSegment::Close);
+            self.current = Some(self.start);
+        }
+        self
+    }
+
+    /// Circular arc around (cx, cy) from `start_angle` to `end_angle`, in degrees
+    /// counterclockwise from the positive x axis; an end below the start draws clockwise.
+    /// Connects to the current point with a line, like the canvas `arc`.
+    pub fn arc(&mut self, cx: f64, cy: f64, radius: f64, start_angle: f64, end_angle: f64) -> &mut Self {
+        self.elliptic_arc(cx, cy, radius, radius, start_angle, end_angle)
+    }
+
+    /// Arc of the axis-aligned ellipse with radii `rx` and `ry` (angles as in `arc`)
+    pub fn elliptic_arc(&mut self, cx: f64, cy: f64, rx: f64, ry: f64, start_angle: f64, end_angle: f64) -> &mut Self {
+        let start = start_angle.to_radians();
+        let sweep = (end_angle - start_angle).to_radians();
+        let point = |angle: f64| (cx + rx * angle.cos(), cy + ry * angle.sin());
+
+        let (x0, y0) = point(start);
+        if self.current.is_some() {
+            self.line_to(x0, y0);
+        } else {
+            self.move_to(x0, y0);
+        }
+
+        // At most a quarter turn per curve keeps the approximation within 0.03% of the radius
+        let pieces = (sweep.abs() / std::f64::consts::FRAC_PI_2).ceil().max(1.0) as usize;
+        let step = sweep / pieces as f64;
+        let k = 4.0 / 3.0 * (step / 4.0).tan();
+        for i in 0..pieces {
+            let a0 = start + step * i as f64;
+            let a1 = a0 + step;
+            let (x1, y1) = point(a1);
+            self.cubic_to(
+                cx + rx * (a0.cos() - k * a0.sin()), cy + ry * (a0.sin() + k * a0.cos()),
+                cx + rx * (a1.cos() + k * a1.sin()), cy + ry * (a1.sin() - k * a1.cos()),
+                x1, y1,
+            );
+        }
+        self
+    }
+
+    /// Closed ellipse centered on (cx, cy)
+    pub fn ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64) -> &mut Self {
+        let (kx, ky) = (rx * KAPPA, ry * KAPPA);
+        self.move_to(cx + rx, cy);
+        self.cubic_to(cx + rx, cy + ky, cx + kx, cy + ry, cx, cy + ry);
+        self.cubic_to(cx - kx, cy + ry, cx - rx, cy + ky, cx - rx, cy);
+        self.cubic_to(cx - rx, cy - ky, cx - kx, cy - ry, cx, cy - ry);
+        self.cubic_to(cx + kx, cy - ry, cx + rx, cy - ky, cx + rx, cy);
+        self.close()
+    }
+
+    pub fn circle(&mut self, cx: f64, cy: f64, radius: f64) -> &mut Self {
+        self.ellipse(cx, cy, radius, radius)
+    }
+
+    /// Rectangle with its bottom-left corner at (x, y)
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64) -> &mut Self {
+        self.segments.push(PathSegment::Rect(x, y, width, height));
+        self.current = Some((x, y));
+        self.start = (x, y);
+        self
+    }
+
+    /// Rectangle with corners rounded to `radius`, clamped to half the shorter side
+    pub fn rounded_rect(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64) -> &mut Self {
+        let r = radius.min(width / 2.0).min(height / 2.0);
+        if r <= 0.0 {
+            return self.rect(x, y, width, height);
+        }
+        let k = r * KAPPA;
+        self.move_to(x + r, y);
+        self.line_to(x + width - r, y);
+        self.cubic_to(x + width - r + k, y, x + width, y + r - k, x + width, y + r);
+        self.line_to(x + width, y + height - r);
+        self.cubic_to(x + width, y + height - r + k, x + width - r + k, y + height, x + width - r, y + height);
+        self.line_to(x + r, y + height);
+        self.cubic_to(x + r - k, y + height, x, y + height - r + k, x, y + height - r);
+        self.line_to(x, y + r);
+        self.cubic_to(x, y + r - k, x + r - k, y, x + r, y);
+        self.close()
+    }
+
+    /// Closed polygon through the points
+    pub fn polygon(&mut self, points: &[(f64, f64)]) -> &mut Self {
+        let Some((&(x, y), rest)) = points.split_first() else { return self };
+        self.move_to(x, y);
+        for &(x, y) in rest {
+            self.line_to(x, y);
+        }
+        self.close()
+    }
+
+    /// A copy of this path moved by (dx, dy)
+    pub fn translated(&self, dx: f64, dy: f64) -> Path {
+        let segments = self.segments.iter()
+            .map(|segment| match *segment {
+                PathSegment::MoveTo(x, y) => PathSegment::MoveTo(x + dx, y + dy),
+                PathSegment::LineTo(x, y) => PathSegment::LineTo(x + dx, y + dy),
+                PathSegment::CubicTo(x1, y1, x2, y2, x, y) => PathSegment::CubicTo(x1 + dx, y1 + dy, x2 + dx, y2 + dy, x + dx, y + dy),
+                PathSegment::Rect(x, y, w, h) => PathSegment::Rect(x + dx, y + dy, w, h),
+                PathSegment::Close => PathSegment::Close,
+            })
+            .collect();
+        Path {
+            segments,
+            current: self.current.map(|(x, y)| (x + dx, y + dy)),
+            start: (self.start.0 + dx, self.start.1 + dy),
+        }
+    }
+
+    /// Bounding box (x, y, width, height) of all points, control points included
+    pub fn bounds(&self) -> Option<(f64, f64, f64, f64)> {
+        let mut points = Vec::new();
+        for segment in &self.segments {
+            match *segment {
+                PathSegment::MoveTo(x, y) | PathSegment::LineTo(x, y) => points.push((x, y)),
+                PathSegment::CubicTo(x1, y1, x2, y2, x, y) => points.extend([(x1, y1), (x2, y2), (x, y)]),
+                PathSegment::Rect(x, y, w, h) => points.extend([(x, y), (x + w, y + h)]),
+                PathSegment::Close => {}
+            }
+        }
+        let (first, rest) = points.split_first()?;
+        let (mut min, mut max) = (*first, *first);
+        for &(x, y) in rest {
+            min = (min.0.min(x), min.1.min(y));
+            max = (max.0.max(x), max.1.max(y));
+        }
+        Some((min.0, min.1, max.0 - min.0, max.1 - min.1))
+    }
+
+    /// Path construction operators (m, l, c, re, h)
+    pub fn to_pdf(&self) -> String {
+        let mut out = String::new();
+        for segment in &self.segments {
+            let (numbers, op): (Vec<f64>, &str) = match *segment {
+                PathSegment::MoveTo(x, y) => (vec![x, y], "m"),
+                PathSegment::LineTo(x, y) => (vec![x, y], "l"),
+                PathSegment::CubicTo(x1, y1, x2, y2, x, y) => (vec![x1, y1, x2, y2, x, y], "c"),
+                PathSegment::Rect(x, y, w, h) => (vec![x, y, w, h], "re"),
+                PathSegment::Close => (Vec::new(), "h"),
+            };
+            for n in numbers {
+                out.push_str(&num(n));
+                out.push(' ');
+            }
+            out.push_str(op);
+            out.push(' ');
+        }
+        out
+    }
+}
+
+/// Coordinates rounded to 1/10000 pt, so curve math leaves no float noise in the stream
+fn num(value: f64) -> String {
+    let rounded = (value * 10000.0).round() / 10000.0;
+    if rounded == 0.0 { "0".to_string() } else { rounded.to_string() }
+}
+
+/// Which areas of a self-intersecting path count as inside
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum FillRule {
+    #[default]
+    NonZero,
+    EvenOdd,
+}
+
+impl FromStr for FillRule {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "evenodd" | "even-odd" => Self::EvenOdd,
+            "nonzero" | "non-zero" => Self::NonZero,
+            _ => return Err(ParseValueError::new("fill rule", s)),
+        })
+    }
+}
+
+/// Shape of open stroke ends (PDF `J`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineCap {
+    #[default]
+    Butt,
+    Round,
+    Square,
+}
+
+impl FromStr for LineCap {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "round" => Self::Round,
+            "square" | "projecting" => Self::Square,
+            "butt" => Self::Butt,
+            _ => return Err(ParseValueError::new("line cap", s)),
+        })
+    }
+}
+
+/// Shape of stroke corners (PDF `j`)
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineJoin {
+    #[default]
+    Miter,
+    Round,
+    Bevel,
+}
+
+impl FromStr for LineJoin {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "round" => Self::Round,
+            "bevel" => Self::Bevel,
+            "miter" => Self::Miter,
+            _ => return Err(ParseValueError::new("line join", s)),
+        })
+    }
+}
+
+/// How a path is painted: fill, stroke, or both (fill first)
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(default)]
+pub struct PathStyle {
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: f64,
+    pub fill_rule: FillRule,
+    /// Alternating dash and gap lengths; empty for a solid line
+    pub dash: Vec<f64>,
+    pub dash_phase: f64,
+    pub line_cap: LineCap,
+    pub line_join: LineJoin,
+    pub miter_limit: f64,
+}
+
+impl Default for PathStyle {
+    fn default() -> Self {
+        PathStyle {
+            fill: None,
+            stroke: Some(Color::black()),
+            stroke_width: 1.0,
+            fill_rule: FillRule::NonZero,
+            dash: Vec::new(),
+            dash_phase: 0.0,
+            line_cap: LineCap::Butt,
+            line_join: LineJoin::Miter,
+            miter_limit: 10.0,
+        }
+    }
+}
+
+impl PathStyle {
+    pub fn fill(color: Color) -> Self {
+        PathStyle { fill: Some(color), stroke: None, ..Default::default() }
+    }
+
+    pub fn stroke(color: Color, width: f64) -> Self {
+        PathStyle { stroke: Some(color), stroke_width: width, ..Default::default() }
+    }
+
+    /// Line width, cap, join, miter limit and dash operators, when the path is stroked.
+    /// Colors are set by the page, which also handles their alpha.
+    pub fn to_pdf_line_state(&self) -> String {
+        if self.stroke.is_none() {
+            return String::new();
+        }
+        let mut out = format!("{} w {} J {} j {} M ", self.stroke_width, self.line_cap as u8, self.line_join as u8, self.miter_limit);
+        if !self.dash.is_empty() {
+            let dash: Vec<String> = self.dash.iter().map(|d| d.to_string()).collect();
+            out.push_str(&format!("[{}] {} d ", dash.join(" "), self.dash_phase));
+        }
+        out
+    }
+
+    /// Painting operator: f, f*, S, B, B* or n
+    pub fn paint_operator(&self) -> &'static str {
+        let even_odd = self.fill_rule == FillRule::EvenOdd;
+        match (self.fill.is_some(), self.stroke.is_some() && self.stroke_width > 0.0) {
+            (true, true) if even_odd => "B*",
+            (true, true) => "B",
+            (true, false) if even_odd => "f*",
+            (true, false) => "f",
+            (false, true) => "S",
+            (false, false) => "n",
+        }
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn close_to(a: (f64, f64), b: (f64, f64)) -> bool {
+        (a.0 - b.0).abs() < 1e-9 && (a.1 - b.1).abs() < 1e-9
+    }
+
+    fn end_point(segment: &PathSegment) -> (f64, f64) {
+        match *segment {
+            PathSegment::MoveTo(x, y) | PathSegment::LineTo(x, y) | PathSegment::CubicTo(_, _, _, _, x, y) => (x, y),
+            PathSegment::Rect(x, y, _, _) => (x, y),
+            PathSegment::Close => panic!("close has no end point"),
+        }
+    }
+
+    #[test]
+    fn test_elliptic_arc_segments() {
+        // A half turn takes two quarter curves after the move
+        let mut path = Path::new();
+        path.elliptic_arc(10.0, 20.0, 4.0, 2.0, 0.0, 180.0);
+        assert_eq!(path.segments.len(), 3);
+        assert!(close_to(end_point(&path.segments[0]), (14.0, 20.0)));
+        assert!(close_to(end_point(&path.segments[1]), (10.0, 22.0)));
+        assert!(close_to(end_point(&path.segments[2]), (6.0, 20.0)));
+
+        // Just over a quarter turn needs a second curve; clockwise sweeps end below the start
+        let mut path = Path::new();
+        path.elliptic_arc(0.0, 0.0, 1.0, 1.0, 0.0, -100.0);
+        assert_eq!(path.segments.len(), 3);
+        let angle = (-100.0f64).to_radians();
+        assert!(close_to(end_point(&path.segments[2]), (angle.cos(), angle.sin())));
+
+        // With a subpath open the arc joins it with a line
+        let mut path = Path::new();
+        path.move_to(0.0, 0.0).arc(0.0, 0.0, 5.0, 90.0, 120.0);
+        assert!(matches!(path.segments[1], PathSegment::LineTo(_, _)));
+        assert!(close_to(end_point(&path.segments[1]), (0.0, 5.0)));
+        assert_eq!(path.segments.len(), 3);
+    }
+
+    #[test]
+    fn test_quad_to_as_cubic() {
+        let mut path = Path::new();
+        path.move_to(0.0, 0.0).quad_to(3.0, 6.0, 6.0, 0.0);
+        assert_eq!(path.segments[1], PathSegment::CubicTo(2.0, 4.0, 4.0, 4.0, 6.0, 0.0));
+
+        // Without a current point the control point starts the subpath
+        let mut path = Path::new();
+        path.quad_to(3.0, 3.0, 6.0, 0.0);
+        assert_eq!(path.segments[0], PathSegment::MoveTo(3.0, 3.0));
+        assert_eq!(path.segments.len(), 2);
+    }
+
+    #[test]
+    fn test_rounded_rect() {
+        let mut path = Path::new();
+        path.rounded_rect(0.0, 0.0, 100.0, 40.0, 10.0);
+        // Move, four sides and four corners, then close
+        assert_eq!(path.segments.len(), 10);
+        assert_eq!(path.segments[0], PathSegment::MoveTo(10.0, 0.0));
+        assert_eq!(path.segments[1], PathSegment::LineTo(90.0, 0.0));
+        assert_eq!(path.segments[9], PathSegment::Close);
+        assert_eq!(path.bounds(), Some((0.0, 0.0, 100.0, 40.0)));
+
+        // The radius is clamped to half the shorter side, and no radius gives a plain rect
+        let mut pill = Path::new();
+        pill.rounded_rect(0.0, 0.0, 100.0, 40.0, 50.0);
+        assert_eq!(pill.segments[0], PathSegment::MoveTo(20.0, 0.0));
+        let mut square = Path::new();
+        square.rounded_rect(1.0, 2.0, 3.0, 4.0, 0.0);
+        assert_eq!(square.segments, vec![PathSegment::Rect(1.0, 2.0, 3.0, 4.0)]);
+    }
+
+    #[test]
+    fn test_bounds() {
+        assert_eq!(Path::new().bounds(), None);
+
+        let mut path = Path::new();
+        path.move_to(5.0, 5.0).line_to(-5.0, 10.0).cubic_to(0.0, 20.0, 10.0, -3.0, 8.0, 8.0);
+        assert_eq!(path.bounds(), Some((-5.0, -3.0, 15.0, 23.0)));
+
+        let mut path = Path::new();
+        path.rect(10.0, 10.0, 30.0, 20.0);
+        assert_eq!(path.bounds(), Some((10.0, 10.0, 30.0, 20.0)));
+        assert_eq!(path.translated(-10.0, 5.0).bounds(), Some((0.0, 15.0, 30.0, 20.0)));
+    }
+}
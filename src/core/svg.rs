@@ -0,0 +1,370 @@
+use std::io::{self, Error, ErrorKind};
+use usvg::tiny_skia_path::{self, PathSegment};
+use usvg::{Group, Node, Paint, Tree};
+use crate::core::color::Color;
+use crate::core::font::Font;
+use crate::core::form::Form;
+use crate::core::gradient::{ColorStop, Shading};
+use crate::core::graphics_state::BlendMode;
+use crate::core::page::Page;
+use crate::core::path::{FillRule, LineCap, LineJoin, Path, PathStyle};
+use crate::core::transform::Matrix;
+
+/// Whether `data` looks like an SVG document (plain or gzip-compressed)
+pub fn is_svg(data: &[u8]) -> bool {
+    if data.starts_with(&[0x1f, 0x8b]) {
+        return true;
+    }
+    let head = &data[..data.len().min(4096)];
+    let text = String::from_utf8_lossy(head);
+    text.trim_start_matches('\u{feff}').trim_start().starts_with('<') && text.contains("<svg")
+}
+
+/// Record an SVG document as a form of its own size, one SVG unit per point.
+///
+/// Supported: paths and basic shapes, groups with transforms, opacity, blend modes and
+/// clip paths, solid and gradient fills and strokes, and `<text>` drawn as outlines in
+/// one of `fonts` (the first is the default family). Masks, filters, patterns and
+/// embedded images are left out. Group opacity applies to each shape separately, and a
+/// gradient stroke is drawn as its outline filled with the gradient.
+pub fn svg_to_form(data: &[u8], fonts: &[Font]) -> io::Result<Form> {
+    let mut options = usvg::Options::default();
+    for font in fonts {
+        options.fontdb_mut().load_font_data(font.get_font_data().to_vec());
+    }
+    if let Some(family) = options.fontdb.faces().next().and_then(|f| f.families.first()) {
+        options.font_family = family.0.clone();
+    }
+    let tree = Tree::from_data(data, &options)
+        .map_err(|e| Error::new(ErrorKind::InvalidData, format!("Failed to parse SVG: {}", e)))?;
+    let focal_radii = FocalRadii::read(data, &tree);
+
+    let (width, height) = (tree.size().width() as f64, tree.size().height() as f64);
+    let mut form = Form::new(width, height);
+    // SVG y runs downward from the top edge
+    form.page.transform(&Matrix { d: -1.0, f: height, ..Matrix::identity() });
+    draw_group(&mut form.page, tree.root(), 1.0, &focal_radii);
+    Ok(form)
+}
+
+fn draw_group(page: &mut Page, group: &Group, parent_opacity: f64, radii: &FocalRadii) {
+    let opacity = parent_opacity * group.opacity().get() as f64;
+    page.save_state();
+    page.transform(&matrix(group.transform()));
+    if let Some(clip) = group.clip_path() {
+        clip_to(page, clip);
+    }
+    page.set_opacity(opacity, opacity);
+    page.set_blend_mode(blend_mode(group.blend_mode()));
+    for child in group.children() {
+        match child {
+            Node::Group(group) => draw_group(page, group, opacity, radii),
+            Node::Path(path) => draw_path(page, path, opacity, radii),
+            Node::Text(text) => draw_group(page, text.flattened(), opacity, radii),
+            Node::Image(_) => {}
+        }
+    }
+    page.restore_state();
+}
+
+/// Intersect the clip region with a `<clipPath>`: the union of its shapes
+fn clip_to(page: &mut Page, clip: &usvg::ClipPath) {
+    if let Some(outer) = clip.clip_path() {
+        clip_to(page, outer);
+    }
+    let mut shapes = Vec::new();
+    collect_clip_shapes(clip.root(), clip.transform(), &mut shapes);
+    let rule = match shapes.as_slice() {
+        [(_, rule)] => *rule,
+        _ => FillRule::NonZero,
+    };
+    let mut path = Path::new();
+    for (shape, _) in &shapes {
+        path.segments.extend(shape.segments.iter().cloned());
+    }
+    if path.is_empty() {
+        page.clip_rect(0.0, 0.0, 0.0, 0.0);
+    } else {
+        page.clip_path(&path, rule);
+    }
+}
+
+fn collect_clip_shapes(group: &Group, transform: usvg::Transform, shapes: &mut Vec<(Path, FillRule)>) {
+    let transform = transform.pre_concat(group.transform());
+    for child in group.children() {
+        match child {
+            Node::Group(group) => collect_clip_shapes(group, transform, shapes),
+            Node::Text(text) => collect_clip_shapes(text.flattened(), transform, shapes),
+            Node::Path(path) => {
+                let rule = path.fill().map_or(FillRule::NonZero, |f| fill_rule(f.rule()));
+                if let Some(data) = path.data().clone().transform(transform) {
+                    shapes.push((to_path(&data), rule));
+                }
+            }
+            Node::Image(_) => {}
+        }
+    }
+}
+
+fn draw_path(page: &mut Page, node: &usvg::Path, opacity: f64, radii: &FocalRadii) {
+    if !node.is_visible() {
+        return;
+    }
+    let path = to_path(node.data());
+    match node.paint_order() {
+        usvg::PaintOrder::FillAndStroke => {
+            fill(page, &path, node.fill(), opacity, radii);
+            stroke(page, node, &path, opacity, radii);
+        }
+        usvg::PaintOrder::StrokeAndFill => {
+            stroke(page, node, &path, opacity, radii);
+            fill(page, &path, node.fill(), opacity, radii);
+        }
+    }
+}
+
+fn fill(page: &mut Page, path: &Path, fill: Option<&usvg::Fill>, opacity: f64, radii: &FocalRadii) {
+    let Some(fill) = fill else { return };
+    let rule = fill_rule(fill.rule());
+    let alpha = fill.opacity().get() as f64;
+    match fill.paint() {
+        Paint::Color(c) => {
+            let style = PathStyle { fill: Some(color(*c, alpha)), stroke: None, fill_rule: rule, ..Default::default() };
+            page.draw_path(path, &style);
+        }
+        paint => paint_gradient(page, path, rule, paint, opacity * alpha, radii),
+    }
+}
+
+/// Fill `path` with a gradient paint; patterns are skipped
+fn paint_gradient(page: &mut Page, path: &Path, rule: FillRule, paint: &Paint, alpha: f64, radii: &FocalRadii) {
+    let (shading, transform) = match paint {
+        Paint::LinearGradient(g) => {
+            let coords = [g.x1(), g.y1(), g.x2(), g.y2()].map(f64::from).to_vec();
+            (Shading::new(2, coords, &stops(g.stops())), g.transform())
+        }
+        Paint::RadialGradient(g) => {
+            let fr = radii.get(g).min(g.r().get());
+            let coords = [g.fx(), g.fy(), fr, g.cx(), g.cy(), g.r().get()].map(f64::from).to_vec();
+            (Shading::new(3, coords, &stops(g.stops())), g.transform())
+        }
+        Paint::Color(_) | Paint::Pattern(_) => return,
+    };
+    // The shading covers the clip; its own opacity multiplies the group's
+    page.push_clip(path, rule);
+    page.set_opacity(alpha, alpha);
+    page.transform(&matrix(transform));
+    page.paint_shading(shading);
+    page.pop_clip();
+}
+
+fn stroke(page: &mut Page, node: &usvg::Path, path: &Path, opacity: f64, radii: &FocalRadii) {
+    let Some(stroke) = node.stroke() else { return };
+    let alpha = stroke.opacity().get() as f64;
+    let paint = match stroke.paint() {
+        Paint::Color(c) => color(*c, alpha),
+        Paint::Pattern(_) => return,
+        gradient => {
+            // PDF can't stroke with a shading, so fill the stroke's outline instead
+            if let Some(outline) = stroke_outline(node.data(), stroke, node.abs_transform()) {
+                paint_gradient(page, &to_path(&outline), FillRule::NonZero, gradient, opacity * alpha, radii);
+            }
+            return;
+        }
+    };
+    let style = PathStyle {
+        fill: None,
+        stroke: Some(paint),
+        stroke_width: stroke.width().get() as f64,
+        dash: stroke.dasharray().unwrap_or_default().iter().map(|d| *d as f64).collect(),
+        dash_phase: stroke.dashoffset() as f64,
+        line_cap: match stroke.linecap() {
+            usvg::LineCap::Butt => LineCap::Butt,
+            usvg::LineCap::Round => LineCap::Round,
+            usvg::LineCap::Square => LineCap::Square,
+        },
+        line_join: match stroke.linejoin() {
+            usvg::LineJoin::Round => LineJoin::Round,
+            usvg::LineJoin::Bevel => LineJoin::Bevel,
+            usvg::LineJoin::Miter | usvg::LineJoin::MiterClip => LineJoin::Miter,
+        },
+        miter_limit: stroke.miterlimit().get() as f64,
+        ..Default::default()
+    };
+    page.draw_path(path, &style);
+}
+
+/// The area a stroke covers, dashes applied, as a path to fill
+fn stroke_outline(data: &tiny_skia_path::Path, stroke: &usvg::Stroke, transform: usvg::Transform) -> Option<tiny_skia_path::Path> {
+    let stroke = stroke.to_tiny_skia();
+    let scale = tiny_skia_path::PathStroker::compute_resolution_scale(&transform);
+    match &stroke.dash {
+        Some(dash) => data.dash(dash, scale)?.stroke(&stroke, scale),
+        None => data.stroke(&stroke, scale),
+    }
+}
+
+/// `fr` of the document's `<radialGradient>`s, which usvg drops. Gradients usvg copies
+/// get new ids, so those are matched by their circles instead.
+#[derive(Default)]
+struct FocalRadii(Vec<FocalRadius>);
+
+struct FocalRadius {
+    id: String,
+    /// cx, cy, r, fx, fy in the gradient's own units
+    circles: [f32; 5],
+    fr: f32,
+}
+
+impl FocalRadii {
+    fn read(data: &[u8], tree: &Tree) -> Self {
+        let text = if data.starts_with(&[0x1f, 0x8b]) {
+            usvg::decompress_svgz(data).ok().and_then(|unzipped| String::from_utf8(unzipped).ok())
+        } else {
+            std::str::from_utf8(data).ok().map(str::to_string)
+        };
+        let options = usvg::roxmltree::ParsingOptions { allow_dtd: true, ..Default::default() };
+        let Some(doc) = text.as_deref().and_then(|t| usvg::roxmltree::Document::parse_with_options(t, options).ok()) else {
+            return FocalRadii::default();
+        };
+        let size = (tree.size().width(), tree.size().height());
+        let radii = doc.descendants()
+            .filter(|n| n.has_tag_name("radialGradient"))
+            .filter_map(|n| {
+                let user_space = gradient_attr(&doc, n, "gradientUnits") == Some("userSpaceOnUse");
+                let length = |name: &str, default: &str, extent: f32| {
+                    parse_length(gradient_attr(&doc, n, name).unwrap_or(default), user_space, extent)
+                };
+                let diagonal = ((size.0 * size.0 + size.1 * size.1) / 2.0).sqrt();
+                let fr = length("fr", "0%", diagonal)?;
+                if fr <= 0.0 {
+                    return None;
+                }
+                let (cx, cy) = (length("cx", "50%", size.0)?, length("cy", "50%", size.1)?);
+                let r = length("r", "50%", diagonal)?;
+                let fx = gradient_attr(&doc, n, "fx").map_or(Some(cx), |v| parse_length(v, user_space, size.0))?;
+                let fy = gradient_attr(&doc, n, "fy").map_or(Some(cy), |v| parse_length(v, user_space, size.1))?;
+                Some(FocalRadius { id: n.attribute("id").unwrap_or_default().to_string(), circles: [cx, cy, r, fx, fy], fr })
+            })
+            .collect();
+        FocalRadii(radii)
+    }
+
+    /// `fr` of a converted gradient, 0 if it has none
+    fn get(&self, g: &usvg::RadialGradient) -> f32 {
+        let circles = [g.cx(), g.cy(), g.r().get(), g.fx(), g.fy()];
+        let same = |a: &[f32; 5]| a.iter().zip(&circles).all(|(x, y)| (x - y).abs() <= 1e-4 * x.abs().max(1.0));
+        self.0.iter()
+            .find(|f| f.id == g.id())
+            .or_else(|| self.0.iter().find(|f| same(&f.circles)))
+            .map_or(0.0, |f| f.fr)
+    }
+}
+
+/// A gradient attribute, inherited through `href` links as SVG specifies
+fn gradient_attr<'a>(doc: &'a usvg::roxmltree::Document, node: usvg::roxmltree::Node<'a, 'a>, name: &str) -> Option<&'a str> {
+    let mut node = node;
+    for _ in 0..16 {
+        if let Some(value) = node.attribute(name) {
+            return Some(value);
+        }
+        let href = node.attribute(("http://www.w3.org/1999/xlink", "href")).or_else(|| node.attribute("href"))?;
+        let id = href.strip_prefix('#')?;
+        node = doc.descendants().find(|n| n.attribute("id") == Some(id))?;
+    }
+    None
+}
+
+/// A plain, `px` or percentage length; percentages are of `extent` in user space and of
+/// the bounding box (1.0) otherwise
+fn parse_length(value: &str, user_space: bool, extent: f32) -> Option<f32> {
+    let value = value.trim();
+    if let Some(percent) = value.strip_suffix('%') {
+        let fraction = percent.trim().parse::<f32>().ok()? / 100.0;
+        return Some(if user_space { fraction * extent } else { fraction });
+    }
+    value.strip_suffix("px").unwrap_or(value).trim().parse().ok()
+}
+
+fn to_path(data: &tiny_skia_path::Path) -> Path {
+    let mut path = Path::new();
+    for segment in data.segments() {
+        match segment {
+            PathSegment::MoveTo(p) => { path.move_to(p.x as f64, p.y as f64); }
+            PathSegment::LineTo(p) => { path.line_to(p.x as f64, p.y as f64); }
+            PathSegment::QuadTo(c, p) => { path.quad_to(c.x as f64, c.y as f64, p.x as f64, p.y as f64); }
+            PathSegment::CubicTo(c1, c2, p) => {
+                path.cubic_to(c1.x as f64, c1.y as f64, c2.x as f64, c2.y as f64, p.x as f64, p.y as f64);
+            }
+            PathSegment::Close => { path.close(); }
+        }
+    }
+    path
+}
+
+fn matrix(t: usvg::Transform) -> Matrix {
+    Matrix { a: t.sx as f64, b: t.ky as f64, c: t.kx as f64, d: t.sy as f64, e: t.tx as f64, f: t.ty as f64 }
+}
+
+fn color(c: usvg::Color, alpha: f64) -> Color {
+    Color::rgba(c.red as f64 / 255.0, c.green as f64 / 255.0, c.blue as f64 / 255.0, alpha)
+}
+
+fn stops(stops: &[usvg::Stop]) -> Vec<ColorStop> {
+    stops.iter()
+        .map(|s| ColorStop { offset: s.offset().get() as f64, color: color(s.color(), 1.0) })
+        .collect()
+}
+
+fn fill_rule(rule: usvg::FillRule) -> FillRule {
+    match rule {
+        usvg::FillRule::NonZero => FillRule::NonZero,
+        usvg::FillRule::EvenOdd => FillRule::EvenOdd,
+    }
+}
+
+fn blend_mode(mode: usvg::BlendMode) -> BlendMode {
+    match mode {
+        usvg::BlendMode::Normal => BlendMode::Normal,
+        usvg::BlendMode::Multiply => BlendMode::Multiply,
+        usvg::BlendMode::Screen => BlendMode::Screen,
+        usvg::BlendMode::Overlay => BlendMode::Overlay,
+        usvg::BlendMode::Darken => BlendMode::Darken,
+        usvg::BlendMode::Lighten => BlendMode::Lighten,
+        usvg::BlendMode::ColorDodge => BlendMode::ColorDodge,
+        usvg::BlendMode::ColorBurn => BlendMode::ColorBurn,
+        usvg::BlendMode::HardLight => BlendMode::HardLight,
+        usvg::BlendMode::SoftLight => BlendMode::SoftLight,
+        usvg::BlendMode::Difference => BlendMode::Difference,
+        usvg::BlendMode::Exclusion => BlendMode::Exclusion,
+        usvg::BlendMode::Hue => BlendMode::Hue,
+        usvg::BlendMode::Saturation => BlendMode::Saturation,
+        usvg::BlendMode::Color => BlendMode::Color,
+        usvg::BlendMode::Luminosity => BlendMode::Luminosity,
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn test_radial_focal_radius_and_gradient_stroke() {
+        let svg = br##"<svg xmlns="http://www.w3.org/2000/svg" xmlns:xlink="http://www.w3.org/1999/xlink" width="100" height="100">
+            <radialGradient id="g" fr="0.2"><stop offset="0" stop-color="red"/><stop offset="1" stop-color="blue"/></radialGradient>
+            <radialGradient id="h" xlink:href="#g" cx="0.4"/>
+            <rect width="50" height="50" fill="url(#g)"/>
+            <rect x="50" width="50" height="50" fill="url(#g)"/>
+            <circle cx="50" cy="80" r="10" fill="none" stroke="url(#h)" stroke-width="4"/>
+        </svg>"##;
+        let form = svg_to_form(svg, &[]).unwrap();
+        let inner_radii: Vec<f64> = form.page.shadings.iter().map(|s| s.coords[2]).collect();
+        assert_eq!(inner_radii.len(), 2);
+        assert!(inner_radii.iter().all(|r| (r - 0.2).abs() < 1e-6));
+
+        // The stroke is filled with the shading, not stroked in a flat color
+        let content = String::from_utf8_lossy(&form.page.content).to_string();
+        assert_eq!(content.matches(" sh ").count(), 3);
+        assert!(!content.contains(" S "));
+    }
+}
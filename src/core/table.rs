@@ -1,12 +1,7 @@
 use serde::{Deserialize, Serialize};
-
-#[derive(Debug, Clone, Serialize, Deserialize)]
-#[serde(rename_all = "lowercase")]
-pub enum TextAlign {
-    Left,
-    Center,
-    Right,
-}
+use crate::core::font::Font;
+use crate::core::text::{Hyphenation, LineBreakOptions, LineHeight, LineMetrics, TextFit, TextOverflow, TextStyle};
+pub use crate::core::text::TextAlign;
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
 pub struct TableColumn {
@@ -46,6 +41,21 @@ pub struct TableSettings {
     pub striped: bool,
     #[serde(default = "default_alternate_row_color")]
     pub alternate_row_color: crate::core::color::Color,
+    /// Language of the cell text; enables hyphenation when patterns are bundled for it
+    #[serde(default)]
+    pub lang: Option<String>,
+    /// Line height of multi-line cell text
+    #[serde(default)]
+    pub line_height: LineHeight,
+    /// Cell text lines after this many are dropped
+    #[serde(default)]
+    pub max_lines: Option<usize>,
+    /// Handling of cell text that does not fit the cell
+    #[serde(default)]
+    pub overflow: TextOverflow,
+    /// Shrink cell text down to this size until it fits `max_lines`
+    #[serde(default)]
+    pub min_font_size: Option<f64>,
 }
 
 fn default_padding() -> f64 { 5.0 }
@@ -74,8 +84,50 @@ impl Default for TableSettings {
             border_color: default_border_color(),
             striped: default_striped(),
             alternate_row_color: default_alternate_row_color(),
+            lang: None,
+            line_height: LineHeight::Normal,
+            max_lines: None,
+            overflow: TextOverflow::Visible,
+            min_font_size: None,
+        }
+    }
+}
+
+impl TableSettings {
+    /// Line breaking options for cell text
+    pub fn line_break_options(&self) -> LineBreakOptions {
+        LineBreakOptions {
+            hyphenation: self.lang.as_deref().map(Hyphenation::new),
+            ..Default::default()
         }
     }
+
+    /// Line metrics of cell text, shared by measuring, splitting and drawing
+    pub fn line_metrics(&self, font: &Font) -> LineMetrics {
+        LineMetrics::new(font, self.font_size, self.line_height)
+    }
+
+    /// Truncation and shrinking rules for cell text
+    pub fn text_fit(&self) -> TextFit {
+        TextFit { max_lines: self.max_lines, overflow: self.overflow, min_size: self.min_font_size }
+    }
+
+    /// How cell text is set at `size`
+    pub fn cell_style<'a>(&self, font: &'a Font, font_index: u32, size: f64) -> TextStyle<'a> {
+        TextStyle { line_height: self.line_height, ..TextStyle::new(font, font_index, size) }
+    }
+
+    /// Font size of a cell's text after shrinking it to fit `width`
+    pub fn cell_font_size(&self, content: &str, width: f64, font: &Font) -> f64 {
+        self.text_fit().fitted_size(content, width, f64::INFINITY, &self.cell_style(font, 0, self.font_size), &self.line_break_options())
+    }
+
+    /// Height of a cell's text wrapped at `width`, after truncation and shrinking
+    pub fn cell_text_height(&self, content: &str, width: f64, font: &Font) -> f64 {
+        let size = self.cell_font_size(content, width, font);
+        let lines = self.text_fit().lines(content, width, f64::INFINITY, &self.cell_style(font, 0, size), &self.line_break_options());
+        LineMetrics::new(font, size, self.line_height).height(lines.len().max(1))
+    }
 }
 
 #[derive(Debug, Clone, Serialize, Deserialize)]
@@ -1,5 +1,10 @@
 use serde::{Deserialize, Serialize};
 use crate::core::color::Color;
+use crate::core::gradient::Gradient;
+use crate::core::layout::ImageMask;
+use crate::core::watermark::{Watermark, WatermarkContent};
+use crate::core::layer::Layer;
+use crate::core::text::{Hyphenation, LineBreaker, LineHeight, TabStop, TextAlign, TextDecoration, TextDirection, TextOverflow, WhiteSpace, WritingMode};
 use std::collections::HashMap;
 
 #[derive(Debug, Deserialize, Serialize, Clone, Default)]
@@ -15,7 +20,7 @@ pub struct Style {
     pub size: Option<f64>,
     pub color: Option<Color>,
     pub background_color: Option<Color>,
-    pub align: Option<String>,
+    pub align: Option<TextAlign>,
     pub width: Option<f64>,
     pub height: Option<f64>,
     pub padding: Option<f64>,
@@ -25,6 +30,54 @@ pub struct Style {
     pub cell_height: Option<f64>,
     pub x: Option<f64>,
     pub y: Option<f64>,
+    pub direction: Option<TextDirection>,
+    pub writing_mode: Option<WritingMode>,
+    pub white_space: Option<WhiteSpace>,
+    pub lang: Option<String>,
+    pub hyphenate: Option<bool>,
+    pub hyphen_min_prefix: Option<usize>,
+    pub hyphen_min_suffix: Option<usize>,
+    pub line_height: Option<LineHeight>,
+    pub letter_spacing: Option<f64>,
+    pub word_spacing: Option<f64>,
+    pub orphans: Option<usize>,
+    pub widows: Option<usize>,
+    pub keep_together: Option<bool>,
+    pub keep_with_next: Option<bool>,
+    pub max_lines: Option<usize>,
+    pub overflow: Option<TextOverflow>,
+    pub min_size: Option<f64>,
+    pub line_breaker: Option<LineBreaker>,
+    pub decoration: Option<TextDecoration>,
+    pub tab_stops: Option<Vec<TabStop>>,
+    pub marker: Option<String>,
+    pub indent: Option<f64>,
+    pub marker_gap: Option<f64>,
+    pub font_family: Option<String>,
+    pub weight: Option<u16>,
+    pub italic: Option<bool>,
+    pub gradient: Option<Gradient>,
+    pub clip: Option<bool>,
+    pub mask: Option<ImageMask>,
+    pub rotate: Option<f64>,
+    pub scale: Option<f64>,
+    pub layer: Option<String>,
+}
+
+impl Style {
+    /// Overrides the table settings this style sets
+    pub fn apply_to_table(&self, settings: &mut crate::core::table::TableSettings) {
+        if let Some(v) = self.padding { settings.padding = v; }
+        if let Some(v) = self.border { settings.border_width = v; }
+        if let Some(v) = self.header_height { settings.header_height = v; }
+        if let Some(v) = self.cell_height { settings.cell_height = v; }
+        if let Some(v) = self.size { settings.font_size = v; }
+        if let Some(v) = self.color.clone() { settings.font_color = v; }
+        if let Some(v) = self.line_height { settings.line_height = v; }
+        if let Some(v) = self.max_lines { settings.max_lines = Some(v); }
+        if let Some(v) = self.overflow { settings.overflow = v; }
+        if let Some(v) = self.min_size { settings.min_font_size = Some(v); }
+    }
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -44,6 +97,28 @@ pub enum TemplateNode {
         #[serde(default)]
         spacing: Option<f64>,
         #[serde(default)]
+        direction: Option<TextDirection>, // "rtl" mirrors child order
+        #[serde(default)]
+        style: Option<String>,
+    },
+    /// Bulleted or numbered list. A `List` among the items is nested in the item before it.
+    List {
+        items: Vec<TemplateNode>,
+        #[serde(default)]
+        marker: Option<String>, // "bullet" (default), "1.", "a)", "i.", "A.", "I.", "disc", "-", ...
+        #[serde(default)]
+        start: Option<usize>,
+        #[serde(default)]
+        indent: Option<f64>, // from the list's edge to item content
+        #[serde(default)]
+        marker_gap: Option<f64>,
+        #[serde(default)]
+        spacing: Option<f64>,
+        #[serde(default)]
+        size: Option<f64>, // marker size
+        #[serde(default)]
+        color: Option<Color>, // marker color
+        #[serde(default)]
         style: Option<String>,
     },
     /// Text block with simple string
@@ -56,7 +131,87 @@ pub enum TemplateNode {
         #[serde(default)]
         background_color: Option<Color>,
         #[serde(default)]
-        width: Option<f64>, // Max width for wrapping
+        width: Option<f64>, // Fixed box width; lines wrap at this width
+        #[serde(default)]
+        align: Option<TextAlign>, // "left", "center", "right" or "justify"
+        #[serde(default)]
+        direction: Option<TextDirection>, // "ltr", "rtl" or "auto"
+        #[serde(default)]
+        writing_mode: Option<WritingMode>, // "horizontal-tb" or "vertical-rl"
+        #[serde(default)]
+        white_space: Option<WhiteSpace>, // "normal", "pre", "pre-wrap" or "nowrap"
+        #[serde(default)]
+        lang: Option<String>, // e.g. "de"; enables hyphenation
+        #[serde(default)]
+        hyphenate: Option<bool>, // set false to turn hyphenation off for a language
+        #[serde(default)]
+        hyphen_min_prefix: Option<usize>,
+        #[serde(default)]
+        hyphen_min_suffix: Option<usize>,
+        #[serde(default)]
+        line_height: Option<LineHeight>, // multiple of size (1.5) or points ("18pt")
+        #[serde(default)]
+        letter_spacing: Option<f64>,
+        #[serde(default)]
+        word_spacing: Option<f64>,
+        #[serde(default)]
+        orphans: Option<usize>, // minimum lines before a page break (default 2)
+        #[serde(default)]
+        widows: Option<usize>, // minimum lines after a page break (default 2)
+        #[serde(default)]
+        max_lines: Option<usize>,
+        #[serde(default)]
+        overflow: Option<TextOverflow>, // "visible", "clip" or "ellipsis"
+        #[serde(default)]
+        min_size: Option<f64>, // shrink down to this size to fit the box
+        #[serde(default)]
+        line_breaker: Option<LineBreaker>, // "greedy" or "optimal"
+        #[serde(default)]
+        decoration: Option<TextDecoration>, // {"underline": true, "line_through": true, "highlight": {...}, "skip_ink": true}
+        #[serde(default)]
+        tab_stops: Option<Vec<TabStop>>, // [{"position": 300, "align": "right", "leader": "dots"}]
+        #[serde(default)]
+        style: Option<String>,
+    },
+    /// Paragraph of inline spans with their own font, weight, size, color, decorations and link
+    RichText {
+        spans: Vec<TextSpan>,
+        #[serde(default)]
+        size: Option<f64>,
+        #[serde(default)]
+        color: Option<Color>,
+        #[serde(default)]
+        align: Option<TextAlign>,
+        #[serde(default)]
+        white_space: Option<WhiteSpace>,
+        #[serde(default)]
+        lang: Option<String>,
+        #[serde(default)]
+        line_height: Option<LineHeight>,
+        #[serde(default)]
+        letter_spacing: Option<f64>,
+        #[serde(default)]
+        word_spacing: Option<f64>,
+        #[serde(default)]
+        orphans: Option<usize>,
+        #[serde(default)]
+        widows: Option<usize>,
+        #[serde(default)]
+        style: Option<String>,
+    },
+    /// Markdown converted to headings, paragraphs, lists, quotes, rules and tables.
+    /// Elements take their style from `styles` by name: "h1".."h6", "p", "strong", "em",
+    /// "code", "pre", "a", "ul", "ol", "li", "blockquote", "hr" and "table".
+    Markdown {
+        content: String, // literal or "{{ binding }}"
+        #[serde(default)]
+        size: Option<f64>,
+        #[serde(default)]
+        color: Option<Color>,
+        #[serde(default)]
+        spacing: Option<f64>, // between blocks
+        #[serde(default)]
+        style_prefix: Option<String>, // "md-" looks up "md-h1", "md-p", ...
         #[serde(default)]
         style: Option<String>,
     },
@@ -68,6 +223,8 @@ pub enum TemplateNode {
         #[serde(default)]
         height: Option<f64>,
         #[serde(default)]
+        mask: Option<ImageMask>, // {"type": "circle"}, {"type": "rounded", "radius": 8} or {"type": "path", "segments": [...]}
+        #[serde(default)]
         style: Option<String>,
     },
     /// Empty space or container, used for shapes, spacing, and styled blocks
@@ -86,12 +243,42 @@ pub enum TemplateNode {
         #[serde(default)]
         background_color: Option<Color>,
         #[serde(default)]
+        background_gradient: Option<Gradient>, // {"type": "linear", "angle": 90, "stops": [{"offset": 0, "color": ...}, ...]}
+        #[serde(default)]
+        clip: Option<bool>, // cut the child off at the rounded border box
+        #[serde(default)]
         width: Option<f64>,
         #[serde(default)]
         height: Option<f64>,
         #[serde(default)]
         style: Option<String>,
     },
+    /// Rotated, scaled or skewed child; its transformed bounding box takes up the space,
+    /// placed at the top-left of the area whatever point the child turns about
+    Transform {
+        child: Box<TemplateNode>,
+        #[serde(default)]
+        rotate: Option<f64>, // degrees, counterclockwise
+        #[serde(default)]
+        scale: Option<f64>, // both axes, overridden by scale_x / scale_y
+        #[serde(default)]
+        scale_x: Option<f64>,
+        #[serde(default)]
+        scale_y: Option<f64>,
+        #[serde(default)]
+        skew_x: Option<f64>, // degrees
+        #[serde(default)]
+        skew_y: Option<f64>,
+        #[serde(default)]
+        style: Option<String>,
+    },
+    /// Child placed on a layer named in the settings' `layers`
+    Layer {
+        child: Box<TemplateNode>,
+        layer: String,
+        #[serde(default)]
+        style: Option<String>,
+    },
     /// Table with columns and rows
     Table {
         columns: Vec<crate::core::table::TableColumn>,
@@ -110,7 +297,7 @@ pub enum TemplateNode {
         #[serde(default)]
         size: Option<f64>,
         #[serde(default)]
-        align: Option<String>,
+        align: Option<TextAlign>,
         #[serde(default)]
         style: Option<String>,
     },
@@ -139,6 +326,10 @@ pub struct TemplateSettings {
     pub size: Option<String>,
     pub orientation: Option<String>,
     pub margins: Option<Margins>,
+    /// Watermarks and backgrounds for every page; image `src` names an asset
+    pub watermarks: Option<Vec<Watermark>>,
+    /// Layers that nodes are placed on by name, registered with the document like assets
+    pub layers: Option<Vec<Layer>>,
 }
 
 #[derive(Debug, Deserialize, Serialize, Clone)]
@@ -154,6 +345,12 @@ pub struct Template {
     pub assets: HashMap<String, Vec<u8>>,
     #[serde(skip)]
     pub asset_indices: HashMap<String, u32>,
+    /// Layers registered with the document, by the name used in `layer` and styles
+    #[serde(skip)]
+    pub layer_indices: HashMap<String, u32>,
+    /// Font assets registered with the document, for rich text spans
+    #[serde(skip)]
+    pub fonts: FontSet,
 }
 
 impl Template {
@@ -162,6 +359,7 @@ impl Template {
         t.validate_version().map_err(|e| serde::de::Error::custom(e))?;
         t.assets = HashMap::new();
         t.asset_indices = HashMap::new();
+        t.layer_indices = HashMap::new();
         Ok(t)
     }
 
@@ -185,9 +383,8 @@ impl Template {
         if let Ok(mut style_file) = archive.by_name("styles.json") {
             let mut style_json = String::new();
             if std::io::Read::read_to_string(&mut style_file, &mut style_json).is_ok() {
-                if let Ok(styles) = serde_json::from_str::<HashMap<String, Style>>(&style_json) {
-                    template.styles.extend(styles);
-                }
+                let styles: HashMap<String, Style> = serde_json::from_str(&style_json).map_err(|e| format!("Failed to parse styles.json: {}", e))?;
+                template.styles.extend(styles);
             }
         }
 
@@ -206,6 +403,7 @@ impl Template {
         
         template.assets = HashMap::new();
         template.asset_indices = HashMap::new();
+        template.layer_indices = HashMap::new();
         
         // 4. Read all other files as assets
         for i in 0..archive.len() {
@@ -241,15 +439,34 @@ impl Template {
     }
 
     pub fn to_layout_node(&self) -> std::sync::Arc<dyn crate::core::layout::LayoutNode> {
-        self.root.to_layout_node(&serde_json::Value::Null, &self.asset_indices, &self.styles)
+        self.root.to_layout_node(&serde_json::Value::Null, &self.asset_indices, &self.layer_indices, &self.styles, &self.fonts)
+    }
+
+    /// The layers defined in the settings
+    pub fn layers(&self) -> Vec<Layer> {
+        self.settings.as_ref().and_then(|s| s.layers.clone()).unwrap_or_default()
+    }
+
+    /// The settings' watermarks, with image assets resolved to their registered index
+    pub fn watermarks(&self) -> Vec<Watermark> {
+        let watermarks = self.settings.as_ref().and_then(|s| s.watermarks.clone()).unwrap_or_default();
+        watermarks.into_iter().map(|mut watermark| {
+            if let WatermarkContent::Image { image, src: Some(src), .. } = &mut watermark.content
+                && image.is_none() {
+                *image = self.asset_indices.get(src.as_str()).copied();
+            }
+            watermark
+        }).collect()
     }
 
     pub fn render(&self, data: &serde_json::Value) -> std::sync::Arc<dyn crate::core::layout::LayoutNode> {
-        self.root.to_layout_node(data, &self.asset_indices, &self.styles)
+        self.root.to_layout_node(data, &self.asset_indices, &self.layer_indices, &self.styles, &self.fonts)
     }
 }
 
-use crate::core::layout::{LayoutNode as CoreLayoutNode, Column, Row, TextNode, ImageNode, Container, TableNode, PageNumberNode};
+use crate::core::layout::{LayoutNode as CoreLayoutNode, Column, Row, TextNode, RichTextNode, TextSpan, ImageNode, Container, TableNode, PageNumberNode, Keep, ListItem, ListNode, TransformNode, LayerNode};
+use crate::core::font::FontSet;
+use crate::core::markdown::Markdown;
 use std::sync::Arc;
 use serde_json::Value;
 
@@ -330,10 +547,105 @@ fn resolve_option<T: Clone>(val: Option<T>, style: Option<&String>, styles: &Has
 }
 
 impl TemplateNode {
-    pub fn to_layout_node(&self, data: &Value, asset_indices: &HashMap<String, u32>, styles: &HashMap<String, Style>) -> Arc<dyn CoreLayoutNode> {
+    /// Name of the style the node refers to
+    pub fn style(&self) -> Option<&String> {
+        match self {
+            TemplateNode::Text { style, .. } |
+            TemplateNode::RichText { style, .. } |
+            TemplateNode::Markdown { style, .. } |
+            TemplateNode::Image { style, .. } |
+            TemplateNode::Container { style, .. } |
+            TemplateNode::Transform { style, .. } |
+            TemplateNode::Layer { style, .. } |
+            TemplateNode::Column { style, .. } |
+            TemplateNode::Row { style, .. } |
+            TemplateNode::List { style, .. } |
+            TemplateNode::Table { style, .. } |
+            TemplateNode::PageNumber { style, .. } |
+            TemplateNode::Canvas { style, .. } => style.as_ref(),
+        }
+    }
+
+    pub fn to_layout_node(&self, data: &Value, asset_indices: &HashMap<String, u32>, layer_indices: &HashMap<String, u32>, styles: &HashMap<String, Style>, fonts: &FontSet) -> Arc<dyn CoreLayoutNode> {
+        let node = self.build_layout_node(data, asset_indices, layer_indices, styles, fonts);
+        let node = self.wrap_layer(node, layer_indices, styles);
+        self.wrap_keep(node, styles)
+    }
+
+    /// A layer from the style applies to any node
+    fn wrap_layer(&self, node: Arc<dyn CoreLayoutNode>, layer_indices: &HashMap<String, u32>, styles: &HashMap<String, Style>) -> Arc<dyn CoreLayoutNode> {
+        if matches!(self, TemplateNode::Layer { .. }) {
+            return node;
+        }
+        match resolve_option(None, self.style(), styles, |s| s.layer.clone()).and_then(|name| layer_indices.get(&name)) {
+            Some(layer) => Arc::new(LayerNode { child: node, layer: *layer }),
+            None => node,
+        }
+    }
+
+    /// Page break rules from the style apply to any node
+    fn wrap_keep(&self, node: Arc<dyn CoreLayoutNode>, styles: &HashMap<String, Style>) -> Arc<dyn CoreLayoutNode> {
+        let style = self.style();
+        let together = resolve_prop(None, style, styles, |s| s.keep_together, false);
+        let with_next = resolve_prop(None, style, styles, |s| s.keep_with_next, false);
+        if together || with_next {
+            return Arc::new(Keep { child: node, together, with_next });
+        }
+        node
+    }
+
+    /// Builds a `List` nested `level` deep; nested lists pick their default bullet by level
+    fn build_list(&self, level: usize, data: &Value, asset_indices: &HashMap<String, u32>, layer_indices: &HashMap<String, u32>, styles: &HashMap<String, Style>, fonts: &FontSet) -> Arc<dyn CoreLayoutNode> {
+        let TemplateNode::List { items, marker, start, indent, marker_gap, spacing, size, color, style } = self else {
+            return self.to_layout_node(data, asset_indices, layer_indices, styles, fonts);
+        };
+        let spacing_val = resolve_prop(*spacing, style.as_ref(), styles, |s| s.spacing, 0.0);
+
+        // (parts, marker): nested lists join the item before them
+        let mut parts: Vec<(Vec<Arc<dyn CoreLayoutNode>>, bool)> = Vec::new();
+        for item in items {
+            if matches!(item, TemplateNode::List { .. }) {
+                let nested = item.wrap_keep(item.build_list(level + 1, data, asset_indices, layer_indices, styles, fonts), styles);
+                match parts.last_mut() {
+                    Some((children, _)) => children.push(nested),
+                    None => parts.push((vec![nested], false)),
+                }
+            } else {
+                parts.push((vec![item.to_layout_node(data, asset_indices, layer_indices, styles, fonts)], true));
+            }
+        }
+        let list_items = parts.into_iter().map(|(mut children, marker)| {
+            let content = if children.len() == 1 {
+                children.remove(0)
+            } else {
+                Arc::new(Column {
+                    children,
+                    spacing: spacing_val,
+                    align_items: crate::core::layout::FlexAlign::Start,
+                    justify_content: crate::core::layout::FlexJustify::Start,
+                })
+            };
+            ListItem { content, marker }
+        }).collect();
+
+        let marker_val = resolve_option(marker.clone(), style.as_ref(), styles, |s| s.marker.clone());
+        Arc::new(ListNode {
+            items: list_items,
+            marker: marker_val.as_deref().and_then(|v| v.parse().ok()).unwrap_or_default(),
+            start: start.unwrap_or(1),
+            level,
+            indent: resolve_prop(*indent, style.as_ref(), styles, |s| s.indent, 18.0),
+            marker_gap: resolve_prop(*marker_gap, style.as_ref(), styles, |s| s.marker_gap, 6.0),
+            spacing: spacing_val,
+            marker_size: resolve_prop(*size, style.as_ref(), styles, |s| s.size, 12.0),
+            marker_color: resolve_option(color.clone(), style.as_ref(), styles, |s| s.color.clone()),
+        })
+    }
+
+    fn build_layout_node(&self, data: &Value, asset_indices: &HashMap<String, u32>, layer_indices: &HashMap<String, u32>, styles: &HashMap<String, Style>, fonts: &FontSet) -> Arc<dyn CoreLayoutNode> {
         match self {
             TemplateNode::Column { children, spacing, style } => {
-                let nodes: Vec<Arc<dyn CoreLayoutNode>> = children.iter().map(|c| c.to_layout_node(data, asset_indices, styles)).collect();
+                let nodes: Vec<Arc<dyn CoreLayoutNode>> = children.iter().map(|c| c.to_layout_node(data, asset_indices, layer_indices, styles, fonts)).collect();
                 let spacing_val = resolve_prop(*spacing, style.as_ref(), styles, |s| s.spacing, 0.0);
                 Arc::new(Column { 
                     children: nodes, 
@@ -342,51 +654,152 @@ impl TemplateNode {
                     justify_content: crate::core::layout::FlexJustify::Start 
                 })
             },
-            TemplateNode::Row { children, spacing, style } => {
-                let nodes = children.iter().map(|c| c.to_layout_node(data, asset_indices, styles)).collect();
+            TemplateNode::List { .. } => self.build_list(0, data, asset_indices, layer_indices, styles, fonts),
+            TemplateNode::Row { children, spacing, direction, style } => {
+                let nodes = children.iter().map(|c| c.to_layout_node(data, asset_indices, layer_indices, styles, fonts)).collect();
                 let spacing_val = resolve_prop(*spacing, style.as_ref(), styles, |s| s.spacing, 0.0);
+                let direction_val = resolve_prop(*direction, style.as_ref(), styles, |s| s.direction, TextDirection::Ltr);
                 Arc::new(Row { 
                     children: nodes, 
                     spacing: spacing_val, 
                     align_items: crate::core::layout::FlexAlign::Start, 
-                    justify_content: crate::core::layout::FlexJustify::Start 
+                    justify_content: crate::core::layout::FlexJustify::Start,
+                    direction: direction_val,
                 })
             },
-            TemplateNode::Text { content, size, color, background_color, width: _, style } => {
+            TemplateNode::Text { content, size, color, background_color, width, align, direction, writing_mode, white_space, lang, hyphenate, hyphen_min_prefix, hyphen_min_suffix, line_height, letter_spacing, word_spacing, orphans, widows, max_lines, overflow, min_size, line_breaker, decoration, tab_stops, style } => {
                 // Resolve content
                 let resolved = resolve_template_string(content, data);
                 let size_val = resolve_prop(*size, style.as_ref(), styles, |s| s.size, 12.0);
-                let color_val = resolve_option(*color, style.as_ref(), styles, |s| s.color);
-                let bg_val = resolve_option(*background_color, style.as_ref(), styles, |s| s.background_color);
+                let color_val = resolve_option(color.clone(), style.as_ref(), styles, |s| s.color.clone());
+                let bg_val = resolve_option(background_color.clone(), style.as_ref(), styles, |s| s.background_color.clone());
+                let direction_val = resolve_prop(*direction, style.as_ref(), styles, |s| s.direction, TextDirection::Auto);
+                let writing_mode_val = resolve_prop(*writing_mode, style.as_ref(), styles, |s| s.writing_mode, WritingMode::HorizontalTb);
+                let white_space_val = resolve_prop(*white_space, style.as_ref(), styles, |s| s.white_space, WhiteSpace::Normal);
+                let width_val = resolve_option(*width, style.as_ref(), styles, |s| s.width);
+                let align_val = resolve_option(*align, style.as_ref(), styles, |s| s.align);
+                let lang_val = resolve_option(lang.clone(), style.as_ref(), styles, |s| s.lang.clone());
+                let hyphenate_val = resolve_prop(*hyphenate, style.as_ref(), styles, |s| s.hyphenate, true);
+                let line_height_val = resolve_prop(*line_height, style.as_ref(), styles, |s| s.line_height, LineHeight::Normal);
+                let letter_spacing_val = resolve_prop(*letter_spacing, style.as_ref(), styles, |s| s.letter_spacing, 0.0);
+                let word_spacing_val = resolve_prop(*word_spacing, style.as_ref(), styles, |s| s.word_spacing, 0.0);
+                let hyphenation = lang_val.filter(|_| hyphenate_val).map(|lang| {
+                    let defaults = Hyphenation::new(&lang);
+                    Hyphenation {
+                        min_prefix: resolve_prop(*hyphen_min_prefix, style.as_ref(), styles, |s| s.hyphen_min_prefix, defaults.min_prefix),
+                        min_suffix: resolve_prop(*hyphen_min_suffix, style.as_ref(), styles, |s| s.hyphen_min_suffix, defaults.min_suffix),
+                        lang,
+                    }
+                });
 
                 Arc::new(TextNode {
                      text: resolved, 
                      size: size_val, 
                      color: color_val, 
-                     background_color: bg_val 
+                     background_color: bg_val,
+                     direction: direction_val,
+                     writing_mode: writing_mode_val,
+                     white_space: white_space_val,
+                     hyphenation,
+                     align: align_val,
+                     width: width_val,
+                     line_height: line_height_val,
+                     letter_spacing: letter_spacing_val,
+                     word_spacing: word_spacing_val,
+                     orphans: resolve_prop(*orphans, style.as_ref(), styles, |s| s.orphans, 2),
+                     widows: resolve_prop(*widows, style.as_ref(), styles, |s| s.widows, 2),
+                     max_lines: resolve_option(*max_lines, style.as_ref(), styles, |s| s.max_lines),
+                     overflow: resolve_prop(*overflow, style.as_ref(), styles, |s| s.overflow, TextOverflow::Visible),
+                     min_size: resolve_option(*min_size, style.as_ref(), styles, |s| s.min_size),
+                     line_breaker: resolve_prop(*line_breaker, style.as_ref(), styles, |s| s.line_breaker, LineBreaker::Greedy),
+                     continues: false,
+                     decoration: resolve_prop(decoration.clone(), style.as_ref(), styles, |s| s.decoration.clone(), TextDecoration::default()),
+                     tab_stops: resolve_prop(tab_stops.clone(), style.as_ref(), styles, |s| s.tab_stops.clone(), Vec::new()),
                 })
             },
-            TemplateNode::Container { child, padding, margin, border, border_color, border_radius, background_color, width, height, style } => {
+            TemplateNode::RichText { spans, size, color, align, white_space, lang, line_height, letter_spacing, word_spacing, orphans, widows, style } => {
+                let size_val = resolve_prop(*size, style.as_ref(), styles, |s| s.size, 12.0);
+                let color_val = resolve_option(color.clone(), style.as_ref(), styles, |s| s.color.clone());
+                let align_val = resolve_option(*align, style.as_ref(), styles, |s| s.align);
+                let white_space_val = resolve_prop(*white_space, style.as_ref(), styles, |s| s.white_space, WhiteSpace::Normal);
+                let lang_val = resolve_option(lang.clone(), style.as_ref(), styles, |s| s.lang.clone());
+                let line_height_val = resolve_prop(*line_height, style.as_ref(), styles, |s| s.line_height, LineHeight::Normal);
+                let letter_spacing_val = resolve_prop(*letter_spacing, style.as_ref(), styles, |s| s.letter_spacing, 0.0);
+                let word_spacing_val = resolve_prop(*word_spacing, style.as_ref(), styles, |s| s.word_spacing, 0.0);
+                let spans = spans.iter()
+                    .map(|span| TextSpan { text: resolve_template_string(&span.text, data), ..span.clone() })
+                    .collect();
+
+                Arc::new(RichTextNode {
+                    spans,
+                    size: size_val,
+                    color: color_val,
+                    fonts: fonts.clone(),
+                    align: align_val,
+                    white_space: white_space_val,
+                    hyphenation: lang_val.as_deref().map(Hyphenation::new),
+                    line_height: line_height_val,
+                    letter_spacing: letter_spacing_val,
+                    word_spacing: word_spacing_val,
+                    orphans: resolve_prop(*orphans, style.as_ref(), styles, |s| s.orphans, 2),
+                    widows: resolve_prop(*widows, style.as_ref(), styles, |s| s.widows, 2),
+                    continues: false,
+                })
+            },
+            TemplateNode::Markdown { content, size, color, spacing, style_prefix, style } => {
+                let source = resolve_template_string(content, data);
+                Markdown {
+                    styles,
+                    base: style.as_ref().and_then(|name| styles.get(name)),
+                    prefix: style_prefix.as_deref().unwrap_or(""),
+                    fonts,
+                    size: resolve_prop(*size, style.as_ref(), styles, |s| s.size, 12.0),
+                    color: resolve_option(color.clone(), style.as_ref(), styles, |s| s.color.clone()),
+                    spacing: resolve_prop(*spacing, style.as_ref(), styles, |s| s.spacing, 8.0),
+                }.to_layout_node(&source)
+            },
+            TemplateNode::Container { child, padding, margin, border, border_color, border_radius, background_color, background_gradient, clip, width, height, style } => {
                  let padding_val = resolve_prop(*padding, style.as_ref(), styles, |s| s.padding, 0.0);
                  let margin_val = resolve_prop(*margin, style.as_ref(), styles, |s| s.padding, 0.0);
                  let border_val = resolve_prop(*border, style.as_ref(), styles, |s| s.border, 0.0);
                  let w_val = resolve_prop(*width, style.as_ref(), styles, |s| s.width, 0.0);
                  let h_val = resolve_prop(*height, style.as_ref(), styles, |s| s.height, 0.0);
-                 let bg = background_color.or_else(|| resolve_option(None, style.as_ref(), styles, |s| s.background_color));
+                 let bg = background_color.clone().or_else(|| resolve_option(None, style.as_ref(), styles, |s| s.background_color.clone()));
+                 let gradient = resolve_option(background_gradient.clone(), style.as_ref(), styles, |s| s.gradient.clone());
                  
                  Arc::new(Container {
-                     child: child.to_layout_node(data, asset_indices, styles),
+                     child: child.to_layout_node(data, asset_indices, layer_indices, styles, fonts),
                      padding: crate::core::layout::Spacing::uniform(padding_val),
                      margin: crate::core::layout::Spacing::uniform(margin_val),
                      border_width: crate::core::layout::Spacing::uniform(border_val),
-                     border_color: border_color.or(None),
+                     border_color: border_color.clone(),
                      border_radius: border_radius.unwrap_or(0.0),
                      width: w_val,
                      height: h_val,
                      background_color: bg,
+                     background_gradient: gradient,
+                     clip: resolve_prop(*clip, style.as_ref(), styles, |s| s.clip, false),
                  })
             },
-            TemplateNode::Image { src, width, height, style } => {
+            TemplateNode::Transform { child, rotate, scale, scale_x, scale_y, skew_x, skew_y, style } => {
+                let scale_val = resolve_prop(*scale, style.as_ref(), styles, |s| s.scale, 1.0);
+                Arc::new(TransformNode {
+                    child: child.to_layout_node(data, asset_indices, layer_indices, styles, fonts),
+                    rotate: resolve_prop(*rotate, style.as_ref(), styles, |s| s.rotate, 0.0),
+                    scale_x: scale_x.unwrap_or(scale_val),
+                    scale_y: scale_y.unwrap_or(scale_val),
+                    skew_x: skew_x.unwrap_or(0.0),
+                    skew_y: skew_y.unwrap_or(0.0),
+                })
+            },
+            TemplateNode::Layer { child, layer, .. } => {
+                let child = child.to_layout_node(data, asset_indices, layer_indices, styles, fonts);
+                match layer_indices.get(layer) {
+                    Some(layer) => Arc::new(LayerNode { child, layer: *layer }),
+                    None => child,
+                }
+            },
+            TemplateNode::Image { src, width, height, mask, style } => {
                 let index = *asset_indices.get(src).unwrap_or(&0);
                 let w_val = resolve_prop(*width, style.as_ref(), styles, |s| s.width, 100.0);
                 let h_val = resolve_prop(*height, style.as_ref(), styles, |s| s.height, 100.0);
@@ -394,7 +807,8 @@ impl TemplateNode {
                 Arc::new(ImageNode {
                     image_index: index, 
                     width: w_val, 
-                    height: h_val 
+                    height: h_val,
+                    mask: resolve_option(mask.clone(), style.as_ref(), styles, |s| s.mask.clone()),
                 })
             },
             TemplateNode::Table { columns, rows, settings, data: data_path, style } => {
@@ -405,12 +819,7 @@ impl TemplateNode {
                  // Apply style overrides if settings were defaults or just to inherit
                  if let Some(style_name) = style {
                      if let Some(s) = styles.get(style_name) {
-                         if let Some(v) = s.padding { resolved_settings.padding = v; }
-                         if let Some(v) = s.border { resolved_settings.border_width = v; }
-                         if let Some(v) = s.header_height { resolved_settings.header_height = v; }
-                         if let Some(v) = s.cell_height { resolved_settings.cell_height = v; }
-                         if let Some(v) = s.size { resolved_settings.font_size = v; }
-                         if let Some(v) = s.color { resolved_settings.font_color = v; }
+                         s.apply_to_table(&mut resolved_settings);
                      }
                  }
 
@@ -461,7 +870,7 @@ impl TemplateNode {
             },
             TemplateNode::PageNumber { format, size, align, style } => {
                 let size_val = resolve_prop(*size, style.as_ref(), styles, |s| s.size, 10.0);
-                let align_val = resolve_prop(align.clone(), style.as_ref(), styles, |s| s.align.clone(), "left".to_string());
+                let align_val = resolve_prop(*align, style.as_ref(), styles, |s| s.align, TextAlign::Left);
                 
                 Arc::new(PageNumberNode {
                     format: format.clone(),
@@ -475,23 +884,10 @@ impl TemplateNode {
                 
                 let mut positioned_children = Vec::new();
                 for child in children {
-                    let node = child.to_layout_node(data, asset_indices, styles);
+                    let node = child.to_layout_node(data, asset_indices, layer_indices, styles, fonts);
                     
-                    // Helper to get style from TemplateNode
-                    let (x, y) = match child {
-                        TemplateNode::Text { style, .. } |
-                        TemplateNode::Image { style, .. } |
-                        TemplateNode::Container { style, .. } |
-                        TemplateNode::Column { style, .. } |
-                        TemplateNode::Row { style, .. } |
-                        TemplateNode::Table { style, .. } |
-                        TemplateNode::PageNumber { style, .. } |
-                        TemplateNode::Canvas { style, .. } => {
-                             let x = resolve_prop(None, style.as_ref(), styles, |s| s.x, 0.0);
-                             let y = resolve_prop(None, style.as_ref(), styles, |s| s.y, 0.0);
-                             (x, y)
-                        }
-                    };
+                    let x = resolve_prop(None, child.style(), styles, |s| s.x, 0.0);
+                    let y = resolve_prop(None, child.style(), styles, |s| s.y, 0.0);
                     
                     positioned_children.push((node, x, y));
                 }
@@ -535,3 +931,48 @@ fn get_value_by_path<'a>(path: &str, data: &'a Value) -> Option<&'a Value> {
     }
     Some(current)
 }
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::core::font::Font;
+    use crate::core::layout::{PageContext, Rect};
+    use crate::core::page::Page;
+
+    fn roboto() -> Font {
+        Font::from_bytes(include_bytes!("../../Roboto-Regular.ttf").to_vec(), "Roboto".to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_layer_and_image_sharing_a_name() {
+        let json = r#"{
+            "root": { "type": "Column", "children": [
+                { "type": "Layer", "layer": "logo", "child": { "type": "Image", "src": "logo", "width": 20, "height": 20 } },
+                { "type": "Image", "src": "logo", "width": 20, "height": 20, "style": "overlay" }
+            ] },
+            "styles": { "overlay": { "layer": "logo" } }
+        }"#;
+        let mut template = Template::from_json(json).unwrap();
+        template.asset_indices.insert("logo".to_string(), 5);
+        template.layer_indices.insert("logo".to_string(), 1);
+
+        let mut page = Page::new(200.0, 200.0);
+        let area = Rect { x: 0.0, y: 200.0, width: 200.0, height: 200.0 };
+        template.to_layout_node().render(&mut page, area, &roboto(), 0, &PageContext::default());
+        let content = String::from_utf8_lossy(&page.content);
+        assert_eq!(content.matches("/OC /OC1 BDC").count(), 2);
+        assert_eq!(content.matches("/Im5 Do").count(), 2);
+    }
+
+    #[test]
+    fn test_unknown_style_values_rejected() {
+        let json = |align: &str| format!(r#"{{
+            "root": {{ "type": "Text", "content": "Hello", "style": "body" }},
+            "styles": {{ "body": {{ "align": "{}", "line_height": "1.5" }} }}
+        }}"#, align);
+        assert!(Template::from_json(&json("center")).is_ok());
+        let error = Template::from_json(&json("centre")).unwrap_err().to_string();
+        assert!(error.contains("centre"), "{}", error);
+        assert!(Template::from_json(r#"{ "root": { "type": "Text", "content": "Hi" }, "styles": { "body": { "line_height": "1.5x" } } }"#).is_err());
+    }
+}
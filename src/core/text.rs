@@ -1,176 +1,1402 @@
-use crate::core::font::Font;
+use crate::core::color::Color;
+use crate::core::font::{Font, ShapedGlyph};
+use serde::{Deserialize, Serialize};
+use unicode_bidi::{BidiInfo, Level};
+use crate::core::parse::ParseValueError;
+use std::str::FromStr;
 
-/// Calculate how many lines are needed for text with wrapping
-/// Implements character-level breaking for long words
-pub fn calculate_text_lines(text: &str, width: f64, size: f64, font: &Font) -> usize {
-    if text.is_empty() {
-        return 1;
-    }
-    
-    let words: Vec<&str> = text.split_whitespace().collect();
-    let mut buffer = Vec::new();
-    let mut line_count = 0;
-    
-    for word in words {
-        // Check if word alone is wider than available width
-        let word_width = font.measure_text(word, size);
-        
-        if word_width > width {
-            // Word needs character-level breaking
-            // First, count the current buffer as a line if not empty
-            if !buffer.is_empty() {
-                line_count += 1;
-                buffer.clear();
+/// Paragraph direction used for bidirectional text.
+/// `Auto` picks the direction of the first strong character (UBA rules P2/P3).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextDirection {
+    #[default]
+    Auto,
+    Ltr,
+    Rtl,
+}
+
+impl FromStr for TextDirection {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "ltr" => Self::Ltr,
+            "rtl" => Self::Rtl,
+            "auto" => Self::Auto,
+            _ => return Err(ParseValueError::new("text direction", s)),
+        })
+    }
+}
+
+impl TextDirection {
+    /// Resolve `Auto` against the paragraph text. Never returns `Auto`.
+    pub fn resolve(self, text: &str) -> Self {
+        match self {
+            Self::Auto => match unicode_bidi::get_base_direction(text) {
+                unicode_bidi::Direction::Rtl => Self::Rtl,
+                _ => Self::Ltr,
+            },
+            other => other,
+        }
+    }
+
+    pub fn is_rtl(self) -> bool {
+        self == Self::Rtl
+    }
+}
+
+/// Writing mode of a text block.
+/// `VerticalRl` sets glyphs top-to-bottom in columns that advance right-to-left (tategaki).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WritingMode {
+    #[default]
+    HorizontalTb,
+    VerticalRl,
+}
+
+impl FromStr for WritingMode {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "vertical-rl" | "vertical" => Self::VerticalRl,
+            "horizontal-tb" | "horizontal" => Self::HorizontalTb,
+            _ => return Err(ParseValueError::new("writing mode", s)),
+        })
+    }
+}
+
+impl WritingMode {
+    pub fn is_vertical(self) -> bool {
+        self == Self::VerticalRl
+    }
+}
+
+/// Horizontal alignment of lines within their box.
+/// `Justify` stretches the spaces of every line except the last line of a paragraph.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextAlign {
+    #[default]
+    Left,
+    Center,
+    Right,
+    Justify,
+}
+
+impl FromStr for TextAlign {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "center" => Self::Center,
+            "right" => Self::Right,
+            "justify" => Self::Justify,
+            "left" => Self::Left,
+            _ => return Err(ParseValueError::new("text alignment", s)),
+        })
+    }
+}
+
+/// Distance between the baselines of consecutive lines.
+/// In JSON a number is a multiple of the font size (`1.5`); a string may also
+/// give points (`"18pt"`) or `"normal"`.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+#[serde(try_from = "LineHeightValue", into = "LineHeightValue")]
+pub enum LineHeight {
+    /// 1.2 times the font size
+    #[default]
+    Normal,
+    /// Multiple of the font size
+    Multiple(f64),
+    /// Fixed distance in points
+    Absolute(f64),
+}
+
+impl FromStr for LineHeight {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        let value = s.trim();
+        let parsed = match value.strip_suffix("pt") {
+            Some(points) => points.trim().parse().map(Self::Absolute),
+            None if value == "normal" => Ok(Self::Normal),
+            None => value.parse().map(Self::Multiple),
+        };
+        parsed.map_err(|_| ParseValueError::new("line height", s))
+    }
+}
+
+impl LineHeight {
+    /// Baseline-to-baseline distance for text of `size`
+    pub fn leading(self, size: f64) -> f64 {
+        match self {
+            Self::Normal => size * 1.2,
+            Self::Multiple(factor) => size * factor,
+            Self::Absolute(points) => points,
+        }
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum LineHeightValue {
+    Multiple(f64),
+    Text(String),
+}
+
+impl TryFrom<LineHeightValue> for LineHeight {
+    type Error = ParseValueError;
+
+    fn try_from(value: LineHeightValue) -> Result<Self, Self::Error> {
+        match value {
+            LineHeightValue::Multiple(factor) => Ok(Self::Multiple(factor)),
+            LineHeightValue::Text(s) => s.parse(),
+        }
+    }
+}
+
+impl From<LineHeight> for LineHeightValue {
+    fn from(value: LineHeight) -> Self {
+        match value {
+            LineHeight::Normal => Self::Text("normal".to_string()),
+            LineHeight::Multiple(factor) => Self::Multiple(factor),
+            LineHeight::Absolute(points) => Self::Text(format!("{}pt", points)),
+        }
+    }
+}
+
+/// Vertical metrics of the lines of a paragraph set in one font and size.
+/// Every line box is `leading` tall; the space left over after the font's ascent and
+/// descent is shared equally above and below the glyphs, as CSS does.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct LineMetrics {
+    pub ascent: f64,
+    /// Depth below the baseline, as a positive distance
+    pub descent: f64,
+    pub leading: f64,
+}
+
+impl LineMetrics {
+    pub fn new(font: &Font, size: f64, line_height: LineHeight) -> Self {
+        LineMetrics {
+            ascent: font.ascent() as f64 * size / 1000.0,
+            descent: -(font.descent() as f64) * size / 1000.0,
+            leading: line_height.leading(size),
+        }
+    }
+
+    /// Distance from the top of a line box down to its baseline
+    pub fn baseline_offset(&self) -> f64 {
+        (self.leading - self.ascent - self.descent) / 2.0 + self.ascent
+    }
+
+    /// Height of `lines` line boxes
+    pub fn height(&self, lines: usize) -> f64 {
+        lines as f64 * self.leading
+    }
+
+    /// Number of whole lines that fit in `height`
+    pub fn lines_in(&self, height: f64) -> usize {
+        if self.leading <= 0.0 {
+            return usize::MAX;
+        }
+        // Tolerate rounding when height is an exact multiple of the leading
+        ((height + 1e-6) / self.leading).floor().max(0.0) as usize
+    }
+}
+
+/// Split a single line into directional runs in visual (left-to-right display) order.
+/// Each run is returned with a flag telling whether it must be shaped right-to-left.
+pub fn visual_runs(line: &str, direction: TextDirection) -> Vec<(&str, bool)> {
+    if line.is_empty() {
+        return Vec::new();
+    }
+
+    let base_level = if direction.is_rtl() { Level::rtl() } else { Level::ltr() };
+    let bidi = BidiInfo::new(line, Some(base_level));
+
+    // Pure LTR text needs no reordering
+    if !bidi.has_rtl() {
+        return vec![(line, false)];
+    }
+
+    let mut runs = Vec::new();
+    for para in &bidi.paragraphs {
+        let (levels, level_runs) = bidi.visual_runs(para, para.range.clone());
+        for run in level_runs {
+            let rtl = levels[run.start].is_rtl();
+            runs.push((&line[run], rtl));
+        }
+    }
+    runs
+}
+
+/// Shape one line of text and return its glyphs in visual order.
+/// Right-to-left runs are shaped with RTL direction so Arabic joins and mirroring apply.
+pub fn shape_line(font: &Font, line: &str, size: f64, direction: TextDirection) -> Vec<ShapedGlyph> {
+    visual_runs(line, direction)
+        .into_iter()
+        .flat_map(|(run, rtl)| font.shape_text_directed(run, size, rtl))
+        .collect()
+}
+
+/// How whitespace and line breaks in the source text are treated (CSS `white-space`).
+/// Mandatory breaks (`\n`, `\r\n`, U+2028 ...) are honored in every mode.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WhiteSpace {
+    /// Collapse runs of spaces and wrap at line break opportunities
+    #[default]
+    Normal,
+    /// Preserve spaces, never wrap
+    Pre,
+    /// Preserve spaces and wrap at line break opportunities
+    PreWrap,
+    /// Collapse runs of spaces, never wrap
+    Nowrap,
+}
+
+impl FromStr for WhiteSpace {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s {
+            "pre" => Self::Pre,
+            "pre-wrap" => Self::PreWrap,
+            "nowrap" => Self::Nowrap,
+            "normal" => Self::Normal,
+            _ => return Err(ParseValueError::new("white space mode", s)),
+        })
+    }
+}
+
+impl WhiteSpace {
+    pub fn wraps(self) -> bool {
+        matches!(self, Self::Normal | Self::PreWrap)
+    }
+
+    pub fn collapses(self) -> bool {
+        matches!(self, Self::Normal | Self::Nowrap)
+    }
+}
+
+/// Pattern-based hyphenation (Liang/TeX patterns) for one language.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Hyphenation {
+    /// BCP 47 language tag, e.g. "de" or "en-US". Only the primary subtag is used.
+    pub lang: String,
+    /// Minimum number of characters before a hyphen
+    pub min_prefix: usize,
+    /// Minimum number of characters after a hyphen
+    pub min_suffix: usize,
+}
+
+impl Hyphenation {
+    pub fn new(lang: &str) -> Self {
+        Self { lang: lang.to_string(), min_prefix: 2, min_suffix: 3 }
+    }
+
+    fn language(&self) -> Option<hypher::Lang> {
+        let primary = self.lang.split(['-', '_']).next()?.to_ascii_lowercase();
+        let code: [u8; 2] = primary.as_bytes().try_into().ok()?;
+        hypher::Lang::from_iso(code)
+    }
+
+    /// Whether patterns are bundled for this language
+    pub fn is_supported(&self) -> bool {
+        self.language().is_some()
+    }
+
+    /// Byte offsets inside `word` where it may be hyphenated
+    pub fn break_points(&self, word: &str) -> Vec<usize> {
+        let Some(lang) = self.language() else {
+            return Vec::new();
+        };
+
+        let mut points = Vec::new();
+        let mut offset = 0;
+        for syllable in hypher::hyphenate_bounded(word, lang, self.min_prefix.max(1), self.min_suffix.max(1)) {
+            offset += syllable.len();
+            if offset < word.len() {
+                points.push(offset);
+            }
+        }
+        points
+    }
+}
+
+/// How a paragraph is divided into lines.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum LineBreaker {
+    /// Fill each line as far as it goes, then start the next one
+    #[default]
+    Greedy,
+    /// Choose all breaks of a paragraph together so spacing is as even as possible
+    /// (Knuth–Plass total fit)
+    Optimal,
+}
+
+impl FromStr for LineBreaker {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "optimal" | "total-fit" | "knuth-plass" => Self::Optimal,
+            "greedy" | "first-fit" => Self::Greedy,
+            _ => return Err(ParseValueError::new("line breaker", s)),
+        })
+    }
+}
+
+/// Where the text after a tab lines up against its tab stop.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TabAlign {
+    /// Text starts at the stop
+    #[default]
+    Left,
+    /// Text ends at the stop
+    Right,
+    /// Text is centered on the stop
+    Center,
+    /// The decimal point sits on the stop; text without one ends there
+    Decimal,
+}
+
+impl FromStr for TabAlign {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "right" | "end" => Self::Right,
+            "center" => Self::Center,
+            "decimal" => Self::Decimal,
+            "left" | "start" => Self::Left,
+            _ => return Err(ParseValueError::new("tab alignment", s)),
+        })
+    }
+}
+
+/// Characters repeated across the gap a tab leaves.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TabLeader {
+    #[default]
+    None,
+    Dots,
+    Dashes,
+    Underline,
+}
+
+impl FromStr for TabLeader {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "dots" | "dot" | "." => Self::Dots,
+            "dashes" | "dash" | "-" => Self::Dashes,
+            "underline" | "_" => Self::Underline,
+            "none" | "" => Self::None,
+            _ => return Err(ParseValueError::new("tab leader", s)),
+        })
+    }
+}
+
+impl TabLeader {
+    pub fn char(&self) -> Option<char> {
+        match self {
+            Self::None => None,
+            Self::Dots => Some('.'),
+            Self::Dashes => Some('-'),
+            Self::Underline => Some('_'),
+        }
+    }
+}
+
+/// A tab position, in points from the start of the line.
+#[derive(Debug, Clone, Copy, PartialEq, Default, Serialize, Deserialize)]
+pub struct TabStop {
+    pub position: f64,
+    #[serde(default)]
+    pub align: TabAlign,
+    #[serde(default)]
+    pub leader: TabLeader,
+}
+
+/// Tabs past the last tab stop advance to the next multiple of this, in points
+pub const DEFAULT_TAB_INTERVAL: f64 = 36.0;
+
+/// A line of text with its tabs resolved: the pieces between tabs at their offsets
+/// from the line start, and the leaders in front of them.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TabLayout {
+    /// (x offset, text)
+    pub pieces: Vec<(f64, String)>,
+    /// (x offset, leader text)
+    pub leaders: Vec<(f64, String)>,
+    pub width: f64,
+}
+
+/// Options shared by every line breaking entry point, so measuring, splitting
+/// and rendering a piece of text always produce the same lines.
+#[derive(Debug, Clone, Default, PartialEq)]
+pub struct LineBreakOptions {
+    pub white_space: WhiteSpace,
+    /// Automatic hyphenation; soft hyphens (U+00AD) are honored even when this is None
+    pub hyphenation: Option<Hyphenation>,
+    /// Extra space after every character, in points (PDF `Tc`)
+    pub letter_spacing: f64,
+    /// Extra space after every space character, in points
+    pub word_spacing: f64,
+    pub breaker: LineBreaker,
+    /// The paragraph goes on past the end of the text (the head of a page split),
+    /// so the last line is a full line rather than the end of a paragraph.
+    pub continues: bool,
+    /// With tab stops, tabs survive whitespace collapsing and advance to the stops
+    pub tab_stops: Vec<TabStop>,
+}
+
+impl LineBreakOptions {
+    /// Width of a drawn line including letter and word spacing and tabs
+    pub fn measure(&self, font: &Font, line: &str, size: f64) -> f64 {
+        if self.has_tabs(line) {
+            return self.layout_tabs(font, line, size).width;
+        }
+        self.measure_run(font, line, size)
+    }
+
+    /// Whether `line` is laid out at tab stops
+    pub fn has_tabs(&self, line: &str) -> bool {
+        !self.tab_stops.is_empty() && line.contains('\t')
+    }
+
+    /// Place the pieces of `line` between tabs at the tab stops. Each tab moves to the
+    /// first stop past the text so far where its piece still fits after that text.
+    pub fn layout_tabs(&self, font: &Font, line: &str, size: f64) -> TabLayout {
+        let mut pieces = Vec::new();
+        let mut leaders = Vec::new();
+        let mut parts = line.split('\t');
+        let first = parts.next().unwrap_or_default();
+        let mut pen = self.measure_run(font, first, size);
+        pieces.push((0.0, first.to_string()));
+
+        for piece in parts {
+            let width = self.measure_run(font, piece, size);
+            let (stop, start) = self.tab_stops.iter()
+                .filter(|stop| stop.position > pen)
+                .map(|stop| {
+                    let start = match stop.align {
+                        TabAlign::Left => stop.position,
+                        TabAlign::Right => stop.position - width,
+                        TabAlign::Center => stop.position - width / 2.0,
+                        TabAlign::Decimal => match piece.find('.') {
+                            Some(point) => stop.position - self.measure_run(font, &piece[..point], size),
+                            None => stop.position - width,
+                        },
+                    };
+                    (Some(*stop), start)
+                })
+                .find(|(_, start)| *start >= pen)
+                .unwrap_or_else(|| {
+                    let next = ((pen / DEFAULT_TAB_INTERVAL).floor() + 1.0) * DEFAULT_TAB_INTERVAL;
+                    (None, next)
+                });
+
+            if let Some(leader) = stop.and_then(|stop| stop.leader.char()) {
+                // Leaders end flush against the text so columns of them line up
+                let leader_width = self.measure_run(font, &leader.to_string(), size);
+                let count = if leader_width > 0.0 { ((start - pen) / leader_width).floor() as usize } else { 0 };
+                if count > 0 {
+                    leaders.push((start - count as f64 * leader_width, leader.to_string().repeat(count)));
+                }
+            }
+            pieces.push((start, piece.to_string()));
+            pen = start + width;
+        }
+
+        TabLayout { pieces, leaders, width: pen }
+    }
+
+    /// Width of text without tabs including letter and word spacing
+    fn measure_run(&self, font: &Font, line: &str, size: f64) -> f64 {
+        let mut width = font.measure_text(line, size);
+        if self.letter_spacing != 0.0 {
+            width += self.letter_spacing * line.chars().count() as f64;
+        }
+        if self.word_spacing != 0.0 {
+            width += self.word_spacing * line.chars().filter(|c| *c == ' ').count() as f64;
+        }
+        width
+    }
+}
+
+/// One line produced by `break_lines`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct TextLine {
+    /// Text to draw, with whitespace already processed for the mode
+    pub text: String,
+    /// Byte offset in the source where this line starts
+    pub start: usize,
+    /// Byte offset in the source where the next line starts
+    pub next: usize,
+    /// The line ends inside a word and carries a hyphen
+    pub hyphenated: bool,
+    /// The line ends at a mandatory break or the end of the text
+    pub ends_paragraph: bool,
+}
+
+const SOFT_HYPHEN: char = '\u{AD}';
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum Break {
+    Mandatory,
+    Allowed,
+    Hyphen,
+}
+
+fn is_line_terminator(ch: char) -> bool {
+    matches!(ch, '\n' | '\r' | '\u{0B}' | '\u{0C}' | '\u{85}' | '\u{2028}' | '\u{2029}')
+}
+
+fn is_collapsible_space(ch: char) -> bool {
+    matches!(ch, ' ' | '\t') || is_line_terminator(ch)
+}
+
+/// Turn a slice of source text into the text drawn for a line.
+/// Soft hyphens are invisible unless the line breaks at one; `hyphen` adds a hyphen for pattern breaks.
+fn line_content(raw: &str, white_space: WhiteSpace, hyphen: bool, keep_tabs: bool) -> String {
+    line_chars(raw, white_space, hyphen, keep_tabs).into_iter().map(|(_, ch)| ch).collect()
+}
+
+/// Characters drawn for a line, each with the byte offset in `raw` it came from.
+/// Inserted characters (collapsed spaces, hyphens) take the offset of the character they replace.
+/// `keep_tabs` keeps tabs from collapsing, absorbing the spaces around them.
+pub(crate) fn line_chars(raw: &str, white_space: WhiteSpace, hyphen: bool, keep_tabs: bool) -> Vec<(usize, char)> {
+    let mut out: Vec<(usize, char)> = if white_space.collapses() {
+        let mut out = Vec::with_capacity(raw.len());
+        let mut pending_space = None;
+        for (offset, ch) in raw.char_indices() {
+            if ch == '\t' && keep_tabs {
+                pending_space = None;
+                out.push((offset, ch));
+            } else if is_collapsible_space(ch) {
+                if out.last().is_some_and(|(_, last)| *last != '\t') && pending_space.is_none() {
+                    pending_space = Some(offset);
+                }
+            } else {
+                if let Some(space) = pending_space.take() {
+                    out.push((space, ' '));
+                }
+                out.push((offset, ch));
             }
-            
-            // Count lines needed for this word broken at character level
-            let chars: Vec<char> = word.chars().collect();
-            let mut char_buffer = String::new();
-            
-            for ch in chars {
-                let test_str = format!("{}{}", char_buffer, ch);
-                let test_width = font.measure_text(&test_str, size);
-                
-                if test_width <= width {
-                    char_buffer.push(ch);
-                } else {
-                    if !char_buffer.is_empty() {
-                        line_count += 1;
+        }
+        out
+    } else {
+        let raw = raw.trim_end_matches(is_line_terminator);
+        // Spaces at a wrap point hang past the line end
+        let raw = if white_space.wraps() { raw.trim_end_matches([' ', '\t']) } else { raw };
+        raw.char_indices().collect()
+    };
+
+    let ends_with_soft_hyphen = out.last().is_some_and(|(_, ch)| *ch == SOFT_HYPHEN);
+    let last_offset = out.last().map_or(0, |(offset, _)| *offset);
+    out.retain(|(_, ch)| *ch != SOFT_HYPHEN);
+    if hyphen || ends_with_soft_hyphen {
+        out.push((last_offset, '-'));
+    }
+    out
+}
+
+/// UAX #14 break opportunities, plus hyphenation points inside words.
+/// Words that already contain soft hyphens are left to the author.
+fn break_opportunities(text: &str, hyphenation: Option<&Hyphenation>) -> Vec<(usize, Break)> {
+    let mut breaks = Vec::new();
+    let mut prev = 0;
+
+    for (pos, opportunity) in unicode_linebreak::linebreaks(text) {
+        let segment = &text[prev..pos];
+        if let Some(hyphenation) = hyphenation.filter(|_| !segment.contains(SOFT_HYPHEN)) {
+            let mut word_start = None;
+            for (offset, ch) in segment.char_indices().chain(std::iter::once((segment.len(), ' '))) {
+                match (ch.is_alphabetic(), word_start) {
+                    (true, None) => word_start = Some(offset),
+                    (false, Some(start)) => {
+                        for point in hyphenation.break_points(&segment[start..offset]) {
+                            breaks.push((prev + start + point, Break::Hyphen));
+                        }
+                        word_start = None;
                     }
-                    char_buffer.clear();
-                    char_buffer.push(ch);
+                    _ => {}
+                }
+            }
+        }
+
+        let kind = match opportunity {
+            unicode_linebreak::BreakOpportunity::Mandatory => Break::Mandatory,
+            unicode_linebreak::BreakOpportunity::Allowed => Break::Allowed,
+        };
+        breaks.push((pos, kind));
+        prev = pos;
+    }
+    breaks
+}
+
+/// Break text into lines using the Unicode Line Breaking Algorithm (UAX #14).
+/// Lines end at mandatory breaks and, in wrapping modes, at the last break opportunity
+/// (or hyphenation point) that keeps the line within `width`. A segment wider than `width`
+/// on its own is broken between characters. Lines are in logical order; reordering for
+/// display happens in `shape_line`.
+pub fn break_lines(text: &str, width: f64, size: f64, font: &Font, options: &LineBreakOptions) -> Vec<TextLine> {
+    let white_space = options.white_space;
+    let measure = |start: usize, end: usize, hyphen: bool| {
+        options.measure(font, &line_content(&text[start..end], white_space, hyphen, !options.tab_stops.is_empty()), size)
+    };
+    if options.breaker == LineBreaker::Optimal && white_space.wraps() && width.is_finite() && !text.is_empty() {
+        let space_width = options.measure(font, " ", size);
+        return optimal_lines(text, width, options, &measure, space_width);
+    }
+    break_lines_by(text, width, options, measure)
+}
+
+/// Builds the line for the source range `start..next`.
+fn make_line(text: &str, start: usize, next: usize, hyphen: bool, ends_paragraph: bool, options: &LineBreakOptions) -> TextLine {
+    let raw = &text[start..next];
+    let hyphenated = hyphen || raw.trim_end_matches(is_collapsible_space).ends_with(SOFT_HYPHEN);
+    let text = line_content(raw, options.white_space, hyphen, !options.tab_stops.is_empty());
+    TextLine { text, start, next, hyphenated, ends_paragraph }
+}
+
+/// Whether the break at `pos` ends a paragraph. The end of a text that `continues`
+/// is only the end of a line.
+fn ends_paragraph(text: &str, pos: usize, options: &LineBreakOptions) -> bool {
+    !(options.continues && pos == text.len() && !text.ends_with(is_line_terminator))
+}
+
+/// Total-fit line breaking after Knuth and Plass. Every break opportunity is a candidate
+/// and the lines with the least total demerits over the whole paragraph win. Spaces
+/// stretch by half of `space_width` but never shrink, so no line is wider than `width`
+/// whatever the alignment. Hyphenated lines pay a penalty.
+///
+/// The best lines up to each break only depend on the text before it, so breaking the
+/// head of a split (with `continues` set) gives back exactly the lines it had in the
+/// whole paragraph.
+fn optimal_lines(text: &str, width: f64, options: &LineBreakOptions, measure: &impl Fn(usize, usize, bool) -> f64, space_width: f64) -> Vec<TextLine> {
+    const LINE_PENALTY: f64 = 10.0;
+    const HYPHEN_PENALTY: f64 = 50.0;
+    const DOUBLE_HYPHEN_DEMERITS: f64 = 3000.0;
+    const FITNESS_DEMERITS: f64 = 3000.0;
+
+    struct Node {
+        demerits: f64,
+        prev: usize,
+        // 1 = decent, 2 = loose, 3 = very loose
+        fitness: i32,
+        line: Option<TextLine>,
+    }
+
+    // (position, kind, forced): lines never run across a forced break
+    let mut breaks = Vec::new();
+    let mut segment_start = 0;
+    for (pos, kind) in break_opportunities(text, options.hyphenation.as_ref()) {
+        if measure(segment_start, pos, kind == Break::Hyphen) > width {
+            // A segment wider than the line is broken between characters, as the greedy breaker does
+            let mut char_start = segment_start;
+            for (offset, ch) in text[segment_start..pos].char_indices() {
+                let char_end = segment_start + offset + ch.len_utf8();
+                if char_end > char_start + ch.len_utf8() && measure(char_start, char_end, false) > width {
+                    char_start = char_end - ch.len_utf8();
+                    breaks.push((char_start, Break::Allowed, true));
                 }
             }
-            
-            // Count the last character buffer line
-            if !char_buffer.is_empty() {
-                line_count += 1;
+        }
+        breaks.push((pos, kind, kind == Break::Mandatory));
+        segment_start = pos;
+    }
+
+    // Node 0 is the start of the text, node i + 1 the break `breaks[i]`
+    let mut nodes = Vec::with_capacity(breaks.len() + 1);
+    nodes.push(Node { demerits: 0.0, prev: 0, fitness: 1, line: None });
+    let mut first = 0;
+
+    for (index, &(pos, kind, forced)) in breaks.iter().enumerate() {
+        let hyphen = kind == Break::Hyphen;
+        let ends_paragraph = kind == Break::Mandatory && ends_paragraph(text, pos, options);
+        let mut best: Option<Node> = None;
+
+        for from in (first..=index).rev() {
+            let start = if from == 0 { 0 } else { breaks[from - 1].0 };
+            let natural = measure(start, pos, hyphen);
+            if natural > width && from < index {
+                // Starting any earlier only makes the line longer
+                break;
             }
-        } else {
-            // Try adding this word to the buffer
-            let mut test_line = buffer.clone();
-            test_line.push(word);
-            let test_text = test_line.join(" ");
-            let test_width = font.measure_text(&test_text, size);
-            
-            if test_width <= width {
-                // Word fits, add it to buffer
-                buffer.push(word);
-            } else {
-                // Word doesn't fit
-                if !buffer.is_empty() {
-                    // Complete the current line
-                    line_count += 1;
-                    buffer.clear();
+            let line = make_line(text, start, pos, hyphen, ends_paragraph, options);
+            let prev = &nodes[from];
+
+            // A line without spaces still counts as stretching one, so its looseness is comparable
+            let stretch = line.text.matches(' ').count().max(1) as f64 * space_width / 2.0;
+            let ratio = if ends_paragraph { 0.0 } else { (width - natural).max(0.0) / stretch };
+            // Not capped as in TeX: without shrinking there is no emergency pass, and loose
+            // lines must still be told apart from looser ones
+            let badness = 100.0 * ratio.powi(3);
+            let fitness = match ratio {
+                r if r <= 0.5 => 1,
+                r if r <= 1.0 => 2,
+                _ => 3,
+            };
+
+            let mut demerits = (LINE_PENALTY + badness).powi(2);
+            if line.hyphenated {
+                demerits += HYPHEN_PENALTY.powi(2);
+                if prev.line.as_ref().is_some_and(|l| l.hyphenated) {
+                    demerits += DOUBLE_HYPHEN_DEMERITS;
                 }
-                // Start new line with this word
-                buffer.push(word);
+            }
+            if (fitness - prev.fitness).abs() > 1 {
+                demerits += FITNESS_DEMERITS;
+            }
+            let total = prev.demerits + demerits;
+            if best.as_ref().is_none_or(|b| total < b.demerits) {
+                best = Some(Node { demerits: total, prev: from, fitness, line: Some(line) });
             }
         }
+
+        if forced {
+            first = index + 1;
+        }
+        // The line from the previous break is always a candidate, so there is a best one
+        nodes.push(best.expect("line from the previous break"));
     }
-    
-    // Count the last line
-    if !buffer.is_empty() {
-        line_count += 1;
+
+    let mut lines = Vec::new();
+    let mut node = nodes.len() - 1;
+    while node > 0 {
+        lines.extend(nodes[node].line.take());
+        node = nodes[node].prev;
     }
-    
-    line_count.max(1) // At least 1 line
+    lines.reverse();
+    lines
 }
 
-/// Split text into two parts: one that fits in max_lines, and the remainder.
-/// Returns (Head, Tail). Tail is None if all fits.
-pub fn split_text_at_lines(text: &str, width: f64, size: f64, font: &Font, max_lines: usize) -> (String, Option<String>) {
-    if max_lines == 0 {
-        return (String::new(), Some(text.to_string()));
+/// Line breaking with a custom measure, used when a paragraph mixes fonts and sizes.
+/// `measure(start, end, hyphen)` returns the drawn width of the source range `start..end`.
+pub(crate) fn break_lines_by(text: &str, width: f64, options: &LineBreakOptions, measure: impl Fn(usize, usize, bool) -> f64) -> Vec<TextLine> {
+    let mut lines = Vec::new();
+    if text.is_empty() {
+        return lines;
     }
 
-    // Reuse logic from calculate_text_lines but track byte index
-    let words: Vec<&str> = text.split_whitespace().collect();
-    // let mut buffer = Vec::new(); // Unused in split version
-    let mut current_lines = 1;
-    
-    // Naive re-implementation for MVP (ideally we refactor to shared iterator)
-    // We will build the "Head" string.
-    let mut head_str = String::new();
-    let mut word_iter = words.iter().peekable();
-    
-    // We need to reconstruct the string carefully or just return String.
-    // Let's use the buffer approach to build lines.
-    
-    // Logic: Fill buffer. When line is full, flush buffer to head_str. 
-    // If lines > max_lines, stop and return rest.
-    
-    let mut line_buffer = Vec::new();
-
-    while let Some(&word) = word_iter.peek() {
-        // word is &&str here because peek returns &Item
-        
-        let _word_width = font.measure_text(word, size);
-        
-        // Check if word fits in current line
-        let mut test_line = line_buffer.clone();
-        test_line.push(*word);
-        let test_text = test_line.join(" ");
-        let test_width = font.measure_text(&test_text, size);
-        
-        if test_width <= width {
-            // Fits
-            line_buffer.push(*word);
-            word_iter.next(); 
-        } else {
-            // Doesn't fit.
-            if line_buffer.is_empty() {
-                // Word is wider than line. Forced break.
-                line_buffer.push(*word);
-                word_iter.next();
+    let white_space = options.white_space;
+    let wraps = white_space.wraps();
+    let fits = |start: usize, end: usize, hyphen: bool| {
+        !wraps || measure(start, end, hyphen) <= width
+    };
+    let push_line = |lines: &mut Vec<TextLine>, start: usize, next: usize, hyphen: bool, ends_paragraph: bool| {
+        lines.push(make_line(text, start, next, hyphen, ends_paragraph, options));
+    };
+
+    let mut line_start = 0;
+    // Last break opportunity at which the current line still fits
+    let mut last_fit: Option<(usize, bool)> = None;
+    let hyphenation = if wraps { options.hyphenation.as_ref() } else { None };
+
+    for (pos, kind) in break_opportunities(text, hyphenation) {
+        let hyphen = kind == Break::Hyphen;
+        if !fits(line_start, pos, hyphen) {
+            if let Some((fit, fit_hyphen)) = last_fit.take() {
+                push_line(&mut lines, line_start, fit, fit_hyphen, false);
+                line_start = fit;
             }
-            
-            // Flush current line
-            if !head_str.is_empty() {
-                head_str.push(' ');
+            if !fits(line_start, pos, hyphen) {
+                // A single unbreakable segment is wider than the line: break between characters
+                let mut char_start = line_start;
+                for (offset, ch) in text[line_start..pos].char_indices() {
+                    let char_end = line_start + offset + ch.len_utf8();
+                    if char_end > char_start + ch.len_utf8() && !fits(char_start, char_end, false) {
+                        let brk = char_end - ch.len_utf8();
+                        push_line(&mut lines, char_start, brk, false, false);
+                        char_start = brk;
+                    }
+                }
+                line_start = char_start;
             }
-            head_str.push_str(&line_buffer.join(" "));
-            line_buffer.clear();
-            
-            if current_lines >= max_lines {
-                break; 
+        }
+
+        if kind == Break::Mandatory {
+            push_line(&mut lines, line_start, pos, false, ends_paragraph(text, pos, options));
+            line_start = pos;
+            last_fit = None;
+        } else {
+            last_fit = Some((pos, hyphen));
+        }
+    }
+
+    lines
+}
+
+/// Width of the widest line when only mandatory breaks are taken
+pub fn max_line_width(text: &str, size: f64, font: &Font, options: &LineBreakOptions) -> f64 {
+    break_lines(text, f64::INFINITY, size, font, options)
+        .iter()
+        .map(|line| options.measure(font, &line.text, size))
+        .fold(0.0, f64::max)
+}
+
+/// Wrap text into lines that fit within `width`.
+pub fn wrap_text(text: &str, width: f64, size: f64, font: &Font, options: &LineBreakOptions) -> Vec<String> {
+    break_lines(text, width, size, font, options)
+        .into_iter()
+        .map(|line| line.text)
+        .collect()
+}
+
+/// Wrap text into vertical columns no taller than `height`.
+/// CJK text has no spaces, so any character boundary is a break opportunity;
+/// whitespace at the top of a column is dropped.
+pub fn wrap_text_vertical(text: &str, height: f64, size: f64, font: &Font) -> Vec<String> {
+    vertical_columns(text, height, size, font)
+        .into_iter()
+        .map(|(start, end)| text[start..end].to_string())
+        .collect()
+}
+
+/// Byte ranges of the columns `wrap_text_vertical` produces
+fn vertical_columns(text: &str, height: f64, size: f64, font: &Font) -> Vec<(usize, usize)> {
+    let mut columns = Vec::new();
+    let mut column: Option<(usize, usize)> = None;
+    let mut column_height = 0.0;
+
+    for (i, ch) in text.char_indices() {
+        let end = i + ch.len_utf8();
+        if ch == '\n' {
+            columns.push(column.take().unwrap_or((i, i)));
+            column_height = 0.0;
+            continue;
+        }
+        if column.is_none() && ch.is_whitespace() {
+            continue;
+        }
+
+        let mut buf = [0u8; 4];
+        let advance = font.measure_text_vertical(ch.encode_utf8(&mut buf), size);
+        if let Some(range) = column && column_height + advance > height {
+            columns.push(range);
+            column = None;
+            column_height = 0.0;
+            if ch.is_whitespace() {
+                continue;
             }
-            current_lines += 1;
         }
+        column = Some(match column {
+            Some((start, _)) => (start, end),
+            None => (i, end),
+        });
+        column_height += advance;
     }
-    
-    // If loop finished (all words consumed)
-    if !line_buffer.is_empty() {
-         if current_lines <= max_lines {
-             if !head_str.is_empty() { head_str.push(' '); }
-             head_str.push_str(&line_buffer.join(" "));
-             return (head_str, None);
-         }
-    } else {
-        // Buffer empty, meaning we flushed exactly at boundary?
-        return (head_str, Some(collect_rest(word_iter)));
+
+    if let Some(range) = column {
+        columns.push(range);
+    } else if columns.is_empty() {
+        columns.push((text.len(), text.len()));
+    }
+
+    columns
+}
+
+/// Split vertical text into the first `max_columns` columns and the remainder.
+/// The head keeps its column breaks as newlines so it wraps the same way at any height;
+/// the tail is a slice of the source and is wrapped again where it goes. Tail is None if all fits.
+pub fn split_text_at_columns(text: &str, height: f64, size: f64, font: &Font, max_columns: usize) -> (String, Option<String>) {
+    let columns = vertical_columns(text, height, size, font);
+    let head = columns.iter()
+        .take(max_columns)
+        .map(|&(start, end)| &text[start..end])
+        .collect::<Vec<_>>()
+        .join("\n");
+    let tail = columns.get(max_columns).map(|&(start, _)| text[start..].to_string());
+    (head, tail)
+}
+
+/// Calculate how many lines are needed for text with wrapping
+pub fn calculate_text_lines(text: &str, width: f64, size: f64, font: &Font, options: &LineBreakOptions) -> usize {
+    break_lines(text, width, size, font, options).len().max(1) // At least 1 line
+}
+
+/// Lines drawn over or under text and a highlight behind it, repeated on every wrapped line.
+/// Positions and thicknesses come from the font's `post` and `OS/2` metrics.
+#[derive(Debug, Clone, Default, Serialize, Deserialize)]
+#[serde(default)]
+pub struct TextDecoration {
+    pub underline: bool,
+    pub overline: bool,
+    /// Strikethrough
+    pub line_through: bool,
+    /// Background behind each line of text
+    pub highlight: Option<Color>,
+    /// Color of the lines; None uses the text color. Named apart from the text
+    /// color so spans can carry it inline
+    #[serde(rename = "decoration_color")]
+    pub color: Option<Color>,
+    /// Leave gaps in the underline where descenders cross it
+    pub skip_ink: bool,
+}
+
+impl TextDecoration {
+    pub fn is_none(&self) -> bool {
+        !self.underline && !self.overline && !self.line_through && self.highlight.is_none()
+    }
+}
+
+/// How text is set when drawn on a page: font, size, direction, alignment,
+/// line height, spacing and decoration. Decoration lines without a color of
+/// their own take the page's fill color.
+#[derive(Clone)]
+pub struct TextStyle<'a> {
+    pub font: &'a Font,
+    /// Index the font was added to the document at
+    pub font_index: u32,
+    pub size: f64,
+    pub direction: TextDirection,
+    /// Line alignment in paragraphs; None aligns to the paragraph's start edge
+    pub align: Option<TextAlign>,
+    pub line_height: LineHeight,
+    /// Extra space after every space character, in points
+    pub word_spacing: f64,
+    /// Extra space after every character, in points (PDF `Tc`)
+    pub letter_spacing: f64,
+    pub decoration: TextDecoration,
+}
+
+impl<'a> TextStyle<'a> {
+    /// Plain text in `font` at `size`, in the direction of its first strong character
+    pub fn new(font: &'a Font, font_index: u32, size: f64) -> Self {
+        TextStyle {
+            font,
+            font_index,
+            size,
+            direction: TextDirection::Auto,
+            align: None,
+            line_height: LineHeight::Normal,
+            word_spacing: 0.0,
+            letter_spacing: 0.0,
+            decoration: TextDecoration::default(),
+        }
+    }
+
+    /// `options` with this style's spacing, so lines are broken at the widths they are drawn at
+    pub fn line_break_options(&self, options: &LineBreakOptions) -> LineBreakOptions {
+        LineBreakOptions { letter_spacing: self.letter_spacing, word_spacing: self.word_spacing, ..options.clone() }
+    }
+}
+
+/// What happens to text that does not fit its box (CSS `text-overflow`).
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum TextOverflow {
+    /// Draw everything, even past the box
+    #[default]
+    Visible,
+    /// Cut the text off at the edges of the box
+    Clip,
+    /// End the last visible line with an ellipsis
+    Ellipsis,
+}
+
+impl FromStr for TextOverflow {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "clip" | "hidden" => Self::Clip,
+            "ellipsis" => Self::Ellipsis,
+            "visible" => Self::Visible,
+            _ => return Err(ParseValueError::new("text overflow", s)),
+        })
+    }
+}
+
+/// Rules for fitting text into a fixed box.
+/// The default draws all lines at the requested size.
+#[derive(Debug, Clone, Copy, PartialEq, Default)]
+pub struct TextFit {
+    /// Lines after this many are dropped
+    pub max_lines: Option<usize>,
+    pub overflow: TextOverflow,
+    /// Smallest size the text may shrink to so it fits; None never shrinks
+    pub min_size: Option<f64>,
+}
+
+impl TextFit {
+    /// Text is cut or resized to its box, so it moves between pages as one block
+    pub fn is_fixed(&self) -> bool {
+        *self != Self::default()
+    }
+
+    /// Largest size from the style's size down to `min_size` (in half point steps) at which
+    /// the text fits `width` and `height` within `max_lines`. Returns `min_size` when nothing fits.
+    pub fn fitted_size(&self, text: &str, width: f64, height: f64, style: &TextStyle, options: &LineBreakOptions) -> f64 {
+        let Some(min_size) = self.min_size else {
+            return style.size;
+        };
+        let font = style.font;
+        let options = style.line_break_options(options);
+
+        let fits = |size: f64| {
+            let lines = break_lines(text, width, size, font, &options);
+            let count = lines.len().max(1);
+            count <= self.max_lines.unwrap_or(usize::MAX)
+                && (!height.is_finite() || LineMetrics::new(font, size, style.line_height).height(count) <= height + 1e-6)
+                && lines.iter().all(|line| options.measure(font, &line.text, size) <= width + 1e-6)
+        };
+
+        let mut current = style.size;
+        while current > min_size && !fits(current) {
+            current = (current - 0.5).max(min_size);
+        }
+        current
+    }
+
+    /// Lines to draw in a box of `width` x `height`: at most `max_lines`, and with an
+    /// ellipsis where text was cut off or a line is too wide to wrap.
+    pub fn lines(&self, text: &str, width: f64, height: f64, style: &TextStyle, options: &LineBreakOptions) -> Vec<TextLine> {
+        let TextStyle { font, size, .. } = *style;
+        let options = &style.line_break_options(options);
+        let mut lines = break_lines(text, width, size, font, options);
+
+        let mut limit = self.max_lines.unwrap_or(usize::MAX);
+        if self.overflow == TextOverflow::Ellipsis && height.is_finite() {
+            limit = limit.min(LineMetrics::new(font, size, style.line_height).lines_in(height).max(1));
+        }
+        let cut = lines.len() > limit;
+        lines.truncate(limit);
+
+        if self.overflow == TextOverflow::Ellipsis {
+            let last = lines.len().saturating_sub(1);
+            for (i, line) in lines.iter_mut().enumerate() {
+                if (cut && i == last) || options.measure(font, &line.text, size) > width + 1e-6 {
+                    ellipsize(line, width, size, font, options);
+                }
+            }
+        }
+        lines
+    }
+}
+
+/// Shorten a line until it fits `width` with an ellipsis appended, measured with the line's font
+fn ellipsize(line: &mut TextLine, width: f64, size: f64, font: &Font, options: &LineBreakOptions) {
+    let ellipsis = if font.glyph_index('\u{2026}').is_some() { "\u{2026}" } else { "..." };
+    let mut text = line.text.clone();
+    if line.hyphenated {
+        text.pop();
     }
-    
-    // If we broke early
-    if word_iter.peek().is_some() || !line_buffer.is_empty() {
-        // Remainder
-        let mut tail = if !line_buffer.is_empty() { line_buffer.join(" ") } else { String::new() };
-        let rest = collect_rest(word_iter);
-        if !rest.is_empty() {
-            if !tail.is_empty() { tail.push(' '); }
-             tail.push_str(&rest);
+    loop {
+        let candidate = format!("{}{}", text.trim_end(), ellipsis);
+        if text.is_empty() || options.measure(font, &candidate, size) <= width {
+            line.text = candidate;
+            break;
         }
-        return (head_str, Some(tail));
+        text.pop();
     }
+    line.hyphenated = false;
+    line.ends_paragraph = true;
+}
 
-    (head_str, None)
+/// Number of lines to keep before a page break in a paragraph of `total` lines when
+/// `fitting` lines fit, leaving at least `orphans` lines before the break and `widows`
+/// lines after it. Returns 0 when the paragraph has to move to the next page whole.
+pub fn lines_before_break(total: usize, fitting: usize, orphans: usize, widows: usize) -> usize {
+    if fitting >= total {
+        return total;
+    }
+    let head = fitting.min(total.saturating_sub(widows));
+    if head < orphans.max(1) { 0 } else { head }
 }
 
-fn collect_rest(mut iter: std::iter::Peekable<std::slice::Iter<&str>>) -> String {
-    let mut s = String::new();
-    while let Some(&w) = iter.next() {
-        if !s.is_empty() { s.push(' '); }
-        s.push_str(w);
+/// Split text into two parts: one that fits in max_lines, and the remainder.
+/// Returns (Head, Tail). Tail is None if all fits.
+/// Both parts are slices of the source, so they wrap exactly as the lines they came from.
+pub fn split_text_at_lines(text: &str, width: f64, size: f64, font: &Font, max_lines: usize, options: &LineBreakOptions) -> (String, Option<String>) {
+    if max_lines == 0 {
+        return (String::new(), Some(text.to_string()));
+    }
+
+    let lines = break_lines(text, width, size, font, options);
+    if lines.len() <= max_lines {
+        return (text.to_string(), None);
+    }
+
+    let last = &lines[max_lines - 1];
+    let mut head = text[..last.next].to_string();
+    if last.hyphenated && !head.ends_with(SOFT_HYPHEN) {
+        // Keep the hyphen when the head is wrapped again on its own
+        head.push(SOFT_HYPHEN);
+    }
+    (head, Some(text[lines[max_lines].start..].to_string()))
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn options(white_space: WhiteSpace) -> LineBreakOptions {
+        LineBreakOptions { white_space, ..Default::default() }
+    }
+
+    fn roboto() -> Font {
+        Font::from_bytes(include_bytes!("../../Roboto-Regular.ttf").to_vec(), "Roboto".to_string()).unwrap()
+    }
+
+    #[test]
+    fn test_visual_runs_reorders_rtl() {
+        // Hebrew "shalom" followed by a Latin word in an RTL paragraph:
+        // the Latin run is displayed first (leftmost), the Hebrew run (with the space) last
+        let runs = visual_runs("שלום abc", TextDirection::Rtl);
+        assert_eq!(runs, vec![("abc", false), ("שלום ", true)]);
+    }
+
+    #[test]
+    fn test_line_content_whitespace_modes() {
+        assert_eq!(line_content("  a   b \n", WhiteSpace::Normal, false, false), "a b");
+        assert_eq!(line_content("  a   b \n", WhiteSpace::Pre, false, false), "  a   b ");
+        assert_eq!(line_content("  a   b \r\n", WhiteSpace::PreWrap, false, false), "  a   b");
+        assert_eq!(line_content("a\tb", WhiteSpace::Nowrap, false, false), "a b");
+    }
+
+    #[test]
+    fn test_break_lines_honors_hard_breaks() {
+        let font = roboto();
+        let lines = wrap_text("221B Baker Street\nLondon\n\nUK", 1000.0, 12.0, &font, &options(WhiteSpace::Normal));
+        assert_eq!(lines, vec!["221B Baker Street", "London", "", "UK"]);
+    }
+
+    #[test]
+    fn test_break_lines_cjk_without_spaces() {
+        let font = roboto();
+        let width = font.measure_text("漢字", 12.0) + 0.01;
+        let lines = wrap_text("漢字漢字漢", width, 12.0, &font, &options(WhiteSpace::Normal));
+        assert_eq!(lines, vec!["漢字", "漢字", "漢"]);
+    }
+
+    #[test]
+    fn test_split_agrees_with_break_lines() {
+        let font = roboto();
+        let text = "one two three four five six seven eight nine ten";
+        let width = font.measure_text("one two three", 12.0) + 0.01;
+        let lines = wrap_text(text, width, 12.0, &font, &options(WhiteSpace::Normal));
+        let (head, tail) = split_text_at_lines(text, width, 12.0, &font, 2, &options(WhiteSpace::Normal));
+        let tail = tail.unwrap();
+        assert_eq!(wrap_text(&head, width, 12.0, &font, &options(WhiteSpace::Normal)), lines[..2]);
+        assert_eq!(wrap_text(&tail, width, 12.0, &font, &options(WhiteSpace::Normal)), lines[2..]);
+    }
+
+    #[test]
+    fn test_optimal_breaker_evens_lines_and_splits_between_them() {
+        let font = roboto();
+        let text = "In olden times when wishing still helped one, there lived a king whose daughters were all beautiful, \
+                    but the youngest was so beautiful that the sun itself, which has seen so much, was astonished \
+                    whenever it shone in her face.";
+        let width = 150.0;
+        let optimal = LineBreakOptions { breaker: LineBreaker::Optimal, ..Default::default() };
+        let greedy = break_lines(text, width, 12.0, &font, &LineBreakOptions::default());
+        let lines = break_lines(text, width, 12.0, &font, &optimal);
+        assert!(lines.iter().all(|l| font.measure_text(&l.text, 12.0) <= width));
+
+        // Total fit avoids the very loose lines greedy filling leaves behind
+        let loosest = |lines: &[TextLine]| -> f64 {
+            lines[..lines.len() - 1].iter()
+                .map(|l| (width - font.measure_text(&l.text, 12.0)) / l.text.matches(' ').count() as f64)
+                .fold(0.0, f64::max)
+        };
+        assert!(loosest(&lines) < loosest(&greedy));
+
+        // The head of a split keeps exactly the lines chosen for the whole paragraph
+        let (head, tail) = split_text_at_lines(text, width, 12.0, &font, 3, &optimal);
+        let continued = LineBreakOptions { continues: true, ..optimal.clone() };
+        let head_lines: Vec<String> = break_lines(&head, width, 12.0, &font, &continued).into_iter().map(|l| l.text).collect();
+        let texts: Vec<String> = lines.iter().map(|l| l.text.clone()).collect();
+        assert_eq!(head_lines, texts[..3]);
+        assert!(text.ends_with(tail.unwrap().as_str()));
+    }
+
+    #[test]
+    fn test_tab_stops_with_leaders() {
+        let font = roboto();
+        let options = LineBreakOptions {
+            tab_stops: vec![
+                TabStop { position: 100.0, align: TabAlign::Decimal, leader: TabLeader::None },
+                TabStop { position: 200.0, align: TabAlign::Right, leader: TabLeader::Dots },
+            ],
+            ..Default::default()
+        };
+
+        // Tabs survive whitespace collapsing and wrapped lines keep them
+        let lines = wrap_text("Tea \t 3.5\t12.00 Coffee\t4.25\t9.00", 210.0, 12.0, &font, &options);
+        assert_eq!(lines, vec!["Tea\t3.5\t12.00", "Coffee\t4.25\t9.00"]);
+
+        let tea = options.layout_tabs(&font, &lines[0], 12.0);
+        let coffee = options.layout_tabs(&font, &lines[1], 12.0);
+        // Decimal points line up on the first stop
+        assert!((tea.pieces[1].0 + font.measure_text("3", 12.0) - 100.0).abs() < 1e-9);
+        assert!((coffee.pieces[1].0 + font.measure_text("4", 12.0) - 100.0).abs() < 1e-9);
+        // Prices end at the right stop, with dots filling the gap flush against them
+        assert!((tea.width - 200.0).abs() < 1e-9);
+        let (leader_x, dots) = &tea.leaders[0];
+        assert!(dots.chars().all(|c| c == '.'));
+        assert!((leader_x + font.measure_text(dots, 12.0) - tea.pieces[2].0).abs() < 1e-9);
+        assert_eq!(options.measure(&font, &lines[0], 12.0), tea.width);
+    }
+
+    #[test]
+    fn test_vertical_columns() {
+        let font = roboto();
+        let height = font.measure_text_vertical("abc", 10.0) + 0.01;
+        assert_eq!(wrap_text_vertical("abcdefg\nhi", height, 10.0, &font), vec!["abc", "def", "g", "hi"]);
+        // Whitespace at the top of a column is dropped
+        assert_eq!(wrap_text_vertical("abc def", height, 10.0, &font), vec!["abc", "def"]);
+
+        let (head, tail) = split_text_at_columns("abcdefg\nhi", height, 10.0, &font, 2);
+        assert_eq!(head, "abc\ndef");
+        assert_eq!(tail.as_deref(), Some("g\nhi"));
+        // The head wraps into the same columns without a height limit
+        assert_eq!(wrap_text_vertical(&head, f64::INFINITY, 10.0, &font), vec!["abc", "def"]);
+        assert_eq!(split_text_at_columns("abc", height, 10.0, &font, 2), ("abc".to_string(), None));
+    }
+
+    #[test]
+    fn test_nowrap_keeps_one_line() {
+        let font = roboto();
+        let lines = wrap_text("a   long   line", 1.0, 12.0, &font, &options(WhiteSpace::Nowrap));
+        assert_eq!(lines, vec!["a long line"]);
+        let lines = wrap_text("a   long   line", 1.0, 12.0, &font, &options(WhiteSpace::Pre));
+        assert_eq!(lines, vec!["a   long   line"]);
+    }
+
+    #[test]
+    fn test_hyphenation_breaks_long_german_word() {
+        let font = roboto();
+        let options = LineBreakOptions { hyphenation: Some(Hyphenation::new("de")), ..Default::default() };
+        let width = font.measure_text("Silbentren-", 12.0) + 0.01;
+        let lines = wrap_text("Silbentrennung", width, 12.0, &font, &options);
+        assert_eq!(lines, vec!["Silbentren-", "nung"]);
+
+        // Split keeps the hyphen on the head
+        let (head, tail) = split_text_at_lines("Silbentrennung", width, 12.0, &font, 1, &options);
+        assert_eq!(wrap_text(&head, width, 12.0, &font, &options), vec!["Silbentren-"]);
+        assert_eq!(tail.as_deref(), Some("nung"));
+    }
+
+    #[test]
+    fn test_soft_hyphen() {
+        let font = roboto();
+        let text = "Donau\u{AD}dampf\u{AD}schiff";
+        let width = font.measure_text("Donaudampf-", 12.0) + 0.01;
+        let lines = wrap_text(text, width, 12.0, &font, &LineBreakOptions::default());
+        assert_eq!(lines, vec!["Donaudampf-", "schiff"]);
+        let lines = wrap_text(text, 1000.0, 12.0, &font, &LineBreakOptions::default());
+        assert_eq!(lines, vec!["Donaudampfschiff"]);
+    }
+
+    #[test]
+    fn test_direction_auto_resolution() {
+        assert_eq!(TextDirection::Auto.resolve("مرحبا"), TextDirection::Rtl);
+        assert_eq!(TextDirection::Auto.resolve("123 hello"), TextDirection::Ltr);
+        assert_eq!(TextDirection::Ltr.resolve("مرحبا"), TextDirection::Ltr);
+    }
+
+    #[test]
+    fn test_line_height_values() {
+        assert_eq!("1.5".parse(), Ok(LineHeight::Multiple(1.5)));
+        assert_eq!("18pt".parse(), Ok(LineHeight::Absolute(18.0)));
+        assert_eq!("normal".parse(), Ok(LineHeight::Normal));
+        let parsed: Vec<LineHeight> = serde_json::from_str(r#"[1.25, "14pt"]"#).unwrap();
+        assert_eq!(parsed, vec![LineHeight::Multiple(1.25), LineHeight::Absolute(14.0)]);
+        assert_eq!(LineHeight::Multiple(2.0).leading(10.0), 20.0);
+        assert_eq!("tall".parse::<LineHeight>(), Err(ParseValueError::new("line height", "tall")));
+        assert!(serde_json::from_str::<LineHeight>(r#""1.5x""#).is_err());
+    }
+
+    #[test]
+    fn test_unknown_values_rejected() {
+        assert_eq!("Justify".parse(), Ok(TextAlign::Justify));
+        assert_eq!("left".parse(), Ok(TextAlign::Left));
+        let error = "centre".parse::<TextAlign>().unwrap_err();
+        assert_eq!(error.to_string(), "unknown text alignment \"centre\"");
+        assert_eq!("auto".parse(), Ok(TextDirection::Auto));
+        assert!("rlt".parse::<TextDirection>().is_err());
+        assert_eq!("".parse(), Ok(TabLeader::None));
+        assert!("ellipses".parse::<TextOverflow>().is_err());
+    }
+
+    #[test]
+    fn test_spacing_widens_lines() {
+        let font = roboto();
+        let plain = options(WhiteSpace::Normal);
+        let spaced = LineBreakOptions { letter_spacing: 1.0, word_spacing: 3.0, ..plain.clone() };
+        assert_eq!(spaced.measure(&font, "a b", 12.0), plain.measure(&font, "a b", 12.0) + 3.0 + 3.0);
+
+        let width = plain.measure(&font, "aaa bbb", 12.0) + 0.01;
+        assert_eq!(wrap_text("aaa bbb", width, 12.0, &font, &plain).len(), 1);
+        assert_eq!(wrap_text("aaa bbb", width, 12.0, &font, &spaced).len(), 2);
+    }
+
+    #[test]
+    fn test_first_baseline_from_font_metrics() {
+        let font = roboto();
+        let metrics = LineMetrics::new(&font, 10.0, LineHeight::Absolute(20.0));
+        // Half of the extra leading sits above the ascent
+        let expected = (20.0 - metrics.ascent - metrics.descent) / 2.0 + metrics.ascent;
+        assert!((metrics.baseline_offset() - expected).abs() < 1e-9);
+        assert!(metrics.ascent > 0.0 && metrics.descent > 0.0);
+        assert_eq!(metrics.lines_in(60.0), 3);
+    }
+
+    #[test]
+    fn test_widows_and_orphans() {
+        // 10 lines, 9 fit: one line would be widowed, so break after 8
+        assert_eq!(lines_before_break(10, 9, 2, 2), 8);
+        // Only one line fits: it would be orphaned, move the paragraph
+        assert_eq!(lines_before_break(10, 1, 2, 2), 0);
+        // Three lines with two fitting cannot satisfy both rules
+        assert_eq!(lines_before_break(3, 2, 2, 2), 0);
+        assert_eq!(lines_before_break(3, 2, 1, 1), 2);
+        assert_eq!(lines_before_break(3, 5, 2, 2), 3);
+    }
+
+    #[test]
+    fn test_ellipsis_and_max_lines() {
+        let font = roboto();
+        let options = options(WhiteSpace::Normal);
+        let text = "one two three four five six seven eight nine ten";
+        let width = font.measure_text("one two three", 12.0);
+        let fit = TextFit { max_lines: Some(2), overflow: TextOverflow::Ellipsis, min_size: None };
+        let lines = fit.lines(text, width, f64::INFINITY, &TextStyle::new(&font, 0, 12.0), &options);
+        assert_eq!(lines.len(), 2);
+        assert!(lines[1].text.ends_with('\u{2026}'));
+        assert!(options.measure(&font, &lines[1].text, 12.0) <= width);
+
+        // A single unbreakable line is shortened to the box
+        let nowrap = LineBreakOptions { white_space: WhiteSpace::Nowrap, ..Default::default() };
+        let fit = TextFit { overflow: TextOverflow::Ellipsis, ..Default::default() };
+        let lines = fit.lines(text, width, f64::INFINITY, &TextStyle::new(&font, 0, 12.0), &nowrap);
+        assert_eq!(lines.len(), 1);
+        assert!(lines[0].text.starts_with("one two") && lines[0].text.ends_with('\u{2026}'));
+    }
+
+    #[test]
+    fn test_shrink_to_fit() {
+        let font = roboto();
+        let options = options(WhiteSpace::Nowrap);
+        let width = font.measure_text("Label text", 12.0);
+        let fit = TextFit { min_size: Some(6.0), ..Default::default() };
+        let style = TextStyle::new(&font, 0, 12.0);
+        assert_eq!(fit.fitted_size("Label text", width, f64::INFINITY, &style, &options), 12.0);
+        let size = fit.fitted_size("Label text!!", width, f64::INFINITY, &style, &options);
+        assert!(size < 12.0 && size >= 6.0);
+        assert!(font.measure_text("Label text!!", size) <= width);
+        assert_eq!(fit.fitted_size(&"Label ".repeat(10), width, f64::INFINITY, &style, &options), 6.0);
     }
-    s
 }
@@ -0,0 +1,105 @@
+/// An affine transformation `[a b c d e f]`, mapping (x, y) to
+/// (a·x + c·y + e, b·x + d·y + f) as the PDF `cm` operator does
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Matrix {
+    pub a: f64,
+    pub b: f64,
+    pub c: f64,
+    pub d: f64,
+    pub e: f64,
+    pub f: f64,
+}
+
+impl Default for Matrix {
+    fn default() -> Self {
+        Matrix::identity()
+    }
+}
+
+impl Matrix {
+    pub fn identity() -> Self {
+        Matrix { a: 1.0, b: 0.0, c: 0.0, d: 1.0, e: 0.0, f: 0.0 }
+    }
+
+    pub fn translate(tx: f64, ty: f64) -> Self {
+        Matrix { e: tx, f: ty, ..Matrix::identity() }
+    }
+
+    pub fn scale(sx: f64, sy: f64) -> Self {
+        Matrix { a: sx, d: sy, ..Matrix::identity() }
+    }
+
+    /// Rotation by `degrees`, counterclockwise as everywhere in PDF space
+    pub fn rotate(degrees: f64) -> Self {
+        let (sin, cos) = degrees.to_radians().sin_cos();
+        Matrix { a: cos, b: sin, c: -sin, d: cos, e: 0.0, f: 0.0 }
+    }
+
+    /// Skew: x moves by tan(`x_degrees`)·y and y by tan(`y_degrees`)·x
+    pub fn skew(x_degrees: f64, y_degrees: f64) -> Self {
+        Matrix { b: y_degrees.to_radians().tan(), c: x_degrees.to_radians().tan(), ..Matrix::identity() }
+    }
+
+    /// `self` followed by `next`
+    pub fn then(&self, next: &Matrix) -> Matrix {
+        Matrix {
+            a: self.a * next.a + self.b * next.c,
+            b: self.a * next.b + self.b * next.d,
+            c: self.c * next.a + self.d * next.c,
+            d: self.c * next.b + self.d * next.d,
+            e: self.e * next.a + self.f * next.c + next.e,
+            f: self.e * next.b + self.f * next.d + next.f,
+        }
+    }
+
+    /// This transformation with (cx, cy) as its fixed point
+    pub fn around(&self, cx: f64, cy: f64) -> Matrix {
+        Matrix::translate(-cx, -cy).then(self).then(&Matrix::translate(cx, cy))
+    }
+
+    pub fn apply(&self, x: f64, y: f64) -> (f64, f64) {
+        (self.a * x + self.c * y + self.e, self.b * x + self.d * y + self.f)
+    }
+
+    /// Whether horizontal and vertical lines stay horizontal and vertical
+    pub fn is_axis_aligned(&self) -> bool {
+        self.b.abs() < 1e-9 && self.c.abs() < 1e-9
+    }
+
+    /// Bounding box (x, y, width, height) of the rectangle (x, y, width, height) once transformed
+    pub fn transform_rect(&self, x: f64, y: f64, width: f64, height: f64) -> (f64, f64, f64, f64) {
+        let corners = [
+            self.apply(x, y),
+            self.apply(x + width, y),
+            self.apply(x, y + height),
+            self.apply(x + width, y + height),
+        ];
+        let (mut min_x, mut min_y) = corners[0];
+        let (mut max_x, mut max_y) = corners[0];
+        for &(cx, cy) in &corners[1..] {
+            min_x = min_x.min(cx);
+            min_y = min_y.min(cy);
+            max_x = max_x.max(cx);
+            max_y = max_y.max(cy);
+        }
+        (min_x, min_y, max_x - min_x, max_y - min_y)
+    }
+
+    /// The `cm` operator applying this transformation
+    pub fn to_pdf(&self) -> String {
+        // Coefficients get more digits than offsets; they scale whole pages
+        let coefficient = |v: f64| {
+            let rounded = (v * 1e6).round() / 1e6;
+            if rounded == 0.0 { "0".to_string() } else { rounded.to_string() }
+        };
+        let offset = |v: f64| {
+            let rounded = (v * 1e4).round() / 1e4;
+            if rounded == 0.0 { "0".to_string() } else { rounded.to_string() }
+        };
+        format!(
+            "{} {} {} {} {} {} cm ",
+            coefficient(self.a), coefficient(self.b), coefficient(self.c), coefficient(self.d),
+            offset(self.e), offset(self.f)
+        )
+    }
+}
@@ -0,0 +1,296 @@
+use serde::{Deserialize, Serialize};
+use crate::core::color::Color;
+use crate::core::font::Font;
+use crate::core::page::Page;
+use crate::core::transform::Matrix;
+use crate::core::parse::ParseValueError;
+use std::str::FromStr;
+
+/// What a watermark draws
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+pub enum WatermarkContent {
+    /// A line of text such as "DRAFT", in a document font (the first by default)
+    Text {
+        text: String,
+        #[serde(default = "default_text_size")]
+        size: f64,
+        #[serde(default)]
+        color: Option<Color>, // gray by default
+        #[serde(default)]
+        font: Option<u32>,
+    },
+    /// An image registered with the document, or in templates the asset named by `src`
+    Image {
+        #[serde(default)]
+        image: Option<u32>,
+        #[serde(default)]
+        src: Option<String>,
+        #[serde(default)]
+        width: Option<f64>,
+        #[serde(default)]
+        height: Option<f64>,
+    },
+    /// The whole page in one color, for backgrounds
+    Color { color: Color },
+}
+
+fn default_text_size() -> f64 { 72.0 }
+fn default_opacity() -> f64 { 1.0 }
+fn default_margin() -> f64 { 36.0 }
+
+/// Where a watermark sits on the page
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "kebab-case")]
+pub enum WatermarkPosition {
+    #[default]
+    Center,
+    Top,
+    Bottom,
+    Left,
+    Right,
+    TopLeft,
+    TopRight,
+    BottomLeft,
+    BottomRight,
+    /// Stretched over the whole page (images)
+    Fill,
+}
+
+impl FromStr for WatermarkPosition {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().replace('_', "-").as_str() {
+            "top" => Self::Top,
+            "bottom" => Self::Bottom,
+            "left" => Self::Left,
+            "right" => Self::Right,
+            "top-left" => Self::TopLeft,
+            "top-right" => Self::TopRight,
+            "bottom-left" => Self::BottomLeft,
+            "bottom-right" => Self::BottomRight,
+            "fill" => Self::Fill,
+            "center" => Self::Center,
+            _ => return Err(ParseValueError::new("watermark position", s)),
+        })
+    }
+}
+
+/// Whether a watermark is drawn behind or in front of the page content
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Default, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum WatermarkLayer {
+    #[default]
+    Behind,
+    Front,
+}
+
+impl FromStr for WatermarkLayer {
+    type Err = ParseValueError;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        Ok(match s.to_ascii_lowercase().as_str() {
+            "front" | "foreground" | "over" => Self::Front,
+            "behind" | "background" | "under" => Self::Behind,
+            _ => return Err(ParseValueError::new("watermark layer", s)),
+        })
+    }
+}
+
+/// Text, image or color placed on every page a document adds, or on the pages `pages` selects
+#[derive(Debug, Clone, PartialEq, Serialize, Deserialize)]
+pub struct Watermark {
+    #[serde(flatten)]
+    pub content: WatermarkContent,
+    #[serde(default = "default_opacity")]
+    pub opacity: f64,
+    /// Degrees counterclockwise around the watermark's center
+    #[serde(default)]
+    pub rotation: f64,
+    #[serde(default)]
+    pub position: WatermarkPosition,
+    #[serde(default)]
+    pub layer: WatermarkLayer,
+    /// Distance from the page edges for positions other than center and fill
+    #[serde(default = "default_margin")]
+    pub margin: f64,
+    /// Page numbers it applies to, e.g. "1", "2-", "1-3,7", "odd" or "even"; all pages when unset
+    #[serde(default)]
+    pub pages: Option<String>,
+}
+
+impl Watermark {
+    pub fn new(content: WatermarkContent) -> Self {
+        Watermark {
+            content,
+            opacity: default_opacity(),
+            rotation: 0.0,
+            position: WatermarkPosition::Center,
+            layer: WatermarkLayer::Behind,
+            margin: default_margin(),
+            pages: None,
+        }
+    }
+
+    /// A large gray diagonal text watermark in front of the content, such as "DRAFT"
+    pub fn text(text: &str) -> Self {
+        Watermark {
+            rotation: 45.0,
+            opacity: 0.25,
+            layer: WatermarkLayer::Front,
+            ..Watermark::new(WatermarkContent::Text { text: text.to_string(), size: default_text_size(), color: None, font: None })
+        }
+    }
+
+    /// Whether the watermark goes on the page with this 1-based number
+    pub fn applies_to(&self, page_number: usize) -> bool {
+        let Some(spec) = &self.pages else { return true };
+        spec.split(',').map(str::trim).filter(|part| !part.is_empty()).any(|part| match part {
+            "all" => true,
+            "odd" => !page_number.is_multiple_of(2),
+            "even" => page_number.is_multiple_of(2),
+            _ => {
+                let (from, to) = part.split_once('-').unwrap_or((part, part));
+                let from = from.trim().parse().unwrap_or(1);
+                let to = to.trim().parse().unwrap_or(usize::MAX);
+                (from..=to).contains(&page_number)
+            }
+        })
+    }
+
+    /// Draw the watermark on a page-sized canvas. `fonts` are the document's fonts;
+    /// without any, text is set in the built-in Helvetica. `image_sizes` are the natural
+    /// sizes of the document's images, which give an image without a width or height its
+    /// aspect ratio.
+    pub fn draw(&self, page: &mut Page, fonts: &[Font], image_sizes: &[(f64, f64)]) {
+        let (page_width, page_height) = (page.width as f64, page.height as f64);
+        page.set_opacity(self.opacity, self.opacity);
+        match &self.content {
+            WatermarkContent::Color { color } => {
+                page.draw_rect_filled(0.0, 0.0, page_width, page_height, color.clone());
+            }
+            WatermarkContent::Image { image: Some(index), width, height, .. } => {
+                if self.position == WatermarkPosition::Fill {
+                    page.draw_image(*index, 0.0, 0.0, page_width, page_height);
+                    return;
+                }
+                let (w, h) = image_size(*width, *height, image_sizes.get(*index as usize).copied());
+                self.place(page, w, h, |page| { page.draw_image(*index, -w / 2.0, -h / 2.0, w, h); });
+            }
+            WatermarkContent::Image { image: None, .. } => {}
+            WatermarkContent::Text { text, size, color, font } => {
+                let font_index = font.unwrap_or(0);
+                let color = color.clone().unwrap_or(Color::gray(0.5));
+                // Baseline placed so capitals are centered vertically
+                let baseline = -size * 0.35;
+                match fonts.get(font_index as usize) {
+                    Some(font) => {
+                        let width = font.measure_text(text, *size);
+                        self.place(page, width, *size, |page| {
+                            page.set_fill_color(color);
+                            page.text_with_font(text.clone(), -width / 2.0, baseline, *size, font_index, font);
+                        });
+                    }
+                    None => {
+                        // Rough Helvetica advance
+                        let width = text.chars().count() as f64 * size * 0.55;
+                        self.place(page, width, *size, |page| {
+                            page.set_fill_color(color);
+                            page.text(text.clone(), -width / 2.0, baseline, *size);
+                        });
+                    }
+                }
+            }
+        }
+    }
+
+    /// Run `draw`, which draws a `width` × `height` box centered on the origin,
+    /// moved to the watermark's position and rotated
+    fn place(&self, page: &mut Page, width: f64, height: f64, draw: impl FnOnce(&mut Page)) {
+        let (page_width, page_height) = (page.width as f64, page.height as f64);
+        // Keep the rotated box inside the margins
+        let (sin, cos) = self.rotation.to_radians().sin_cos();
+        let (width, height) = (width * cos.abs() + height * sin.abs(), width * sin.abs() + height * cos.abs());
+        let m = self.margin;
+        let left = m + width / 2.0;
+        let right = page_width - m - width / 2.0;
+        let top = page_height - m - height / 2.0;
+        let bottom = m + height / 2.0;
+        let (center_x, center_y) = (page_width / 2.0, page_height / 2.0);
+        let (x, y) = match self.position {
+            WatermarkPosition::Center | WatermarkPosition::Fill => (center_x, center_y),
+            WatermarkPosition::Top => (center_x, top),
+            WatermarkPosition::Bottom => (center_x, bottom),
+            WatermarkPosition::Left => (left, center_y),
+            WatermarkPosition::Right => (right, center_y),
+            WatermarkPosition::TopLeft => (left, top),
+            WatermarkPosition::TopRight => (right, top),
+            WatermarkPosition::BottomLeft => (left, bottom),
+            WatermarkPosition::BottomRight => (right, bottom),
+        };
+        page.push_transform(&Matrix::rotate(self.rotation).then(&Matrix::translate(x, y)));
+        draw(page);
+        page.pop_transform();
+    }
+}
+
+/// Size of an image drawn with an optional width and height: a missing side follows the
+/// image's aspect ratio and both missing give its natural size. Unknown images are 100 × 100.
+fn image_size(width: Option<f64>, height: Option<f64>, natural: Option<(f64, f64)>) -> (f64, f64) {
+    match (width, height, natural) {
+        (Some(w), Some(h), _) => (w, h),
+        (Some(w), None, Some((nw, nh))) if nw > 0.0 => (w, w * nh / nw),
+        (None, Some(h), Some((nw, nh))) if nh > 0.0 => (h * nw / nh, h),
+        (None, None, Some(size)) => size,
+        (w, h, _) => (w.unwrap_or(100.0), h.unwrap_or(100.0)),
+    }
+}
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn pages(spec: &str) -> Vec<usize> {
+        let watermark = Watermark { pages: Some(spec.to_string()), ..Watermark::text("DRAFT") };
+        (1..=10).filter(|&page| watermark.applies_to(page)).collect()
+    }
+
+    #[test]
+    fn test_applies_to_page_specs() {
+        assert_eq!(pages("1-3,7"), vec![1, 2, 3, 7]);
+        assert_eq!(pages(" 1 - 2 , 9 "), vec![1, 2, 9]);
+        assert_eq!(pages("2-"), vec![2, 3, 4, 5, 6, 7, 8, 9, 10]);
+        assert_eq!(pages("-3"), vec![1, 2, 3]);
+        assert_eq!(pages("odd"), vec![1, 3, 5, 7, 9]);
+        assert_eq!(pages("even"), vec![2, 4, 6, 8, 10]);
+        assert_eq!(pages("even,1"), vec![1, 2, 4, 6, 8, 10]);
+        assert_eq!(pages("all").len(), 10);
+        assert!(pages("").is_empty());
+        assert!(Watermark::text("DRAFT").applies_to(42));
+    }
+
+    fn image_watermark(width: Option<f64>, height: Option<f64>) -> Watermark {
+        Watermark::new(WatermarkContent::Image { image: Some(0), src: None, width, height })
+    }
+
+    /// The `cm` that scales the image, as (width, height)
+    fn drawn_size(watermark: &Watermark, image_sizes: &[(f64, f64)]) -> (f64, f64) {
+        let mut page = Page::new(600.0, 800.0);
+        watermark.draw(&mut page, &[], image_sizes);
+        let content = String::from_utf8_lossy(&page.content).to_string();
+        let draw = &content[..content.find("/Im0 Do").unwrap()];
+        let values: Vec<f64> = draw[draw.rfind("q ").unwrap() + 2..].split_whitespace().take(4).map(|v| v.parse().unwrap()).collect();
+        (values[0], values[3])
+    }
+
+    #[test]
+    fn test_image_size_from_aspect_ratio() {
+        let sizes = [(400.0, 200.0)];
+        assert_eq!(drawn_size(&image_watermark(Some(100.0), None), &sizes), (100.0, 50.0));
+        assert_eq!(drawn_size(&image_watermark(None, Some(100.0)), &sizes), (200.0, 100.0));
+        assert_eq!(drawn_size(&image_watermark(None, None), &sizes), (400.0, 200.0));
+        assert_eq!(drawn_size(&image_watermark(Some(30.0), Some(40.0)), &sizes), (30.0, 40.0));
+        assert_eq!(drawn_size(&image_watermark(None, None), &[]), (100.0, 100.0));
+    }
+}
@@ -1,20 +1,34 @@
-use napi::{Result, Error, Status};
+use napi::{Either, Result, Error, Status};
 use napi_derive::napi;
 use std::io;
+use std::str::FromStr;
 
-use crate::core::font::Font as CoreFont;
+use crate::core::font::{Font as CoreFont, FontSet as CoreFontSet};
 use crate::core::page::Page as CorePage;
+use crate::core::gradient::{ColorStop as CoreColorStop, Gradient as CoreGradient};
+use crate::core::transform::Matrix as CoreMatrix;
+use crate::core::path::{Path as CorePath, PathStyle as CorePathStyle};
 use crate::core::document::Document as CoreDocument;
 use crate::core::image::Image as CoreImage;
+use crate::core::form::Form as CoreForm;
+use crate::core::layer::Layer as CoreLayer;
+use crate::core::watermark::{Watermark as CoreWatermark, WatermarkContent as CoreWatermarkContent};
 use crate::core::table::{Table as CoreTable, TableColumn as CoreTableColumn, TextAlign as CoreTextAlign};
-use crate::core::layout::{LayoutNode as CoreLayoutNode, Column as CoreColumn, Row as CoreRow, TextNode as CoreTextNode, Container as CoreContainer, ImageNode as CoreImageNode, Rect as CoreRect, Constraints as CoreConstraints, SplitAction, PageContext as CorePageContext};
+use crate::core::layout::{LayoutNode as CoreLayoutNode, Column as CoreColumn, Row as CoreRow, TextNode as CoreTextNode, RichTextNode as CoreRichTextNode, TextSpan as CoreTextSpan, Container as CoreContainer, Keep as CoreKeep, ListItem as CoreListItem, ListNode as CoreListNode, ImageMask as CoreImageMask, ImageNode as CoreImageNode, TransformNode as CoreTransformNode, LayerNode as CoreLayerNode, Rect as CoreRect, Constraints as CoreConstraints, SplitAction, PageContext as CorePageContext};
 use crate::core::template::Template as CoreTemplate;
+use crate::core::parse::ParseValueError;
+use crate::core::text::{Hyphenation, LineHeight as CoreLineHeight, TabStop as CoreTabStop, TextDecoration as CoreTextDecoration, TextDirection as CoreTextDirection};
 
 // Helper to map IO errors to N-API errors
 fn map_io_err(e: io::Error) -> Error {
     Error::from_reason(e.to_string())
 }
 
+/// Parse an optional string setting, rejecting values it does not name
+fn parse_setting<T: FromStr<Err = ParseValueError>>(value: Option<&str>) -> Result<Option<T>> {
+    value.map(str::parse).transpose().map_err(|e: ParseValueError| Error::new(Status::InvalidArg, e.to_string()))
+}
+
 /// Represents a loaded font with parsing and shaping capabilities
 #[napi]
 pub struct Font {
@@ -59,7 +73,29 @@ impl Font {
     }
 }
 
-/// Represents a loaded image (JPEG or PNG)
+/// Fonts rich text spans can pick from by family and weight
+#[napi]
+#[derive(Default)]
+pub struct FontSet {
+    inner: CoreFontSet,
+}
+
+#[napi]
+impl FontSet {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        FontSet { inner: CoreFontSet::new() }
+    }
+
+    /// Register a font added to the document at `font_index`.
+    /// Its family and weight are read from the font file.
+    #[napi]
+    pub fn add(&mut self, font: &Font, font_index: u32) {
+        self.inner.add(&font.inner, font_index);
+    }
+}
+
+/// Represents a loaded image (JPEG, PNG or SVG)
 #[napi]
 pub struct Image {
     inner: CoreImage,
@@ -80,6 +116,14 @@ impl Image {
         let inner = CoreImage::from_bytes(&data).map_err(map_io_err)?;
         Ok(Image { inner })
     }
+
+    /// Load an SVG as vector graphics; `<text>` is drawn with the given fonts
+    #[napi(factory)]
+    pub fn from_svg(data: Vec<u8>, fonts: Vec<&Font>) -> Result<Self> {
+        let fonts: Vec<CoreFont> = fonts.iter().map(|f| f.inner.clone()).collect();
+        let inner = CoreImage::from_svg(&data, &fonts).map_err(map_io_err)?;
+        Ok(Image { inner })
+    }
 }
 
 /// Represents a shaped glyph with position and advance information
@@ -93,14 +137,383 @@ pub struct ShapedGlyph {
 }
 
 /// Column definition for Table
-/// Color structure (RGB/RGBA)
+/// Color: RGB (`r`, `g`, `b`), `gray`, CMYK (`c`, `m`, `y`, `k`), a `spot` ink at `tint`
+/// with a CMYK `alternate`, or `components` in an `icc` profile registered with the document
 #[napi(object)]
-#[derive(Clone, Copy)]
+#[derive(Clone)]
 pub struct Color {
-    pub r: f64,
-    pub g: f64,
-    pub b: f64,
+    pub r: Option<f64>,
+    pub g: Option<f64>,
+    pub b: Option<f64>,
     pub a: Option<f64>,
+    pub gray: Option<f64>,
+    pub c: Option<f64>,
+    pub m: Option<f64>,
+    pub y: Option<f64>,
+    pub k: Option<f64>,
+    pub spot: Option<String>,
+    pub tint: Option<f64>,
+    pub alternate: Option<Vec<f64>>,
+    pub icc: Option<String>,
+    pub components: Option<Vec<f64>>,
+}
+
+/// Inline span of a rich text paragraph
+#[napi(object)]
+pub struct TextSpan {
+    pub text: String,
+    pub font_family: Option<String>,
+    pub weight: Option<u16>,
+    pub italic: Option<bool>,
+    pub size: Option<f64>,
+    pub color: Option<Color>,
+    pub underline: Option<bool>,
+    pub decoration: Option<TextDecoration>,
+    pub link: Option<String>,
+}
+
+/// Lines over, under or through text and a highlight behind each line
+#[napi(object)]
+pub struct TextDecoration {
+    pub underline: Option<bool>,
+    pub overline: Option<bool>,
+    pub line_through: Option<bool>,
+    pub highlight: Option<Color>,
+    /// Color of the lines, defaults to the text color
+    pub color: Option<Color>,
+    /// Leave gaps in the underline around descenders
+    pub skip_ink: Option<bool>,
+}
+
+/// Tab position in points from the start of the line.
+/// `align` is "left" (default), "right", "center" or "decimal";
+/// `leader` is "dots", "dashes" or "underline".
+#[napi(object)]
+pub struct TabStop {
+    pub position: f64,
+    pub align: Option<String>,
+    pub leader: Option<String>,
+}
+
+fn to_core_tab_stop(t: TabStop) -> Result<CoreTabStop> {
+    Ok(CoreTabStop {
+        position: t.position,
+        align: parse_setting(t.align.as_deref())?.unwrap_or_default(),
+        leader: parse_setting(t.leader.as_deref())?.unwrap_or_default(),
+    })
+}
+
+fn to_core_decoration(d: TextDecoration) -> CoreTextDecoration {
+    CoreTextDecoration {
+        underline: d.underline.unwrap_or(false),
+        overline: d.overline.unwrap_or(false),
+        line_through: d.line_through.unwrap_or(false),
+        highlight: d.highlight.map(to_core_color),
+        color: d.color.map(to_core_color),
+        skip_ink: d.skip_ink.unwrap_or(false),
+    }
+}
+
+/// Settings for `LayoutNode.text`
+#[napi(object)]
+#[derive(Default)]
+pub struct TextOptions {
+    pub color: Option<Color>,
+    pub background_color: Option<Color>,
+    /// "ltr", "rtl" or "auto" (default)
+    pub direction: Option<String>,
+    /// "horizontal-tb" (default) or "vertical-rl"
+    pub writing_mode: Option<String>,
+    /// "normal" (default), "pre", "pre-wrap" or "nowrap"
+    pub white_space: Option<String>,
+    /// Language (e.g. "de") whose patterns hyphenate the text
+    pub lang: Option<String>,
+    /// "left", "center", "right" or "justify"
+    pub align: Option<String>,
+    /// Fixed box width
+    pub width: Option<f64>,
+    /// Multiple of the size (1.5) or points ("18pt")
+    pub line_height: Option<Either<f64, String>>,
+    /// Points
+    pub letter_spacing: Option<f64>,
+    pub word_spacing: Option<f64>,
+    /// Fewest lines left on either side of a page break (default 2)
+    pub orphans: Option<u32>,
+    pub widows: Option<u32>,
+    /// Lines after this many are dropped
+    pub max_lines: Option<u32>,
+    /// "visible" (default), "clip" or "ellipsis"
+    pub overflow: Option<String>,
+    /// Size the text may shrink down to so it fits its box
+    pub min_size: Option<f64>,
+    /// "greedy" (default), or "optimal" to break whole paragraphs at once for even spacing
+    pub line_breaker: Option<String>,
+    /// Underline, overline, strikethrough or a highlight on every line
+    pub decoration: Option<TextDecoration>,
+    /// Positions tabs advance to, which may fill the gap with leaders
+    pub tab_stops: Option<Vec<TabStop>>,
+}
+
+/// Settings for `LayoutNode.richText`; spans override the size and color
+#[napi(object)]
+#[derive(Default)]
+pub struct RichTextOptions {
+    pub color: Option<Color>,
+    /// "left", "center", "right" or "justify"
+    pub align: Option<String>,
+    /// "normal" (default), "pre", "pre-wrap" or "nowrap"
+    pub white_space: Option<String>,
+    /// Language (e.g. "de") whose patterns hyphenate the text
+    pub lang: Option<String>,
+    /// Multiple of each span's size (1.5) or points ("18pt")
+    pub line_height: Option<Either<f64, String>>,
+    /// Points
+    pub letter_spacing: Option<f64>,
+    pub word_spacing: Option<f64>,
+    /// Fewest lines left on either side of a page break (default 2)
+    pub orphans: Option<u32>,
+    pub widows: Option<u32>,
+}
+
+/// Settings for `LayoutNode.list`
+#[napi(object)]
+#[derive(Default)]
+pub struct ListOptions {
+    /// "bullet" (default), a sample number like "1.", "a)" or "i.", or any bullet text
+    pub marker: Option<String>,
+    /// Number of the first item (default 1)
+    pub start: Option<u32>,
+    /// Nesting depth, which picks the default bullet
+    pub level: Option<u32>,
+    /// Distance to the item content (default 18)
+    pub indent: Option<f64>,
+    /// Space between a marker and its item (default 6)
+    pub marker_gap: Option<f64>,
+    /// Space between items
+    pub spacing: Option<f64>,
+    pub marker_size: Option<f64>,
+    pub marker_color: Option<Color>,
+}
+
+/// Paint settings for `Page.drawPath`. Without `fill` or `stroke` the path is stroked in black.
+#[napi(object)]
+pub struct PathStyle {
+    pub fill: Option<Color>,
+    pub stroke: Option<Color>,
+    pub stroke_width: Option<f64>,
+    /// "nonzero" (default) or "evenodd"
+    pub fill_rule: Option<String>,
+    /// Alternating dash and gap lengths
+    pub dash: Option<Vec<f64>>,
+    pub dash_phase: Option<f64>,
+    /// "butt", "round" or "square"
+    pub line_cap: Option<String>,
+    /// "miter", "round" or "bevel"
+    pub line_join: Option<String>,
+    pub miter_limit: Option<f64>,
+}
+
+fn to_core_path_style(s: PathStyle) -> Result<CorePathStyle> {
+    let defaults = CorePathStyle::default();
+    let stroke = match (s.stroke, &s.fill) {
+        (Some(stroke), _) => Some(to_core_color(stroke)),
+        (None, Some(_)) => None,
+        (None, None) => defaults.stroke,
+    };
+    Ok(CorePathStyle {
+        fill: s.fill.map(to_core_color),
+        stroke,
+        stroke_width: s.stroke_width.unwrap_or(defaults.stroke_width),
+        fill_rule: parse_setting(s.fill_rule.as_deref())?.unwrap_or_default(),
+        dash: s.dash.unwrap_or_default(),
+        dash_phase: s.dash_phase.unwrap_or(0.0),
+        line_cap: parse_setting(s.line_cap.as_deref())?.unwrap_or_default(),
+        line_join: parse_setting(s.line_join.as_deref())?.unwrap_or_default(),
+        miter_limit: s.miter_limit.unwrap_or(defaults.miter_limit),
+    })
+}
+
+/// Color at `offset` (0 to 1) along a gradient
+#[napi(object)]
+pub struct ColorStop {
+    pub offset: f64,
+    pub color: Color,
+}
+
+/// Linear or radial gradient fill
+#[napi(object)]
+pub struct Gradient {
+    /// "linear" (default) or "radial"
+    pub kind: Option<String>,
+    /// Linear direction in degrees, CSS style: 0 to top, 90 to right, 180 (default) to bottom
+    pub angle: Option<f64>,
+    /// Radial center as fractions of the box from its top-left corner (default 0.5)
+    pub center_x: Option<f64>,
+    pub center_y: Option<f64>,
+    /// Radial extent in points, the farthest corner by default
+    pub radius: Option<f64>,
+    pub stops: Vec<ColorStop>,
+}
+
+fn to_core_gradient(g: Gradient) -> CoreGradient {
+    let stops = g.stops.into_iter()
+        .map(|s| CoreColorStop { offset: s.offset, color: to_core_color(s.color) })
+        .collect();
+    match g.kind.as_deref() {
+        Some("radial") => CoreGradient::Radial {
+            center_x: g.center_x.unwrap_or(0.5),
+            center_y: g.center_y.unwrap_or(0.5),
+            radius: g.radius,
+            stops,
+        },
+        _ => CoreGradient::Linear { angle: g.angle.unwrap_or(180.0), stops },
+    }
+}
+
+/// Page transformation: scale, then skew, then rotate around the origin, then translate
+#[napi(object)]
+pub struct Transform {
+    /// Degrees, counterclockwise
+    pub rotate: Option<f64>,
+    pub scale_x: Option<f64>,
+    pub scale_y: Option<f64>,
+    /// Degrees
+    pub skew_x: Option<f64>,
+    pub skew_y: Option<f64>,
+    pub translate_x: Option<f64>,
+    pub translate_y: Option<f64>,
+    /// Point that stays in place (default 0, 0)
+    pub origin_x: Option<f64>,
+    pub origin_y: Option<f64>,
+}
+
+fn to_core_matrix(t: Transform) -> CoreMatrix {
+    CoreMatrix::scale(t.scale_x.unwrap_or(1.0), t.scale_y.unwrap_or(1.0))
+        .then(&CoreMatrix::skew(t.skew_x.unwrap_or(0.0), t.skew_y.unwrap_or(0.0)))
+        .then(&CoreMatrix::rotate(t.rotate.unwrap_or(0.0)))
+        .around(t.origin_x.unwrap_or(0.0), t.origin_y.unwrap_or(0.0))
+        .then(&CoreMatrix::translate(t.translate_x.unwrap_or(0.0), t.translate_y.unwrap_or(0.0)))
+}
+
+/// Optional content group registered with `Document.addLayer`
+#[napi(object)]
+pub struct Layer {
+    pub name: String,
+    /// Shown when the document is opened (default true)
+    pub visible: Option<bool>,
+    /// Printed regardless of the on-screen state, e.g. false for screen-only notes
+    pub print: Option<bool>,
+    /// Shown on screen regardless of `visible`
+    pub view: Option<bool>,
+    /// Layers sharing a group name are exclusive: turning one on turns the others off
+    pub group: Option<String>,
+}
+
+fn to_core_layer(l: Layer) -> CoreLayer {
+    CoreLayer {
+        visible: l.visible.unwrap_or(true),
+        print: l.print,
+        view: l.view,
+        group: l.group,
+        ..CoreLayer::new(&l.name)
+    }
+}
+
+/// Text, image or full-page color drawn on every page added after `Document.addWatermark`.
+/// One of `text`, `image` or `color` (alone, a page background) is required.
+#[napi(object)]
+pub struct Watermark {
+    pub text: Option<String>,
+    /// Image index from `Document.addImage`
+    pub image: Option<u32>,
+    /// Text color (gray by default), or the page color without text or image
+    pub color: Option<Color>,
+    /// Text size (default 72)
+    pub size: Option<f64>,
+    /// Font for the text, the first registered font by default
+    pub font_index: Option<u32>,
+    /// Image size; a missing side keeps the image's aspect ratio, and without either
+    /// the image is drawn at its natural size
+    pub width: Option<f64>,
+    pub height: Option<f64>,
+    /// 0 to 1 (default 1)
+    pub opacity: Option<f64>,
+    /// Degrees, counterclockwise
+    pub rotation: Option<f64>,
+    /// "center" (default), "top", "bottom", "left", "right", "top-left", "top-right",
+    /// "bottom-left", "bottom-right" or "fill"
+    pub position: Option<String>,
+    /// "behind" (default) or "front"
+    pub layer: Option<String>,
+    /// Distance from the page edges (default 36)
+    pub margin: Option<f64>,
+    /// Page numbers, e.g. "1", "2-", "1-3,7", "odd" or "even"; all pages by default
+    pub pages: Option<String>,
+}
+
+fn to_core_watermark(w: Watermark) -> Result<CoreWatermark> {
+    let content = match (w.text, w.image, w.color) {
+        (Some(text), _, color) => CoreWatermarkContent::Text {
+            text,
+            size: w.size.unwrap_or(72.0),
+            color: color.map(to_core_color),
+            font: w.font_index,
+        },
+        (None, Some(image), _) => CoreWatermarkContent::Image { image: Some(image), src: None, width: w.width, height: w.height },
+        (None, None, Some(color)) => CoreWatermarkContent::Color { color: to_core_color(color) },
+        (None, None, None) => return Err(Error::new(Status::InvalidArg, "Watermark needs text, image or color".to_string())),
+    };
+    let defaults = CoreWatermark::new(content);
+    Ok(CoreWatermark {
+        opacity: w.opacity.unwrap_or(defaults.opacity),
+        rotation: w.rotation.unwrap_or(defaults.rotation),
+        position: parse_setting(w.position.as_deref())?.unwrap_or_default(),
+        layer: parse_setting(w.layer.as_deref())?.unwrap_or_default(),
+        margin: w.margin.unwrap_or(defaults.margin),
+        pages: w.pages,
+        ..defaults
+    })
+}
+
+// Colors may be given as 0-1 or 0-255 components
+fn to_core_color(c: Color) -> crate::core::color::Color {
+    use crate::core::color::Color as CoreColor;
+    let alpha = c.a.unwrap_or(1.0);
+    let four = |values: &[f64]| [0, 1, 2, 3].map(|i| values.get(i).copied().unwrap_or(0.0));
+    if let Some(spot) = &c.spot {
+        let alternate = four(c.alternate.as_deref().unwrap_or(&[]));
+        return CoreColor::spot(spot, c.tint.unwrap_or(1.0), alternate).with_alpha(alpha);
+    }
+    if let Some(icc) = &c.icc {
+        let components = c.components.as_deref().unwrap_or(&[]);
+        let [c0, c1, c2, c3] = four(components);
+        let color = if components.len() == 4 {
+            CoreColor::icc_cmyk(icc, c0, c1, c2, c3)
+        } else {
+            CoreColor::icc_rgb(icc, c0, c1, c2)
+        };
+        return color.with_alpha(alpha);
+    }
+    if c.c.is_some() || c.m.is_some() || c.y.is_some() || c.k.is_some() {
+        return CoreColor::cmyk(c.c.unwrap_or(0.0), c.m.unwrap_or(0.0), c.y.unwrap_or(0.0), c.k.unwrap_or(0.0)).with_alpha(alpha);
+    }
+    if let Some(gray) = c.gray {
+        return CoreColor::gray(gray).with_alpha(alpha);
+    }
+    let (r, g, b) = (c.r.unwrap_or(0.0), c.g.unwrap_or(0.0), c.b.unwrap_or(0.0));
+    if r > 1.0 || g > 1.0 || b > 1.0 {
+        CoreColor::rgba(r / 255.0, g / 255.0, b / 255.0, alpha)
+    } else {
+        CoreColor::rgba(r, g, b, alpha)
+    }
+}
+
+// Line height is a multiple of the font size, or a string such as "18pt"
+fn to_core_line_height(value: Either<f64, String>) -> Result<CoreLineHeight> {
+    match value {
+        Either::A(factor) => Ok(CoreLineHeight::Multiple(factor)),
+        Either::B(s) => parse_setting(Some(&s)).map(Option::unwrap_or_default),
+    }
 }
 
 /// Template for repeating headers and footers
@@ -160,6 +573,20 @@ impl Table {
     pub fn set_font_size(&mut self, size: f64) {
         self.inner.settings.font_size = size;
     }
+    /// Language of the cell text (e.g. "de"); enables hyphenation in narrow columns
+    #[napi]
+    pub fn set_lang(&mut self, lang: Option<String>) {
+        self.inner.settings.lang = lang;
+    }
+    /// Fit cell text into its cell: keep at most `max_lines` lines, handle the rest with
+    /// `overflow` ("visible", "clip" or "ellipsis"), and shrink the font down to `min_font_size`
+    #[napi]
+    pub fn set_overflow(&mut self, max_lines: Option<u32>, overflow: Option<String>, min_font_size: Option<f64>) -> Result<()> {
+        self.inner.settings.max_lines = max_lines.map(|n| n as usize);
+        self.inner.settings.overflow = parse_setting(overflow.as_deref())?.unwrap_or_default();
+        self.inner.settings.min_font_size = min_font_size;
+        Ok(())
+    }
 }
 
 
@@ -266,8 +693,9 @@ impl LayoutNode {
         LayoutNode { inner: Arc::new(col) }
     }
     
+    /// Create a Row node. `direction` "rtl" mirrors the child order.
     #[napi(factory)]
-    pub fn row(children: Vec<&LayoutNode>, spacing: Option<f64>) ->  Self {
+    pub fn row(children: Vec<&LayoutNode>, spacing: Option<f64>, direction: Option<String>) -> Result<Self> {
         let core_children: Vec<Arc<dyn CoreLayoutNode>> = children.iter()
             .map(|n| n.inner.clone())
             .collect();
@@ -277,31 +705,120 @@ impl LayoutNode {
             spacing: spacing.unwrap_or(0.0),
             align_items: Default::default(),
             justify_content: Default::default(),
+            direction: parse_setting(direction.as_deref())?.unwrap_or(CoreTextDirection::Ltr),
         };
         
-        LayoutNode { inner: Arc::new(row) }
+        Ok(LayoutNode { inner: Arc::new(row) })
     }
     
+    /// Create a Text node; `options` sets its color, direction, wrapping, alignment,
+    /// spacing, page break and overflow rules, decoration and tab stops.
     #[napi(factory)]
-    pub fn text(text: String, size: f64, color: Option<Color>, background_color: Option<Color>) -> Self {
-        let normalize = |c: Color| {
-            if c.r > 1.0 || c.g > 1.0 || c.b > 1.0 {
-                crate::core::color::Color::rgba(c.r / 255.0, c.g / 255.0, c.b / 255.0, c.a.unwrap_or(1.0))
-            } else {
-                crate::core::color::Color::rgba(c.r, c.g, c.b, c.a.unwrap_or(1.0))
-            }
-        };
-
-        let core_color = color.map(normalize);
-        let core_background_color = background_color.map(normalize);
+    pub fn text(text: String, size: f64, options: Option<TextOptions>) -> Result<Self> {
+        let o = options.unwrap_or_default();
         
+        Ok(LayoutNode {
+            inner: Arc::new(CoreTextNode {
+                text,
+                size,
+                color: o.color.map(to_core_color),
+                background_color: o.background_color.map(to_core_color),
+                direction: parse_setting(o.direction.as_deref())?.unwrap_or_default(),
+                writing_mode: parse_setting(o.writing_mode.as_deref())?.unwrap_or_default(),
+                white_space: parse_setting(o.white_space.as_deref())?.unwrap_or_default(),
+                hyphenation: o.lang.as_deref().map(Hyphenation::new),
+                align: parse_setting(o.align.as_deref())?,
+                width: o.width,
+                line_height: o.line_height.map(to_core_line_height).transpose()?.unwrap_or_default(),
+                letter_spacing: o.letter_spacing.unwrap_or(0.0),
+                word_spacing: o.word_spacing.unwrap_or(0.0),
+                orphans: o.orphans.unwrap_or(2) as usize,
+                widows: o.widows.unwrap_or(2) as usize,
+                max_lines: o.max_lines.map(|n| n as usize),
+                overflow: parse_setting(o.overflow.as_deref())?.unwrap_or_default(),
+                min_size: o.min_size,
+                line_breaker: parse_setting(o.line_breaker.as_deref())?.unwrap_or_default(),
+                continues: false,
+                decoration: o.decoration.map(to_core_decoration).unwrap_or_default(),
+                tab_stops: o.tab_stops.unwrap_or_default().into_iter().map(to_core_tab_stop).collect::<Result<_>>()?,
+            }),
+        })
+    }
+    
+    /// Create a RichText node from styled spans.
+    /// Spans with a `font_family`, `weight` or `italic` pick a font from `fonts`.
+    #[napi(factory)]
+    pub fn rich_text(spans: Vec<TextSpan>, size: f64, fonts: Option<&FontSet>, options: Option<RichTextOptions>) -> Result<Self> {
+        let o = options.unwrap_or_default();
+        let core_spans = spans.into_iter().map(|span| CoreTextSpan {
+            text: span.text,
+            font_family: span.font_family,
+            weight: span.weight,
+            italic: span.italic.unwrap_or(false),
+            size: span.size,
+            color: span.color.map(to_core_color),
+            decoration: {
+                let mut decoration = span.decoration.map(to_core_decoration).unwrap_or_default();
+                decoration.underline |= span.underline.unwrap_or(false);
+                decoration
+            },
+            link: span.link,
+        }).collect();
+
+        Ok(LayoutNode {
+            inner: Arc::new(CoreRichTextNode {
+                spans: core_spans,
+                size,
+                color: o.color.map(to_core_color),
+                fonts: fonts.map(|f| f.inner.clone()).unwrap_or_default(),
+                align: parse_setting(o.align.as_deref())?,
+                white_space: parse_setting(o.white_space.as_deref())?.unwrap_or_default(),
+                hyphenation: o.lang.as_deref().map(Hyphenation::new),
+                line_height: o.line_height.map(to_core_line_height).transpose()?.unwrap_or_default(),
+                letter_spacing: o.letter_spacing.unwrap_or(0.0),
+                word_spacing: o.word_spacing.unwrap_or(0.0),
+                orphans: o.orphans.unwrap_or(2) as usize,
+                widows: o.widows.unwrap_or(2) as usize,
+                continues: false,
+            }),
+        })
+    }
+    
+    /// Apply page break rules to a node: `together` moves it to the next page whole
+    /// instead of splitting it, `with_next` keeps it on the same page as the next node.
+    #[napi(factory)]
+    pub fn keep(child: &LayoutNode, together: Option<bool>, with_next: Option<bool>) -> Self {
         LayoutNode {
-            inner: Arc::new(CoreTextNode { text, size, color: core_color, background_color: core_background_color }),
+            inner: Arc::new(CoreKeep {
+                child: child.inner.clone(),
+                together: together.unwrap_or(true),
+                with_next: with_next.unwrap_or(false),
+            }),
         }
     }
     
+    /// Create a List node with one item per node. Nested lists are items holding
+    /// another list with a higher `level` in its `options`.
     #[napi(factory)]
-    pub fn container(child: &LayoutNode, padding: Option<f64>, margin: Option<f64>, border: Option<f64>) -> Self {
+    pub fn list(items: Vec<&LayoutNode>, options: Option<ListOptions>) -> Self {
+        let o = options.unwrap_or_default();
+        LayoutNode {
+            inner: Arc::new(CoreListNode {
+                items: items.iter().map(|n| CoreListItem { content: n.inner.clone(), marker: true }).collect(),
+                marker: o.marker.as_deref().and_then(|v| v.parse().ok()).unwrap_or_default(),
+                start: o.start.unwrap_or(1) as usize,
+                level: o.level.unwrap_or(0) as usize,
+                indent: o.indent.unwrap_or(18.0),
+                marker_gap: o.marker_gap.unwrap_or(6.0),
+                spacing: o.spacing.unwrap_or(0.0),
+                marker_size: o.marker_size.unwrap_or(12.0),
+                marker_color: o.marker_color.map(to_core_color),
+            }),
+        }
+    }
+    
+    #[napi(factory)]
+    pub fn container(child: &LayoutNode, padding: Option<f64>, margin: Option<f64>, border: Option<f64>, background_gradient: Option<Gradient>, clip: Option<bool>) -> Self {
         LayoutNode {
             inner: Arc::new(CoreContainer {
                 child: child.inner.clone(),
@@ -311,19 +828,66 @@ impl LayoutNode {
                 border_color: None,
                 border_radius: 0.0,
                 background_color: None,
+                background_gradient: background_gradient.map(to_core_gradient),
+                clip: clip.unwrap_or(false),
                 width: 0.0,
                 height: 0.0,
             }),
         }
     }
     
+    /// Image, optionally masked to a "circle" or a "rounded" rectangle with corner `mask_radius`
     #[napi(factory)]
-    pub fn image(image_index: u32, width: f64, height: f64) -> Self {
+    pub fn image(image_index: u32, width: f64, height: f64, mask: Option<String>, mask_radius: Option<f64>) -> Self {
+        let mask = match mask.as_deref() {
+            Some("circle") => Some(CoreImageMask::Circle),
+            Some("rounded") => Some(CoreImageMask::Rounded { radius: mask_radius.unwrap_or(0.0) }),
+            _ => None,
+        };
         LayoutNode { 
             inner: Arc::new(CoreImageNode {
                 image_index,
                 width,
                 height,
+                mask,
+            })
+        }
+    }
+
+    /// Child rotated (degrees, counterclockwise), scaled and skewed; the transformed
+    /// bounding box takes up the space and sits at the top-left of the area, so
+    /// unlike `Transform` on a page there is no origin to set
+    #[napi(factory)]
+    pub fn transform(child: &LayoutNode, rotate: Option<f64>, scale_x: Option<f64>, scale_y: Option<f64>, skew_x: Option<f64>, skew_y: Option<f64>) -> Self {
+        LayoutNode {
+            inner: Arc::new(CoreTransformNode {
+                child: child.inner.clone(),
+                rotate: rotate.unwrap_or(0.0),
+                scale_x: scale_x.unwrap_or(1.0),
+                scale_y: scale_y.or(scale_x).unwrap_or(1.0),
+                skew_x: skew_x.unwrap_or(0.0),
+                skew_y: skew_y.unwrap_or(0.0),
+            }),
+        }
+    }
+
+    /// Child placed on a layer from `Document.addLayer`
+    #[napi(factory)]
+    pub fn layer(child: &LayoutNode, layer_index: u32) -> Self {
+        LayoutNode {
+            inner: Arc::new(CoreLayerNode { child: child.inner.clone(), layer: layer_index }),
+        }
+    }
+
+    /// Image cut to a path given relative to the image's bottom-left corner
+    #[napi(factory)]
+    pub fn masked_image(image_index: u32, width: f64, height: f64, mask: &Path) -> Self {
+        LayoutNode {
+            inner: Arc::new(CoreImageNode {
+                image_index,
+                width,
+                height,
+                mask: Some(CoreImageMask::Path(mask.inner.clone())),
             })
         }
     }
@@ -338,14 +902,14 @@ impl LayoutNode {
     }
 
     #[napi(factory)]
-    pub fn page_number(format: String, size: f64, align: Option<String>) -> Self {
-        LayoutNode {
+    pub fn page_number(format: String, size: f64, align: Option<String>) -> Result<Self> {
+        Ok(LayoutNode {
             inner: Arc::new(crate::core::layout::PageNumberNode {
                 format,
                 size,
-                align: align.unwrap_or_else(|| "left".to_string()),
+                align: parse_setting(align.as_deref())?.unwrap_or_default(),
             }),
-        }
+        })
     }
 }
 
@@ -401,6 +965,85 @@ impl Page {
         self
     }
 
+    /// Save the graphics state; opacity and blend mode changes end at the matching `restoreState`
+    #[napi]
+    pub fn save_state(&mut self) -> &Self {
+        self.inner.save_state();
+        self
+    }
+
+    #[napi]
+    pub fn restore_state(&mut self) -> &Self {
+        self.inner.restore_state();
+        self
+    }
+
+    /// Opacity (0-1) of everything drawn next; color alphas multiply with it
+    #[napi]
+    pub fn set_opacity(&mut self, fill: f64, stroke: Option<f64>) -> &Self {
+        self.inner.set_opacity(fill, stroke.unwrap_or(fill));
+        self
+    }
+
+    /// Blend mode of everything drawn next: "normal", "multiply", "screen", "overlay",
+    /// "darken", "lighten", "color-dodge", "color-burn", "hard-light", "soft-light",
+    /// "difference", "exclusion", "hue", "saturation", "color" or "luminosity"
+    #[napi]
+    pub fn set_blend_mode(&mut self, mode: String) -> Result<&Self> {
+        self.inner.set_blend_mode(parse_setting(Some(&mode))?.unwrap_or_default());
+        Ok(self)
+    }
+
+    /// Draw a vector path, stroked and/or filled
+    #[napi]
+    pub fn draw_path(&mut self, path: &Path, style: Option<PathStyle>) -> Result<&Self> {
+        let style = style.map(to_core_path_style).transpose()?.unwrap_or_default();
+        self.inner.draw_path(&path.inner, &style);
+        Ok(self)
+    }
+
+    /// Transform everything drawn until the matching `popTransform`
+    #[napi]
+    pub fn push_transform(&mut self, transform: Transform) -> &Self {
+        self.inner.push_transform(&to_core_matrix(transform));
+        self
+    }
+
+    #[napi]
+    pub fn pop_transform(&mut self) -> &Self {
+        self.inner.pop_transform();
+        self
+    }
+
+    /// Clip everything drawn until the matching `popClip` to a path
+    #[napi]
+    pub fn push_clip(&mut self, path: &Path, fill_rule: Option<String>) -> Result<&Self> {
+        let rule = parse_setting(fill_rule.as_deref())?.unwrap_or_default();
+        self.inner.push_clip(&path.inner, rule);
+        Ok(self)
+    }
+
+    #[napi]
+    pub fn pop_clip(&mut self) -> &Self {
+        self.inner.pop_clip();
+        self
+    }
+
+    /// Fill a path with a gradient spanning the path's bounds
+    #[napi]
+    pub fn fill_gradient(&mut self, path: &Path, gradient: Gradient, fill_rule: Option<String>) -> Result<&Self> {
+        let rule = parse_setting(fill_rule.as_deref())?.unwrap_or_default();
+        self.inner.fill_gradient(&path.inner, &to_core_gradient(gradient), rule);
+        Ok(self)
+    }
+
+    /// Fill a rectangle, rounded when `radius` is given, with a gradient
+    #[napi]
+    pub fn draw_rect_gradient(&mut self, x: f64, y: f64, w: f64, h: f64, gradient: Gradient, radius: Option<f64>) -> &Self {
+        self.inner.draw_rect_gradient(x, y, w, h, radius.unwrap_or(0.0), &to_core_gradient(gradient));
+        self
+    }
+
     /// Draw a filled rectangle (gray)
     #[napi]
     pub fn draw_fill_rect(&mut self, x: f64, y: f64, w: f64, h: f64, gray: f64) -> &Self {
@@ -422,6 +1065,26 @@ impl Page {
         self
     }
 
+    /// Draw a form registered with `Document.addForm` at its own size, bottom-left corner at (x, y)
+    #[napi]
+    pub fn draw_form(&mut self, form_index: u32, x: f64, y: f64) -> &Self {
+        self.inner.draw_form(form_index, x, y);
+        self
+    }
+
+    /// Put everything drawn until the matching `endLayer` on a layer from `Document.addLayer`
+    #[napi]
+    pub fn begin_layer(&mut self, layer_index: u32) -> &Self {
+        self.inner.begin_layer(layer_index);
+        self
+    }
+
+    #[napi]
+    pub fn end_layer(&mut self) -> &Self {
+        self.inner.end_layer();
+        self
+    }
+
     /// Render a declarative layout tree
     #[napi]
     pub fn render_layout(
@@ -463,6 +1126,88 @@ impl Page {
     }
 }
 
+/// Vector path builder for `Page.drawPath`. Coordinates are PDF points, y up.
+#[napi]
+#[derive(Default)]
+pub struct Path {
+    inner: CorePath,
+}
+
+#[napi]
+impl Path {
+    #[napi(constructor)]
+    pub fn new() -> Self {
+        Path { inner: CorePath::new() }
+    }
+
+    /// Start a new subpath
+    #[napi]
+    pub fn move_to(&mut self, x: f64, y: f64) -> &Self {
+        self.inner.move_to(x, y);
+        self
+    }
+
+    #[napi]
+    pub fn line_to(&mut self, x: f64, y: f64) -> &Self {
+        self.inner.line_to(x, y);
+        self
+    }
+
+    /// Cubic Bézier curve with two control points
+    #[napi]
+    pub fn cubic_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64) -> &Self {
+        self.inner.cubic_to(x1, y1, x2, y2, x, y);
+        self
+    }
+
+    /// Quadratic Bézier curve with one control point
+    #[napi]
+    pub fn quad_to(&mut self, cx: f64, cy: f64, x: f64, y: f64) -> &Self {
+        self.inner.quad_to(cx, cy, x, y);
+        self
+    }
+
+    /// Circular arc; angles in degrees counterclockwise from the x axis
+    #[napi]
+    pub fn arc(&mut self, cx: f64, cy: f64, radius: f64, start_angle: f64, end_angle: f64) -> &Self {
+        self.inner.arc(cx, cy, radius, start_angle, end_angle);
+        self
+    }
+
+    #[napi]
+    pub fn circle(&mut self, cx: f64, cy: f64, radius: f64) -> &Self {
+        self.inner.circle(cx, cy, radius);
+        self
+    }
+
+    #[napi]
+    pub fn ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64) -> &Self {
+        self.inner.ellipse(cx, cy, rx, ry);
+        self
+    }
+
+    /// Rectangle from its bottom-left corner, optionally with rounded corners
+    #[napi]
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, radius: Option<f64>) -> &Self {
+        self.inner.rounded_rect(x, y, width, height, radius.unwrap_or(0.0));
+        self
+    }
+
+    /// Closed polygon through flat `[x0, y0, x1, y1, ...]` coordinates
+    #[napi]
+    pub fn polygon(&mut self, points: Vec<f64>) -> &Self {
+        let points: Vec<(f64, f64)> = points.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+        self.inner.polygon(&points);
+        self
+    }
+
+    #[napi]
+    pub fn close(&mut self) -> &Self {
+        self.inner.close();
+        self
+    }
+}
+
 /// Represents a PDF document with multiple pages
 #[napi]
 pub struct Document {
@@ -498,6 +1243,61 @@ impl Document {
         }
     }
 
+    /// Register an ICC profile for colors given as `{ icc: name, components }`
+    #[napi]
+    pub fn add_icc_profile(&mut self, name: String, data: Vec<u8>) -> Result<()> {
+        if let Some(doc) = &mut self.inner {
+            doc.add_icc_profile(&name, data);
+            Ok(())
+        } else {
+            Err(Error::new(Status::GenericFailure, "Document is finalized".to_string()))
+        }
+    }
+
+    /// Register a layer (optional content group)
+    /// Returns the layer index to use with `Page.beginLayer` and `LayoutNode.layer`
+    #[napi]
+    pub fn add_layer(&mut self, layer: Layer) -> Result<u32> {
+        if let Some(doc) = &mut self.inner {
+            doc.add_layer(&to_core_layer(layer)).map_err(map_io_err)
+        } else {
+            Err(Error::new(Status::GenericFailure, "Document is finalized".to_string()))
+        }
+    }
+
+    /// Draw a watermark or background on every page added from now on that it applies to
+    #[napi]
+    pub fn add_watermark(&mut self, watermark: Watermark) -> Result<()> {
+        if let Some(doc) = &mut self.inner {
+            doc.add_watermark(to_core_watermark(watermark)?);
+            Ok(())
+        } else {
+            Err(Error::new(Status::GenericFailure, "Document is finalized".to_string()))
+        }
+    }
+
+    /// Register everything drawn on `page` as a form of the page's size, drawn by reference
+    /// Returns the form index to use with `Page.drawForm`
+    #[napi]
+    pub fn add_form(&mut self, page: &Page) -> Result<u32> {
+        if let Some(doc) = &mut self.inner {
+            Ok(doc.add_form(&CoreForm::from_page(&page.inner)))
+        } else {
+             Err(Error::new(Status::GenericFailure, "Document is finalized".to_string()))
+        }
+    }
+
+    /// Register a layout tree, measured at up to `width`, as a form of its natural size
+    /// Returns the form index to use with `Page.drawForm`
+    #[napi]
+    pub fn add_layout_form(&mut self, node: &LayoutNode, width: f64, font: &Font, font_index: u32) -> Result<u32> {
+        if let Some(doc) = &mut self.inner {
+            Ok(doc.add_form(&CoreForm::from_layout_measured(node.inner.as_ref(), width, &font.inner, font_index)))
+        } else {
+             Err(Error::new(Status::GenericFailure, "Document is finalized".to_string()))
+        }
+    }
+
     /// Register an image with the document
     /// Returns the image index to use in page rendering
     #[napi]
@@ -540,15 +1340,47 @@ impl Document {
     }
 
     /// Register assets from a loaded Template into this Document
-    /// This is required if the template contains images.
+    /// This is required if the template contains images or fonts used by rich text.
     #[napi]
     pub fn register_template_assets(&mut self, template: &mut Template) -> Result<()> {
         if let Some(doc) = &mut self.inner {
+             let mut fonts = Vec::new();
+             let mut svgs = Vec::new();
              for (name, bytes) in &template.inner.assets {
+                 if name.ends_with(".ttf") || name.ends_with(".otf") {
+                     let font = CoreFont::from_bytes(bytes.clone(), name.clone()).map_err(map_io_err)?;
+                     let idx = doc.add_font(&font);
+                     template.inner.asset_indices.insert(name.clone(), idx);
+                     template.inner.fonts.add(&font, idx);
+                     fonts.push(font);
+                     continue;
+                 }
+                 if name.ends_with(".icc") || name.ends_with(".icm") {
+                     doc.add_icc_profile(name, bytes.clone());
+                     continue;
+                 }
+                 if name.ends_with(".svg") {
+                     svgs.push(name.clone());
+                     continue;
+                 }
                  let img = CoreImage::from_bytes(bytes).map_err(map_io_err)?;
                  let idx = doc.add_image(&img).map_err(map_io_err)?;
                  template.inner.asset_indices.insert(name.clone(), idx);
              }
+             // SVG text is drawn with the template's fonts, so those are loaded first
+             for name in svgs {
+                 let img = CoreImage::from_svg(&template.inner.assets[&name], &fonts).map_err(map_io_err)?;
+                 let idx = doc.add_image(&img).map_err(map_io_err)?;
+                 template.inner.asset_indices.insert(name, idx);
+             }
+             // Nodes name their layer, so layers are registered like assets, by their own names
+             for layer in template.inner.layers() {
+                 let idx = doc.add_layer(&layer).map_err(map_io_err)?;
+                 template.inner.layer_indices.insert(layer.name, idx);
+             }
+             for watermark in template.inner.watermarks() {
+                 doc.add_watermark(watermark);
+             }
              Ok(())
         } else {
              Err(Error::new(Status::GenericFailure, "Document is finalized".to_string()))
@@ -601,7 +1433,7 @@ impl Document {
             page_count += 1;
             let node = current_node.unwrap();
             
-            match node.split(content_width, body_available_height, &font.inner) {
+            match node.split_at_page_top(content_width, body_available_height, &font.inner) {
                 SplitAction::Fit | SplitAction::Push => {
                     current_node = None;
                 },
@@ -611,6 +1443,18 @@ impl Document {
             }
         }
 
+        // Headers and footers without page numbers are drawn once and placed on every page
+        let mut header_form = None;
+        let mut footer_form = None;
+        if page_count > 1 && let Some(doc) = &mut self.inner {
+            if let Some(form) = header_node.as_ref().and_then(|h| CoreForm::from_static_layout(h.as_ref(), content_width, header_height, &font.inner, font_index, page_count)) {
+                header_form = Some(doc.add_form(&form));
+            }
+            if let Some(form) = footer_node.as_ref().and_then(|f| CoreForm::from_static_layout(f.as_ref(), content_width, footer_height, &font.inner, font_index, page_count)) {
+                footer_form = Some(doc.add_form(&form));
+            }
+        }
+
         // === PASS 2: Real Render with Accurate Context ===
         let mut current_page = 1;
         let mut current_node = Some(node.inner.clone());
@@ -624,20 +1468,24 @@ impl Document {
              };
              
              // 1. Render Header with context
-             if let Some(h) = &header_node {
+             if let Some(index) = header_form {
+                 page.inner.draw_form(index, margin_left, height - margin_top - header_height);
+             } else if let Some(h) = &header_node {
                  let header_area = CoreRect { x: margin_left, y: height - margin_top, width: content_width, height: header_height };
                  h.render(&mut page.inner, header_area, &font.inner, font_index, &context);
              }
 
              // 2. Render Footer at very bottom with context
-             if let Some(f) = &footer_node {
+             if let Some(index) = footer_form {
+                 page.inner.draw_form(index, margin_left, margin_bottom - footer_height);
+             } else if let Some(f) = &footer_node {
                  let footer_y = margin_bottom;
                  let footer_area = CoreRect { x: margin_left, y: footer_y, width: content_width, height: footer_height };
                  f.render(&mut page.inner, footer_area, &font.inner, font_index, &context);
              }
              
              // 3. Render Body with side margins
-             match node.split(content_width, body_available_height, &font.inner) {
+             match node.split_at_page_top(content_width, body_available_height, &font.inner) {
                  SplitAction::Fit => {
                       page.render_layout(&LayoutNode { inner: node }, margin_left, body_start_y, content_width, font, font_index, Some(current_page as u32), Some(page_count as u32));
                       self.add_page(&page)?;
@@ -1,10 +1,17 @@
 use wasm_bindgen::prelude::*;
-use crate::core::font::Font as CoreFont;
+use crate::core::font::{Font as CoreFont, FontSet as CoreFontSet};
 use crate::core::page::Page as CorePage;
+use crate::core::gradient::Gradient as CoreGradient;
+use crate::core::transform::Matrix as CoreMatrix;
+use crate::core::path::{Path as CorePath, PathStyle as CorePathStyle};
 use crate::core::document::Document as CoreDocument;
 use crate::core::image::Image as CoreImage;
+use crate::core::form::Form as CoreForm;
+use crate::core::watermark::Watermark as CoreWatermark;
+use crate::core::layer::Layer as CoreLayer;
 use crate::core::template::Template as CoreTemplate;
-use crate::core::layout::{LayoutNode as CoreLayoutNode};
+use crate::core::parse::ParseValueError;
+use crate::core::layout::{LayoutNode as CoreLayoutNode, RichTextNode as CoreRichTextNode, TextSpan as CoreTextSpan};
 use std::sync::Arc;
 
 // WASM-specific error handling
@@ -29,6 +36,26 @@ impl WasmFont {
     }
 }
 
+/// Fonts rich text spans can pick from by family and weight
+#[wasm_bindgen]
+pub struct WasmFontSet {
+    inner: CoreFontSet,
+}
+
+#[wasm_bindgen]
+impl WasmFontSet {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmFontSet {
+        WasmFontSet { inner: CoreFontSet::new() }
+    }
+
+    /// Register a font added to the document at `font_index`
+    #[wasm_bindgen]
+    pub fn add(&mut self, font: &WasmFont, font_index: u32) {
+        self.inner.add(&font.inner, font_index);
+    }
+}
+
 // Re-export core types wrapped in WASM-friendly structs
 
 #[wasm_bindgen]
@@ -87,6 +114,8 @@ impl WasmTemplate {
         
         // Reset indices to ensure they match this document instance
         self.inner.asset_indices.clear();
+        self.inner.layer_indices.clear();
+        self.inner.fonts = CoreFontSet::new();
         
         let mut font_found = false;
         
@@ -102,16 +131,21 @@ impl WasmTemplate {
         for (name, bytes) in &self.inner.assets {
             if name.ends_with(".ttf") || name.ends_with(".otf") {
                 fonts_to_add.push((name.clone(), bytes.clone()));
-            } else if name.ends_with(".png") || name.ends_with(".jpg") || name.ends_with(".jpeg") {
+            } else if name.ends_with(".png") || name.ends_with(".jpg") || name.ends_with(".jpeg") || name.ends_with(".svg") {
                 images_to_add.push((name.clone(), bytes.clone()));
+            } else if name.ends_with(".icc") || name.ends_with(".icm") {
+                doc.add_icc_profile(name, bytes.clone());
             }
         }
         
         // Add fonts
+        let mut svg_fonts = Vec::new();
         for (name, bytes) in fonts_to_add {
              if let Ok(font) = CoreFont::from_bytes(bytes, name.clone()) {
                  let idx = doc.add_font(&font);
+                 svg_fonts.push(font.clone());
                  self.inner.asset_indices.insert(name.clone(), idx);
+                 self.inner.fonts.add(&font, idx);
                  
                  // Use the first font found as the main font
                  if !font_found {
@@ -124,7 +158,12 @@ impl WasmTemplate {
         
         // Add images
         for (name, bytes) in images_to_add {
-            if let Ok(image) = CoreImage::from_bytes(&bytes) {
+            let image = if name.ends_with(".svg") {
+                CoreImage::from_svg(&bytes, &svg_fonts)
+            } else {
+                CoreImage::from_bytes(&bytes)
+            };
+            if let Ok(image) = image {
                 if let Ok(idx) = doc.add_image(&image) {
                      self.inner.asset_indices.insert(name.clone(), idx);
                 }
@@ -146,6 +185,16 @@ impl WasmTemplate {
         }
         
         let font = font_to_use.ok_or_else(|| JsValue::from_str("No accessible font found"))?;
+
+        // Nodes name their layer, so layers are registered like assets, by their own names
+        for layer in self.inner.layers() {
+            let idx = doc.add_layer(&layer)
+                .map_err(|e| JsValue::from_str(&format!("Failed to add layer: {}", e)))?;
+            self.inner.layer_indices.insert(layer.name, idx);
+        }
+        for watermark in self.inner.watermarks() {
+            doc.add_watermark(watermark);
+        }
         
         // 2. Render Layout
         // CoreTemplate::render uses self.asset_indices which we just updated
@@ -191,7 +240,7 @@ impl WasmTemplate {
             page_count += 1;
             let node = current_node_p1.unwrap();
             
-            match node.split(content_width, body_available_height, &font) {
+            match node.split_at_page_top(content_width, body_available_height, &font) {
                 crate::core::layout::SplitAction::Fit | crate::core::layout::SplitAction::Push => {
                     current_node_p1 = None;
                 },
@@ -222,7 +271,7 @@ impl WasmTemplate {
                 height: body_available_height,
             };
             
-            match node.clone().split(content_width, body_available_height, &font) {
+            match node.clone().split_at_page_top(content_width, body_available_height, &font) {
                 crate::core::layout::SplitAction::Fit | crate::core::layout::SplitAction::Push => {
                     node.render(&mut page, body_area, &font, font_index, &context);
                     doc.add_page(&page).map_err(|e| JsValue::from_str(&e.to_string()))?;
@@ -251,6 +300,36 @@ pub struct WasmLayoutNode {
     inner: Arc<dyn CoreLayoutNode>,
 }
 
+#[wasm_bindgen]
+impl WasmLayoutNode {
+    /// Create a rich text paragraph from a JSON array of spans
+    /// (`text`, `font_family`, `weight`, `italic`, `size`, `color`, `underline`,
+    /// `overline`, `line_through`, `highlight`, `decoration_color`, `skip_ink`, `link`)
+    #[wasm_bindgen]
+    pub fn rich_text(spans_json: &str, size: f64, fonts: &WasmFontSet) -> Result<WasmLayoutNode, JsValue> {
+        let spans: Vec<CoreTextSpan> = serde_json::from_str(spans_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid spans JSON: {}", e)))?;
+
+        Ok(WasmLayoutNode {
+            inner: Arc::new(CoreRichTextNode {
+                spans,
+                size,
+                color: None,
+                fonts: fonts.inner.clone(),
+                align: None,
+                white_space: Default::default(),
+                hyphenation: None,
+                line_height: Default::default(),
+                letter_spacing: 0.0,
+                word_spacing: 0.0,
+                orphans: 2,
+                widows: 2,
+                continues: false,
+            }),
+        })
+    }
+}
+
 #[wasm_bindgen]
 pub struct WasmDocument {
     inner: CoreDocument,
@@ -274,6 +353,39 @@ impl WasmDocument {
     pub fn add_font(&mut self, font: &WasmFont) -> u32 {
         self.inner.add_font(&font.inner)
     }
+
+    /// Register everything drawn on `page` as a form, drawn by reference with `draw_form`
+    #[wasm_bindgen]
+    pub fn add_form(&mut self, page: &WasmPage) -> u32 {
+        self.inner.add_form(&CoreForm::from_page(&page.inner))
+    }
+
+    /// Register a layer (optional content group) from JSON with `name`, and optionally
+    /// `visible`, `print`, `view` and `group`. Returns the index for `begin_layer`.
+    #[wasm_bindgen]
+    pub fn add_layer(&mut self, layer_json: &str) -> Result<u32, JsValue> {
+        let layer: CoreLayer = serde_json::from_str(layer_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid layer JSON: {}", e)))?;
+        self.inner.add_layer(&layer)
+            .map_err(|e| JsValue::from_str(&format!("Failed to add layer: {}", e)))
+    }
+
+    /// Draw a watermark or background on every page added from now on that it applies to.
+    /// `watermark_json` holds `type` ("text", "image" or "color") with its fields, and
+    /// optionally `opacity`, `rotation`, `position`, `layer`, `margin` and `pages`.
+    #[wasm_bindgen]
+    pub fn add_watermark(&mut self, watermark_json: &str) -> Result<(), JsValue> {
+        let watermark: CoreWatermark = serde_json::from_str(watermark_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid watermark JSON: {}", e)))?;
+        self.inner.add_watermark(watermark);
+        Ok(())
+    }
+
+    /// Register an ICC profile for colors given as `{"icc": name, "components": [...]}`
+    #[wasm_bindgen]
+    pub fn add_icc_profile(&mut self, name: &str, data: Vec<u8>) {
+        self.inner.add_icc_profile(name, data);
+    }
     
     #[wasm_bindgen]
     pub fn save(&self) -> Result<Vec<u8>, JsValue> {
@@ -299,6 +411,82 @@ impl WasmPage {
         }
     }
     
+    /// Opacity (0-1) of everything drawn next; color alphas multiply with it
+    #[wasm_bindgen]
+    pub fn set_opacity(&mut self, fill: f64, stroke: f64) {
+        self.inner.set_opacity(fill, stroke);
+    }
+
+    /// Blend mode of everything drawn next, e.g. "multiply" or "screen"
+    #[wasm_bindgen]
+    pub fn set_blend_mode(&mut self, mode: &str) -> Result<(), JsValue> {
+        let mode = mode.parse().map_err(|e: ParseValueError| JsValue::from_str(&e.to_string()))?;
+        self.inner.set_blend_mode(mode);
+        Ok(())
+    }
+
+    /// Draw a path. `style_json` holds `fill`, `stroke`, `stroke_width`, `fill_rule`,
+    /// `dash`, `dash_phase`, `line_cap`, `line_join` and `miter_limit`.
+    #[wasm_bindgen]
+    pub fn draw_path(&mut self, path: &WasmPath, style_json: &str) -> Result<(), JsValue> {
+        let style: CorePathStyle = serde_json::from_str(style_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid path style JSON: {}", e)))?;
+        self.inner.draw_path(&path.inner, &style);
+        Ok(())
+    }
+
+    /// Transform everything drawn until the matching `pop_transform` by the matrix [a b c d e f]
+    #[wasm_bindgen]
+    pub fn push_transform(&mut self, a: f64, b: f64, c: f64, d: f64, e: f64, f: f64) {
+        self.inner.push_transform(&CoreMatrix { a, b, c, d, e, f });
+    }
+
+    #[wasm_bindgen]
+    pub fn pop_transform(&mut self) {
+        self.inner.pop_transform();
+    }
+
+    /// Clip everything drawn until the matching `pop_clip` to a path
+    #[wasm_bindgen]
+    pub fn push_clip(&mut self, path: &WasmPath, fill_rule: &str) -> Result<(), JsValue> {
+        let rule = fill_rule.parse().map_err(|e: ParseValueError| JsValue::from_str(&e.to_string()))?;
+        self.inner.push_clip(&path.inner, rule);
+        Ok(())
+    }
+
+    #[wasm_bindgen]
+    pub fn pop_clip(&mut self) {
+        self.inner.pop_clip();
+    }
+
+    /// Fill a path with a gradient. `gradient_json` is `{"type": "linear", "angle": 90, "stops": [...]}`
+    /// or `{"type": "radial", "center_x": 0.5, "center_y": 0.5, "stops": [...]}`.
+    #[wasm_bindgen]
+    pub fn fill_gradient(&mut self, path: &WasmPath, gradient_json: &str, fill_rule: &str) -> Result<(), JsValue> {
+        let gradient: CoreGradient = serde_json::from_str(gradient_json)
+            .map_err(|e| JsValue::from_str(&format!("Invalid gradient JSON: {}", e)))?;
+        let rule = fill_rule.parse().map_err(|e: ParseValueError| JsValue::from_str(&e.to_string()))?;
+        self.inner.fill_gradient(&path.inner, &gradient, rule);
+        Ok(())
+    }
+
+    /// Draw a form registered with `add_form` at its own size, bottom-left corner at (x, y)
+    #[wasm_bindgen]
+    pub fn draw_form(&mut self, form_index: u32, x: f64, y: f64) {
+        self.inner.draw_form(form_index, x, y);
+    }
+
+    /// Put everything drawn until the matching `end_layer` on a layer from `add_layer`
+    #[wasm_bindgen]
+    pub fn begin_layer(&mut self, layer_index: u32) {
+        self.inner.begin_layer(layer_index);
+    }
+
+    #[wasm_bindgen]
+    pub fn end_layer(&mut self) {
+        self.inner.end_layer();
+    }
+
     #[wasm_bindgen]
     pub fn render_layout(&mut self, node: &WasmLayoutNode, font: &WasmFont, font_index: u32) {
         // Draw a test rectangle (FILLED BLACK) to verify rendering
@@ -363,3 +551,60 @@ impl WasmPage {
         node.inner.render(&mut self.inner, area, &font.inner, font_index, &context);
     }
 }
+
+/// Vector path builder for `WasmPage.draw_path`. Coordinates are PDF points, y up.
+#[wasm_bindgen]
+pub struct WasmPath {
+    inner: CorePath,
+}
+
+#[wasm_bindgen]
+impl WasmPath {
+    #[wasm_bindgen(constructor)]
+    pub fn new() -> WasmPath {
+        WasmPath { inner: CorePath::new() }
+    }
+
+    pub fn move_to(&mut self, x: f64, y: f64) {
+        self.inner.move_to(x, y);
+    }
+
+    pub fn line_to(&mut self, x: f64, y: f64) {
+        self.inner.line_to(x, y);
+    }
+
+    pub fn cubic_to(&mut self, x1: f64, y1: f64, x2: f64, y2: f64, x: f64, y: f64) {
+        self.inner.cubic_to(x1, y1, x2, y2, x, y);
+    }
+
+    pub fn quad_to(&mut self, cx: f64, cy: f64, x: f64, y: f64) {
+        self.inner.quad_to(cx, cy, x, y);
+    }
+
+    /// Angles in degrees counterclockwise from the x axis
+    pub fn arc(&mut self, cx: f64, cy: f64, radius: f64, start_angle: f64, end_angle: f64) {
+        self.inner.arc(cx, cy, radius, start_angle, end_angle);
+    }
+
+    pub fn circle(&mut self, cx: f64, cy: f64, radius: f64) {
+        self.inner.circle(cx, cy, radius);
+    }
+
+    pub fn ellipse(&mut self, cx: f64, cy: f64, rx: f64, ry: f64) {
+        self.inner.ellipse(cx, cy, rx, ry);
+    }
+
+    pub fn rect(&mut self, x: f64, y: f64, width: f64, height: f64, radius: f64) {
+        self.inner.rounded_rect(x, y, width, height, radius);
+    }
+
+    /// Closed polygon through flat `[x0, y0, x1, y1, ...]` coordinates
+    pub fn polygon(&mut self, points: &[f64]) {
+        let points: Vec<(f64, f64)> = points.chunks_exact(2).map(|p| (p[0], p[1])).collect();
+        self.inner.polygon(&points);
+    }
+
+    pub fn close(&mut self) {
+        self.inner.close();
+    }
+}